@@ -10,7 +10,7 @@ use ra_syntax::{
 };
 use ra_text_edit::TextEditBuilder;
 
-use crate::{AssistAction, AssistId, AssistLabel, GroupLabel, ResolvedAssist};
+use crate::{AssistAction, AssistId, AssistLabel, AssistOptions, GroupLabel, ResolvedAssist};
 
 #[derive(Clone, Debug)]
 pub(crate) struct Assist(pub(crate) Vec<AssistInfo>);
@@ -71,26 +71,27 @@ pub(crate) type AssistHandler = fn(AssistCtx) -> Option<Assist>;
 /// with `should_compute_edit = false`, and then applying the selected edit
 /// again, with `should_compute_edit = true` this time.
 ///
-/// Note, however, that we don't actually use such two-phase logic at the
-/// moment, because the LSP API is pretty awkward in this place, and it's much
-/// easier to just compute the edit eagerly :-)
+/// The LSP server uses this two-phase logic for clients which support lazy
+/// code action resolution, and computes all the edits eagerly otherwise.
 #[derive(Clone)]
 pub(crate) struct AssistCtx<'a> {
     pub(crate) sema: &'a Semantics<'a, RootDatabase>,
     pub(crate) db: &'a RootDatabase,
     pub(crate) frange: FileRange,
     source_file: SourceFile,
+    options: &'a AssistOptions,
     should_compute_edit: bool,
 }
 
 impl<'a> AssistCtx<'a> {
     pub fn new(
         sema: &'a Semantics<'a, RootDatabase>,
+        options: &'a AssistOptions,
         frange: FileRange,
         should_compute_edit: bool,
     ) -> AssistCtx<'a> {
         let source_file = sema.parse(frange.file_id);
-        AssistCtx { sema, db: sema.db, frange, source_file, should_compute_edit }
+        AssistCtx { sema, db: sema.db, frange, source_file, options, should_compute_edit }
     }
 
     pub(crate) fn add_assist(
@@ -104,7 +105,7 @@ impl<'a> AssistCtx<'a> {
         let mut info = AssistInfo::new(label);
         if self.should_compute_edit {
             let action = {
-                let mut edit = ActionBuilder::default();
+                let mut edit = ActionBuilder::new(self.options);
                 f(&mut edit);
                 edit.build()
             };
@@ -155,7 +156,7 @@ impl<'a> AssistGroup<'a> {
         let mut info = AssistInfo::new(label).with_group(GroupLabel(self.group_name.clone()));
         if self.ctx.should_compute_edit {
             let action = {
-                let mut edit = ActionBuilder::default();
+                let mut edit = ActionBuilder::new(self.ctx.options);
                 f(&mut edit);
                 edit.build()
             };
@@ -174,14 +175,27 @@ impl<'a> AssistGroup<'a> {
     }
 }
 
-#[derive(Default)]
 pub(crate) struct ActionBuilder {
     edit: TextEditBuilder,
     cursor_position: Option<TextUnit>,
     target: Option<TextRange>,
+    snippet_edits: bool,
+    /// Edits which were inserted as snippets, all other edits get their text
+    /// escaped when the action is built.
+    snippets: Vec<(TextRange, String)>,
 }
 
 impl ActionBuilder {
+    fn new(options: &AssistOptions) -> ActionBuilder {
+        ActionBuilder {
+            edit: TextEditBuilder::default(),
+            cursor_position: None,
+            target: None,
+            snippet_edits: options.snippet_edits,
+            snippets: Vec::new(),
+        }
+    }
+
     /// Replaces specified `range` of text with a given string.
     pub(crate) fn replace(&mut self, range: TextRange, replace_with: impl Into<String>) {
         self.edit.replace(range, replace_with.into())
@@ -213,6 +227,29 @@ impl ActionBuilder {
         self.edit.insert(offset, text.into())
     }
 
+    /// Inserts a snippet with tab stops (`$0`, `${1:placeholder}`) at `offset`.
+    ///
+    /// If the client can't apply snippets, the tab stops are stripped and the
+    /// cursor is placed at `$0` instead, as if by `set_cursor`.
+    pub(crate) fn insert_snippet(&mut self, offset: TextUnit, snippet: impl Into<String>) {
+        self.replace_snippet(TextRange::offset_len(offset, 0.into()), snippet)
+    }
+
+    /// Replaces specified `range` of text with a snippet, see `insert_snippet`.
+    pub(crate) fn replace_snippet(&mut self, range: TextRange, snippet: impl Into<String>) {
+        let snippet = snippet.into();
+        if self.snippet_edits {
+            self.snippets.push((range, snippet.clone()));
+            self.replace(range, snippet);
+        } else {
+            let (text, cursor) = render_snippet(&snippet);
+            if let Some(cursor) = cursor {
+                self.set_cursor(range.start() + cursor);
+            }
+            self.replace(range, text);
+        }
+    }
+
     /// Specify desired position of the cursor after the assist is applied.
     pub(crate) fn set_cursor(&mut self, offset: TextUnit) {
         self.cursor_position = Some(offset)
@@ -236,10 +273,111 @@ impl ActionBuilder {
     }
 
     fn build(self) -> AssistAction {
+        let edit = self.edit.finish();
+        let is_snippet = !self.snippets.is_empty();
+        let edit = if is_snippet {
+            let mut builder = TextEditBuilder::default();
+            for atom in edit.as_atoms() {
+                let is_snippet_atom = self
+                    .snippets
+                    .iter()
+                    .any(|(range, text)| *range == atom.delete && *text == atom.insert);
+                let insert = if is_snippet_atom {
+                    atom.insert.clone()
+                } else {
+                    escape_snippet(&atom.insert)
+                };
+                builder.replace(atom.delete, insert);
+            }
+            builder.finish()
+        } else {
+            edit
+        };
         AssistAction {
-            edit: self.edit.finish(),
+            edit,
             cursor_position: self.cursor_position,
             target: self.target,
+            is_snippet,
         }
     }
 }
+
+/// Escapes characters which have a special meaning in snippets.
+fn escape_snippet(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '$' | '}' => res.push('\\'),
+            _ => (),
+        }
+        res.push(c);
+    }
+    res
+}
+
+/// Turns a snippet into plain text, keeping placeholders and dropping tab
+/// stops. Returns the text and the offset of the final tab stop `$0`.
+fn render_snippet(snippet: &str) -> (String, Option<TextUnit>) {
+    let mut text = String::with_capacity(snippet.len());
+    let mut cursor = None;
+    let mut chars = snippet.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            '$' => {
+                let braced = chars.peek() == Some(&'{');
+                if braced {
+                    chars.next();
+                }
+                let mut idx = String::new();
+                while let Some(&d) = chars.peek() {
+                    if !d.is_ascii_digit() {
+                        break;
+                    }
+                    idx.push(d);
+                    chars.next();
+                }
+                if idx.is_empty() {
+                    text.push('$');
+                    if braced {
+                        text.push('{');
+                    }
+                    continue;
+                }
+                if idx == "0" && cursor.is_none() {
+                    cursor = Some(TextUnit::of_str(&text));
+                }
+                if braced {
+                    if chars.peek() == Some(&':') {
+                        chars.next();
+                    }
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '}' => break,
+                            '\\' => text.extend(chars.next()),
+                            c => text.push(c),
+                        }
+                    }
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    (text, cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_snippet;
+
+    #[test]
+    fn renders_snippets() {
+        assert_eq!(render_snippet("foo"), ("foo".to_string(), None));
+        assert_eq!(render_snippet("#[derive($0)]"), ("#[derive()]".to_string(), Some(9.into())));
+        assert_eq!(
+            render_snippet("fn ${1:foo}() { $0 }"),
+            ("fn foo() {  }".to_string(), Some(11.into()))
+        );
+        assert_eq!(render_snippet("\\$1 costs $"), ("$1 costs $".to_string(), None));
+    }
+}
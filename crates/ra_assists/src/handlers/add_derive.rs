@@ -34,15 +34,14 @@ pub(crate) fn add_derive(ctx: AssistCtx) -> Option<Assist> {
             .filter(|(name, _arg)| name == "derive")
             .map(|(_name, arg)| arg)
             .next();
-        let offset = match derive_attr {
-            None => {
-                edit.insert(node_start, "#[derive()]\n");
-                node_start + TextUnit::of_str("#[derive(")
+        match derive_attr {
+            None => edit.insert_snippet(node_start, "#[derive($0)]\n"),
+            Some(tt) => {
+                let offset = tt.syntax().text_range().end() - TextUnit::of_char(')');
+                edit.insert_snippet(offset, "$0")
             }
-            Some(tt) => tt.syntax().text_range().end() - TextUnit::of_char(')'),
         };
         edit.target(nominal.syntax().text_range());
-        edit.set_cursor(offset)
     })
 }
 
@@ -58,7 +57,7 @@ fn derive_insertion_offset(nominal: &ast::NominalDef) -> Option<TextUnit> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::helpers::{check_assist, check_assist_target};
+    use crate::helpers::{check_assist, check_assist_snippet, check_assist_target};
 
     #[test]
     fn add_derive_new() {
@@ -83,6 +82,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn add_derive_snippet() {
+        check_assist_snippet(
+            add_derive,
+            "struct Foo { a: i32, <|>}",
+            "#[derive($0)]\nstruct Foo { a: i32, }",
+        );
+        check_assist_snippet(
+            add_derive,
+            "#[derive(Clone)]\nstruct Foo { a: i32<|>, }",
+            "#[derive(Clone$0)]\nstruct Foo { a: i32, }",
+        );
+    }
+
     #[test]
     fn add_derive_new_with_doc_comment() {
         check_assist(
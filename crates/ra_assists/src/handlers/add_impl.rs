@@ -1,10 +1,7 @@
 use format_buf::format;
 
 use join_to_string::join;
-use ra_syntax::{
-    ast::{self, AstNode, NameOwner, TypeParamsOwner},
-    TextUnit,
-};
+use ra_syntax::ast::{self, AstNode, NameOwner, TypeParamsOwner};
 
 use crate::{Assist, AssistCtx, AssistId};
 
@@ -50,17 +47,15 @@ pub(crate) fn add_impl(ctx: AssistCtx) -> Option<Assist> {
                 type_params.type_params().filter_map(|it| it.name()).map(|it| it.text().clone());
             join(lifetime_params.chain(type_params)).surround_with("<", ">").to_buf(&mut buf);
         }
-        buf.push_str(" {\n");
-        edit.set_cursor(start_offset + TextUnit::of_str(&buf));
-        buf.push_str("\n}");
-        edit.insert(start_offset, buf);
+        buf.push_str(" {\n$0\n}");
+        edit.insert_snippet(start_offset, buf);
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::helpers::{check_assist, check_assist_target};
+    use crate::helpers::{check_assist, check_assist_snippet, check_assist_target};
 
     #[test]
     fn test_add_impl() {
@@ -77,6 +72,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn add_impl_snippet() {
+        check_assist_snippet(
+            add_impl,
+            "struct Foo {<|>}\n",
+            "struct Foo {}\n\nimpl Foo {\n$0\n}\n",
+        );
+    }
+
     #[test]
     fn add_impl_target() {
         check_assist_target(
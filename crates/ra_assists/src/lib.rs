@@ -35,6 +35,13 @@ pub struct AssistLabel {
 #[derive(Clone, Debug)]
 pub struct GroupLabel(pub String);
 
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AssistOptions {
+    /// Whether the client can apply edits containing snippet tab stops, see
+    /// `AssistAction::is_snippet`.
+    pub snippet_edits: bool,
+}
+
 impl AssistLabel {
     pub(crate) fn new(label: String, id: AssistId) -> AssistLabel {
         // FIXME: make fields private, so that this invariant can't be broken
@@ -49,6 +56,15 @@ pub struct AssistAction {
     pub cursor_position: Option<TextUnit>,
     // FIXME: This belongs to `AssistLabel`
     pub target: Option<TextRange>,
+    /// Whether the inserted text uses snippet syntax, with `$0` and
+    /// `${1:placeholder}` tab stops, and with backslash-escaped `$`, `}` and `\`.
+    pub is_snippet: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnresolvedAssist {
+    pub label: AssistLabel,
+    pub group_label: Option<GroupLabel>,
 }
 
 #[derive(Debug, Clone)]
@@ -62,14 +78,18 @@ pub struct ResolvedAssist {
 ///
 /// Assists are returned in the "unresolved" state, that is only labels are
 /// returned, without actual edits.
-pub fn unresolved_assists(db: &RootDatabase, range: FileRange) -> Vec<AssistLabel> {
+pub fn unresolved_assists(
+    db: &RootDatabase,
+    options: &AssistOptions,
+    range: FileRange,
+) -> Vec<UnresolvedAssist> {
     let sema = Semantics::new(db);
-    let ctx = AssistCtx::new(&sema, range, false);
+    let ctx = AssistCtx::new(&sema, options, range, false);
     handlers::all()
         .iter()
         .filter_map(|f| f(ctx.clone()))
         .flat_map(|it| it.0)
-        .map(|a| a.label)
+        .map(|a| UnresolvedAssist { label: a.label, group_label: a.group_label })
         .collect()
 }
 
@@ -77,9 +97,13 @@ pub fn unresolved_assists(db: &RootDatabase, range: FileRange) -> Vec<AssistLabe
 ///
 /// Assists are returned in the "resolved" state, that is with edit fully
 /// computed.
-pub fn resolved_assists(db: &RootDatabase, range: FileRange) -> Vec<ResolvedAssist> {
+pub fn resolved_assists(
+    db: &RootDatabase,
+    options: &AssistOptions,
+    range: FileRange,
+) -> Vec<ResolvedAssist> {
     let sema = Semantics::new(db);
-    let ctx = AssistCtx::new(&sema, range, true);
+    let ctx = AssistCtx::new(&sema, options, range, true);
     let mut a = handlers::all()
         .iter()
         .filter_map(|f| f(ctx.clone()))
@@ -90,6 +114,31 @@ pub fn resolved_assists(db: &RootDatabase, range: FileRange) -> Vec<ResolvedAssi
     a
 }
 
+/// Computes the edit of a single assist, previously returned by
+/// `unresolved_assists` with the given `id` and `label`.
+///
+/// Only the handler which produced the assist computes its edits.
+pub fn resolve_assist(
+    db: &RootDatabase,
+    options: &AssistOptions,
+    range: FileRange,
+    id: &str,
+    label: &str,
+) -> Option<ResolvedAssist> {
+    let sema = Semantics::new(db);
+    let ctx = AssistCtx::new(&sema, options, range, false);
+    let handler = handlers::all().iter().find(|f| match f(ctx.clone()) {
+        Some(assist) => assist.0.iter().any(|it| it.label.id.0 == id && it.label.label == label),
+        None => false,
+    })?;
+    let ctx = AssistCtx::new(&sema, options, range, true);
+    handler(ctx)?
+        .0
+        .into_iter()
+        .find(|it| it.label.id.0 == id && it.label.label == label)
+        .and_then(|it| it.into_resolved())
+}
+
 mod handlers {
     use crate::AssistHandler;
 
@@ -168,7 +217,7 @@ mod helpers {
     use ra_syntax::TextRange;
     use test_utils::{add_cursor, assert_eq_text, extract_range_or_offset, RangeOrOffset};
 
-    use crate::{AssistCtx, AssistHandler};
+    use crate::{AssistCtx, AssistHandler, AssistOptions};
     use hir::Semantics;
 
     pub(crate) fn with_single_file(text: &str) -> (RootDatabase, FileId) {
@@ -199,6 +248,22 @@ mod helpers {
         check(assist, ra_fixture, ExpectedResult::NotApplicable);
     }
 
+    /// Like `check_assist`, but for a client which supports snippet edits, so
+    /// `ra_fixture_after` contains the snippet tab stops.
+    pub(crate) fn check_assist_snippet(
+        assist: AssistHandler,
+        ra_fixture_before: &str,
+        ra_fixture_after: &str,
+    ) {
+        let options = AssistOptions { snippet_edits: true };
+        check_with_options(
+            assist,
+            &options,
+            ra_fixture_before,
+            ExpectedResult::After(ra_fixture_after),
+        );
+    }
+
     enum ExpectedResult<'a> {
         NotApplicable,
         After(&'a str),
@@ -206,13 +271,22 @@ mod helpers {
     }
 
     fn check(assist: AssistHandler, before: &str, expected: ExpectedResult) {
+        check_with_options(assist, &AssistOptions::default(), before, expected)
+    }
+
+    fn check_with_options(
+        assist: AssistHandler,
+        options: &AssistOptions,
+        before: &str,
+        expected: ExpectedResult,
+    ) {
         let (range_or_offset, before) = extract_range_or_offset(before);
         let range: TextRange = range_or_offset.into();
 
         let (db, file_id) = with_single_file(&before);
         let frange = FileRange { file_id, range };
         let sema = Semantics::new(&db);
-        let assist_ctx = AssistCtx::new(&sema, frange, true);
+        let assist_ctx = AssistCtx::new(&sema, options, frange, true);
 
         match (assist(assist_ctx), expected) {
             (Some(assist), ExpectedResult::After(after)) => {
@@ -220,6 +294,8 @@ mod helpers {
 
                 let mut actual = action.edit.apply(&before);
                 match action.cursor_position {
+                    // Snippets carry their own tab stops instead of a cursor.
+                    None if action.is_snippet => (),
                     None => {
                         if let RangeOrOffset::Offset(before_cursor_pos) = range_or_offset {
                             let off = action
@@ -254,7 +330,7 @@ mod tests {
     use ra_syntax::TextRange;
     use test_utils::{extract_offset, extract_range};
 
-    use crate::{helpers, resolved_assists};
+    use crate::{helpers, resolve_assist, resolved_assists, unresolved_assists, AssistOptions};
 
     #[test]
     fn assist_order_field_struct() {
@@ -263,7 +339,7 @@ mod tests {
         let (db, file_id) = helpers::with_single_file(&before);
         let frange =
             FileRange { file_id, range: TextRange::offset_len(before_cursor_pos, 0.into()) };
-        let assists = resolved_assists(&db, frange);
+        let assists = resolved_assists(&db, &AssistOptions::default(), frange);
         let mut assists = assists.iter();
 
         assert_eq!(
@@ -286,10 +362,32 @@ mod tests {
         let (range, before) = extract_range(before);
         let (db, file_id) = helpers::with_single_file(&before);
         let frange = FileRange { file_id, range };
-        let assists = resolved_assists(&db, frange);
+        let assists = resolved_assists(&db, &AssistOptions::default(), frange);
         let mut assists = assists.iter();
 
         assert_eq!(assists.next().expect("expected assist").label.label, "Extract into variable");
         assert_eq!(assists.next().expect("expected assist").label.label, "Replace with match");
     }
+
+    #[test]
+    fn resolves_single_assist() {
+        let before = "struct Foo { <|>bar: u32 }";
+        let (before_cursor_pos, before) = extract_offset(before);
+        let (db, file_id) = helpers::with_single_file(&before);
+        let frange =
+            FileRange { file_id, range: TextRange::offset_len(before_cursor_pos, 0.into()) };
+        let options = AssistOptions::default();
+
+        let unresolved = unresolved_assists(&db, &options, frange);
+        let derive = unresolved
+            .iter()
+            .find(|it| it.label.id.0 == "add_derive")
+            .expect("expected add_derive assist");
+        let resolved =
+            resolve_assist(&db, &options, frange, derive.label.id.0, &derive.label.label)
+                .expect("expected assist to resolve");
+        assert_eq!(resolved.action.edit.apply(&before), "#[derive()]\nstruct Foo { bar: u32 }");
+
+        assert!(resolve_assist(&db, &options, frange, "add_derive", "Bogus label").is_none());
+    }
 }
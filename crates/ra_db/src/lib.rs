@@ -78,7 +78,7 @@ pub struct FilePosition {
     pub offset: TextUnit,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct FileRange {
     pub file_id: FileId,
     pub range: TextRange,
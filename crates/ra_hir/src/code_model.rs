@@ -8,7 +8,7 @@ use hir_def::{
     adt::VariantData,
     builtin_type::BuiltinType,
     docs::Documentation,
-    expr::{BindingAnnotation, Pat, PatId},
+    expr::{BindingAnnotation, Expr, Pat, PatId},
     per_ns::PerNs,
     resolver::HasResolver,
     type_ref::{Mutability, TypeRef},
@@ -23,7 +23,8 @@ use hir_expand::{
 };
 use hir_ty::{
     autoderef, display::HirFormatter, expr::ExprValidator, method_resolution, ApplicationTy,
-    Canonical, InEnvironment, Substs, TraitEnvironment, Ty, TyDefId, TypeCtor,
+    Canonical, FnSig, GenericPredicate, InEnvironment, Substs, TraitEnvironment, Ty, TyDefId,
+    TypeCtor,
 };
 use ra_db::{CrateId, Edition, FileId};
 use ra_prof::profile;
@@ -369,6 +370,10 @@ impl Struct {
         Type::from_def(db, self.id.lookup(db.upcast()).container.module(db.upcast()).krate, self.id)
     }
 
+    pub fn kind(self, db: &dyn HirDatabase) -> StructKind {
+        self.variant_data(db).kind()
+    }
+
     fn variant_data(self, db: &dyn HirDatabase) -> Arc<VariantData> {
         db.struct_data(self.id).variant_data.clone()
     }
@@ -584,6 +589,69 @@ impl DefWithBody {
             DefWithBody::Const(c) => c.name(db),
         }
     }
+
+    /// Returns the overloaded operator expressions of the body (`a + b`,
+    /// `a?`, ...) which call `func`, either through the trait method they
+    /// desugar to or through the method of the impl it resolves to.
+    pub fn operator_calls(self, db: &dyn HirDatabase, func: Function) -> Vec<InFile<ast::Expr>> {
+        let _p = profile("DefWithBody::operator_calls");
+        let infer = db.infer(self.into());
+        // Operators only resolve to an impl method through the trait it implements
+        let impl_trait = match func.id.lookup(db.upcast()).container {
+            AssocContainerId::ImplId(it) => db.impl_trait(it).map(|it| it.value.trait_),
+            _ => None,
+        };
+        let krate = self.module(db).id.krate;
+        let mut exprs = infer
+            .operator_resolutions()
+            .filter(|(_, (method, substs))| {
+                if *method == func.id {
+                    return true;
+                }
+                let trait_ = match impl_trait {
+                    Some(it) => it,
+                    None => return false,
+                };
+                let container = method.lookup(db.upcast()).container;
+                if container != AssocContainerId::TraitId(trait_)
+                    || db.function_data(*method).name != db.function_data(func.id).name
+                {
+                    return false;
+                }
+                method_resolution::lookup_impl_method(db, krate, *method, substs) == func.id
+            })
+            .map(|(expr_id, _)| expr_id)
+            .peekable();
+        if exprs.peek().is_none() {
+            return Vec::new();
+        }
+        let (_, source_map) = db.body_with_source_map(self.into());
+        exprs
+            .filter_map(|expr_id| {
+                let src = source_map.expr_syntax(expr_id).ok()?;
+                let root = src.file_syntax(db.upcast());
+                src.map(|ptr| ptr.left().map(|it| it.to_node(&root))).transpose()
+            })
+            .collect()
+    }
+
+    /// Returns the `return`, `?` and `.await` expressions of the body,
+    /// including the ones produced by macro calls.
+    pub fn exit_and_await_exprs(self, db: &dyn HirDatabase) -> Vec<InFile<ast::Expr>> {
+        let (body, source_map) = db.body_with_source_map(self.into());
+        body.exprs
+            .iter()
+            .filter(|(_, expr)| match expr {
+                Expr::Return { .. } | Expr::Try { .. } | Expr::Await { .. } => true,
+                _ => false,
+            })
+            .filter_map(|(expr_id, _)| {
+                let src = source_map.expr_syntax(expr_id).ok()?;
+                let root = src.file_syntax(db.upcast());
+                src.map(|ptr| ptr.left().map(|it| it.to_node(&root))).transpose()
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -612,6 +680,38 @@ impl Function {
         db.function_data(self.id).params.clone()
     }
 
+    /// Returns the parameter types (including `self`) as in the signature of
+    /// the function (i.e., with placeholder types for type parameters).
+    pub fn param_types(self, db: &dyn HirDatabase) -> Vec<Type> {
+        let krate = self.module(db).id.krate;
+        let sig = self.signature(db);
+        sig.params().iter().map(|ty| Type::new(db, krate, self.id, ty.clone())).collect()
+    }
+
+    /// Returns the return type as in the signature of the function (i.e., with
+    /// placeholder types for type parameters).
+    pub fn ret_type(self, db: &dyn HirDatabase) -> Type {
+        let sig = self.signature(db);
+        Type::new(db, self.module(db).id.krate, self.id, sig.ret().clone())
+    }
+
+    /// Whether operator expressions (`a + b`, `a?`, ...) may call this
+    /// function, as it belongs to or implements an operator trait.
+    pub fn is_operator_method(self, db: &dyn HirDatabase) -> bool {
+        method_resolution::is_operator_method(db, self.id)
+    }
+
+    /// Returns the text of the tokens of the operators which may call this
+    /// function, e.g. `==` and `!=` for `PartialEq::eq`.
+    pub fn operator_tokens(self, db: &dyn HirDatabase) -> &'static [&'static str] {
+        method_resolution::operator_tokens(db, self.id)
+    }
+
+    fn signature(self, db: &dyn HirDatabase) -> FnSig {
+        let substs = Substs::type_params(db, self.id);
+        db.callable_item_signature(self.id.into()).subst(&substs)
+    }
+
     pub fn diagnostics(self, db: &dyn HirDatabase, sink: &mut DiagnosticSink) {
         let _p = profile("Function::diagnostics");
         let infer = db.infer(self.id.into());
@@ -696,6 +796,25 @@ impl Trait {
     pub fn is_auto(self, db: &dyn HirDatabase) -> bool {
         db.trait_data(self.id).auto
     }
+
+    /// Returns the direct supertraits, that is the bounds on `Self` from both
+    /// `trait Foo: Bar` and `where Self: Bar`.
+    pub fn super_traits(self, db: &dyn HirDatabase) -> Vec<Trait> {
+        let mut res = Vec::new();
+        for pred in db.generic_predicates(self.id.into()).iter() {
+            if let GenericPredicate::Implemented(trait_ref) = &pred.value {
+                // `Self` is the first generic parameter of a trait
+                if trait_ref.self_ty() != &Ty::Bound(0) || trait_ref.trait_ == self.id {
+                    continue;
+                }
+                let super_trait = Trait { id: trait_ref.trait_ };
+                if !res.contains(&super_trait) {
+                    res.push(super_trait);
+                }
+            }
+        }
+        res
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -964,6 +1083,12 @@ impl ImplDef {
         db.impl_data(self.id).target_trait.clone()
     }
 
+    /// Returns the implemented trait, if this is a trait impl which resolves.
+    pub fn target_trait_def(&self, db: &dyn HirDatabase) -> Option<Trait> {
+        let trait_ref = db.impl_trait(self.id)?;
+        Some(Trait { id: trait_ref.value.trait_ })
+    }
+
     pub fn target_type(&self, db: &dyn HirDatabase) -> TypeRef {
         db.impl_data(self.id).target_type.clone()
     }
@@ -1095,6 +1220,26 @@ impl Type {
         Some(self.ty.value.as_callable()?.0)
     }
 
+    /// Returns the parameter types if this is the type of a function, a
+    /// function pointer or a closure.
+    pub fn callable_params(&self, db: &dyn HirDatabase) -> Option<Vec<Type>> {
+        let sig = self.ty.value.callable_sig(db)?;
+        Some(sig.params().iter().map(|ty| self.derived(ty.clone())).collect())
+    }
+
+    /// Returns the return type if this is the type of a function, a function
+    /// pointer or a closure.
+    pub fn callable_ret(&self, db: &dyn HirDatabase) -> Option<Type> {
+        let sig = self.ty.value.callable_sig(db)?;
+        Some(self.derived(sig.ret().clone()))
+    }
+
+    /// Checks if both types are the same, ignoring the environments they were
+    /// created in. Types with unknown parts are never the same.
+    pub fn is_same_type(&self, other: &Type) -> bool {
+        !self.contains_unknown() && self.ty.value == other.ty.value
+    }
+
     pub fn contains_unknown(&self) -> bool {
         return go(&self.ty.value);
 
@@ -1107,6 +1252,27 @@ impl Type {
         }
     }
 
+    /// Returns the ADTs mentioned in this type, outermost first.
+    pub fn referenced_adts(&self) -> Vec<Adt> {
+        let mut res = Vec::new();
+        go(&self.ty.value, &mut res);
+        return res;
+
+        fn go(ty: &Ty, acc: &mut Vec<Adt>) {
+            if let Ty::Apply(a_ty) = ty {
+                if let TypeCtor::Adt(adt) = a_ty.ctor {
+                    let adt = adt.into();
+                    if !acc.contains(&adt) {
+                        acc.push(adt);
+                    }
+                }
+                for ty in a_ty.parameters.iter() {
+                    go(ty, acc);
+                }
+            }
+        }
+    }
+
     pub fn fields(&self, db: &dyn HirDatabase) -> Vec<(StructField, Type)> {
         if let Ty::Apply(a_ty) = &self.ty.value {
             if let TypeCtor::Adt(AdtId::StructId(s)) = a_ty.ctor {
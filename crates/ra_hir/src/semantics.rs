@@ -5,6 +5,8 @@ mod source_to_def;
 use std::{cell::RefCell, fmt, iter::successors};
 
 use hir_def::{
+    as_call_id_in_module,
+    path::ModPath,
     resolver::{self, HasResolver, Resolver},
     AsMacroCall, TraitId,
 };
@@ -79,8 +81,11 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         let macro_call =
             self.find_file(actual_macro_call.syntax().clone()).with_value(actual_macro_call);
         let sa = self.analyze2(macro_call.map(|it| it.syntax()), None);
-        let macro_call_id = macro_call
-            .as_call_id(self.db, |path| sa.resolver.resolve_path_as_macro(self.db, &path))?;
+        let resolver = |path: ModPath| sa.resolver.resolve_path_as_macro(self.db, &path);
+        let macro_call_id = match sa.resolver.module() {
+            Some(module) => as_call_id_in_module(self.db, macro_call, module, resolver)?,
+            None => macro_call.as_call_id(self.db, resolver)?,
+        };
         hir_expand::db::expand_hypothetical(self.db, macro_call_id, hypothetical_args, token_to_map)
     }
 
@@ -113,6 +118,22 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         original_range(self.db, node.as_ref())
     }
 
+    /// Maps a token of a macro expansion up to the token of the macro call
+    /// input it was produced from, through nested macro calls. Returns `None`
+    /// if the token was produced by the body of a macro instead.
+    pub fn original_token_range(&self, token: &SyntaxToken) -> Option<FileRange> {
+        let token = self.find_file(token.parent()).with_value(token.clone());
+        let token = match token.file_id.expansion_info(self.db) {
+            Some(info) => ascend_call_token(self.db, &info, token)?,
+            None => token,
+        };
+        let file_id = token.file_id.original_file(self.db);
+        if token.file_id != file_id.into() {
+            return None;
+        }
+        Some(FileRange { file_id, range: token.value.text_range() })
+    }
+
     pub fn ancestors_with_macros(&self, node: SyntaxNode) -> impl Iterator<Item = SyntaxNode> + '_ {
         let node = self.find_file(node);
         node.ancestors_with_macros(self.db).map(|it| it.value)
@@ -149,6 +170,19 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.analyze(call.syntax()).resolve_method_call(call)
     }
 
+    /// Resolves an overloaded operator expression (`a + b`, `*a`, `a[b]`,
+    /// `a?`, `a.await`) to the method of the impl it calls, or to the trait
+    /// method if the impl can't be determined.
+    pub fn resolve_operator(&self, expr: &ast::Expr) -> Option<Function> {
+        self.analyze(expr.syntax()).resolve_operator(self.db, expr)
+    }
+
+    /// Resolves an overloaded operator expression to the trait method it
+    /// desugars to, e.g. `Add::add` for `a + b`.
+    pub fn resolve_operator_trait_method(&self, expr: &ast::Expr) -> Option<Function> {
+        self.analyze(expr.syntax()).resolve_operator_trait_method(expr)
+    }
+
     pub fn resolve_field(&self, field: &ast::FieldExpr) -> Option<StructField> {
         self.analyze(field.syntax()).resolve_field(field)
     }
@@ -217,6 +251,11 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         SemanticsScope { db: self.db, resolver }
     }
 
+    pub fn scope_for_module(&self, module: Module) -> SemanticsScope<'db, DB> {
+        let resolver = module.id.resolver(self.db);
+        SemanticsScope { db: self.db, resolver }
+    }
+
     fn analyze(&self, node: &SyntaxNode) -> SourceAnalyzer {
         let src = self.find_file(node.clone());
         self.analyze2(src.as_ref(), None)
@@ -8,6 +8,7 @@
 use std::{iter::once, sync::Arc};
 
 use hir_def::{
+    as_call_id_in_module,
     body::{
         scope::{ExprScopes, ScopeId},
         Body, BodySourceMap,
@@ -17,7 +18,7 @@ use hir_def::{
     AsMacroCall, DefWithBodyId,
 };
 use hir_expand::{hygiene::Hygiene, name::AsName, HirFileId, InFile};
-use hir_ty::{InEnvironment, InferenceResult, TraitEnvironment};
+use hir_ty::{method_resolution, InEnvironment, InferenceResult, TraitEnvironment};
 use ra_syntax::{
     ast::{self, AstNode},
     SyntaxNode, SyntaxNodePtr, TextUnit,
@@ -133,6 +134,22 @@ impl SourceAnalyzer {
         self.infer.as_ref()?.method_resolution(expr_id).map(Function::from)
     }
 
+    pub(crate) fn resolve_operator(
+        &self,
+        db: &dyn HirDatabase,
+        expr: &ast::Expr,
+    ) -> Option<Function> {
+        let expr_id = self.expr_id(expr)?;
+        let (func, substs) = self.infer.as_ref()?.operator_resolution(expr_id)?;
+        let krate = self.resolver.krate()?;
+        Some(method_resolution::lookup_impl_method(db, krate, func, &substs).into())
+    }
+
+    pub(crate) fn resolve_operator_trait_method(&self, expr: &ast::Expr) -> Option<Function> {
+        let expr_id = self.expr_id(expr)?;
+        self.infer.as_ref()?.operator_resolution(expr_id).map(|(func, _)| func.into())
+    }
+
     pub(crate) fn resolve_field(&self, field: &ast::FieldExpr) -> Option<crate::StructField> {
         let expr_id = self.expr_id(&field.clone().into())?;
         self.infer.as_ref()?.field_resolution(expr_id).map(|it| it.into())
@@ -234,9 +251,11 @@ impl SourceAnalyzer {
         db: &dyn HirDatabase,
         macro_call: InFile<&ast::MacroCall>,
     ) -> Option<HirFileId> {
-        let macro_call_id = macro_call.as_call_id(db.upcast(), |path| {
-            self.resolver.resolve_path_as_macro(db.upcast(), &path)
-        })?;
+        let resolver = |path: ModPath| self.resolver.resolve_path_as_macro(db.upcast(), &path);
+        let macro_call_id = match self.resolver.module() {
+            Some(module) => as_call_id_in_module(db.upcast(), macro_call, module, resolver)?,
+            None => macro_call.as_call_id(db.upcast(), resolver)?,
+        };
         Some(macro_call_id.as_file())
     }
 }
@@ -457,17 +457,34 @@ impl AsMacroCall for AstIdWithPath<ast::MacroCall> {
         &self,
         db: &dyn db::DefDatabase,
         resolver: impl Fn(path::ModPath) -> Option<MacroDefId>,
+    ) -> Option<MacroCallId> {
+        self.as_call_id_in(db, None, resolver)
+    }
+}
+
+impl AstIdWithPath<ast::MacroCall> {
+    fn as_call_id_in(
+        &self,
+        db: &dyn db::DefDatabase,
+        module: Option<ModuleId>,
+        resolver: impl Fn(path::ModPath) -> Option<MacroDefId>,
     ) -> Option<MacroCallId> {
         let def: MacroDefId = resolver(self.path.clone())?;
 
         if let MacroDefKind::BuiltInEager(_) = def.kind {
             let macro_call = InFile::new(self.ast_id.file_id, self.ast_id.to_node(db.upcast()));
             let hygiene = Hygiene::new(db.upcast(), self.ast_id.file_id);
+            let module_path =
+                module.map(|it| db.crate_def_map(it.krate).module_path(db, it.local_id));
 
             Some(
-                expand_eager_macro(db.upcast(), macro_call, def, &|path: ast::Path| {
-                    resolver(path::ModPath::from_src(path, &hygiene)?)
-                })?
+                expand_eager_macro(
+                    db.upcast(),
+                    macro_call,
+                    def,
+                    module_path.as_deref(),
+                    &|path: ast::Path| resolver(path::ModPath::from_src(path, &hygiene)?),
+                )?
                 .into(),
             )
         } else {
@@ -476,6 +493,22 @@ impl AsMacroCall for AstIdWithPath<ast::MacroCall> {
     }
 }
 
+/// Like `AsMacroCall::as_call_id`, for a macro call in `module`. Only then can
+/// `module_path!` calls, which may also be nested in the arguments of other
+/// eager macros, be expanded to the path of the module.
+pub fn as_call_id_in_module(
+    db: &dyn db::DefDatabase,
+    macro_call: InFile<&ast::MacroCall>,
+    module: ModuleId,
+    resolver: impl Fn(path::ModPath) -> Option<MacroDefId>,
+) -> Option<MacroCallId> {
+    let ast_id = db.ast_id_map(macro_call.file_id).ast_id(macro_call.value);
+    let h = Hygiene::new(db.upcast(), macro_call.file_id);
+    let path = path::ModPath::from_src(macro_call.value.path()?, &h)?;
+
+    AstIdWithPath::new(macro_call.file_id, ast_id, path).as_call_id_in(db, Some(module), resolver)
+}
+
 impl AsMacroCall for AstIdWithPath<ast::ModuleItem> {
     fn as_call_id(
         &self,
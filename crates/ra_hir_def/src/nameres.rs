@@ -219,6 +219,43 @@ impl CrateDefMap {
             .map(|(id, _data)| id)
     }
 
+    /// Returns the `::` separated path of `module`, which is what `module_path!` expands to.
+    pub fn module_path(&self, db: &dyn DefDatabase, module: LocalModuleId) -> String {
+        let mut segments = Vec::new();
+        let mut current = module;
+        while let Some(parent) = self.modules[current].parent {
+            let name = self.modules[parent]
+                .children
+                .iter()
+                .find(|&(_, &child)| child == current)
+                .map(|(name, _)| name.to_string());
+            segments.extend(name);
+            current = parent;
+        }
+        segments.push(self.crate_name(db));
+        segments.reverse();
+        segments.join("::")
+    }
+
+    /// Returns the name rustc knows the crate by, with dashes replaced by underscores.
+    /// Crates without a display name, like those of `rust-project.json`, are named after
+    /// the name their dependents use for them.
+    fn crate_name(&self, db: &dyn DefDatabase) -> String {
+        let crate_graph = db.crate_graph();
+        let name = crate_graph[self.krate].display_name.as_ref().map(|it| it.to_string());
+        let name = name.or_else(|| {
+            crate_graph
+                .iter()
+                .flat_map(|krate| crate_graph[krate].dependencies.iter())
+                .find(|dep| dep.crate_id == self.krate)
+                .map(|dep| dep.name.to_string())
+        });
+        match name {
+            Some(name) => name.replace('-', "_"),
+            None => "crate".to_string(),
+        }
+    }
+
     pub(crate) fn resolve_path(
         &self,
         db: &dyn DefDatabase,
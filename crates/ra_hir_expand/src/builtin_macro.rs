@@ -1,5 +1,5 @@
 //! Builtin macro
-use crate::db::AstDatabase;
+use crate::db::{to_fragment_kind, AstDatabase};
 use crate::{
     ast::{self, AstToken, HasStringValue},
     name, AstId, CrateId, MacroDefId, MacroDefKind, TextUnit,
@@ -94,12 +94,18 @@ register_builtin! {
     // format_args_nl only differs in that it adds a newline in the end,
     // so we use the same stub expansion for now
     (format_args_nl, FormatArgsNl) => format_args_expand,
+    (trace_macros, TraceMacros) => trace_macros_expand,
 
     EAGER:
     (concat, Concat) => concat_expand,
     (include, Include) => include_expand,
     (env, Env) => env_expand,
-    (option_env, OptionEnv) => option_env_expand
+    (option_env, OptionEnv) => option_env_expand,
+    (include_str, IncludeStr) => include_str_expand,
+    (include_bytes, IncludeBytes) => include_bytes_expand,
+    (concat_idents, ConcatIdents) => concat_idents_expand,
+    (module_path, ModulePath) => module_path_expand,
+    (cfg, Cfg) => cfg_expand
 }
 
 fn line_expand(
@@ -291,13 +297,18 @@ fn concat_expand(
     Ok((quote!(#text), FragmentKind::Expr))
 }
 
-fn relative_file(db: &dyn AstDatabase, call_id: MacroCallId, path: &str) -> Option<FileId> {
+fn relative_file(
+    db: &dyn AstDatabase,
+    call_id: MacroCallId,
+    path: &str,
+    allow_recursion: bool,
+) -> Option<FileId> {
     let call_site = call_id.as_file().original_file(db);
 
     // Handle trivial case
     if let Some(res) = db.resolve_relative_path(call_site, &RelativePath::new(&path)) {
         // Prevent include itself
-        return if res == call_site { None } else { Some(res) };
+        return if res == call_site && !allow_recursion { None } else { Some(res) };
     }
 
     // Extern paths ?
@@ -324,8 +335,8 @@ fn include_expand(
     tt: &tt::Subtree,
 ) -> Result<(tt::Subtree, FragmentKind), mbe::ExpandError> {
     let path = parse_string(tt)?;
-    let file_id =
-        relative_file(db, arg_id.into(), &path).ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    let file_id = relative_file(db, arg_id.into(), &path, false)
+        .ok_or_else(|| mbe::ExpandError::ConversionError)?;
 
     // FIXME:
     // Handle include as expression
@@ -336,6 +347,112 @@ fn include_expand(
     Ok((res, FragmentKind::Items))
 }
 
+fn include_str_expand(
+    db: &dyn AstDatabase,
+    arg_id: EagerMacroId,
+    tt: &tt::Subtree,
+) -> Result<(tt::Subtree, FragmentKind), mbe::ExpandError> {
+    let path = parse_string(tt)?;
+    let file_id = relative_file(db, arg_id.into(), &path, true)
+        .ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    let text = db.file_text(file_id);
+    let text = text.as_str();
+
+    Ok((quote!(#text), FragmentKind::Expr))
+}
+
+fn include_bytes_expand(
+    db: &dyn AstDatabase,
+    arg_id: EagerMacroId,
+    tt: &tt::Subtree,
+) -> Result<(tt::Subtree, FragmentKind), mbe::ExpandError> {
+    let path = parse_string(tt)?;
+    // The vfs only loads files which are valid UTF-8, so the bytes of a resolved file's text
+    // are exactly the bytes of the file. Binary files never resolve and fail to expand.
+    let file_id = relative_file(db, arg_id.into(), &path, true)
+        .ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    let text = db.file_text(file_id);
+    let bytes: String = text.bytes().flat_map(std::ascii::escape_default).map(char::from).collect();
+    let res = tt::Subtree {
+        delimiter: None,
+        token_trees: vec![tt::TokenTree::Leaf(tt::Leaf::Literal(tt::Literal {
+            text: format!("b\"{}\"", bytes).into(),
+            id: tt::TokenId::unspecified(),
+        }))],
+    };
+
+    Ok((res, FragmentKind::Expr))
+}
+
+fn concat_idents_expand(
+    _db: &dyn AstDatabase,
+    _arg_id: EagerMacroId,
+    tt: &tt::Subtree,
+) -> Result<(tt::Subtree, FragmentKind), mbe::ExpandError> {
+    let mut ident = String::new();
+    for (i, t) in tt.token_trees.iter().enumerate() {
+        match t {
+            tt::TokenTree::Leaf(tt::Leaf::Ident(it)) if i % 2 == 0 => {
+                ident.push_str(it.text.as_str());
+            }
+            tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) if i % 2 == 1 && punct.char == ',' => (),
+            _ => return Err(mbe::ExpandError::UnexpectedToken),
+        }
+    }
+    if ident.is_empty() {
+        return Err(mbe::ExpandError::NoMatchingRule);
+    }
+    let ident = tt::Ident { text: ident.into(), id: tt::TokenId::unspecified() };
+
+    Ok((quote!(#ident), FragmentKind::Expr))
+}
+
+fn module_path_expand(
+    _db: &dyn AstDatabase,
+    _arg_id: EagerMacroId,
+    _tt: &tt::Subtree,
+) -> Result<(tt::Subtree, FragmentKind), mbe::ExpandError> {
+    // The path of the calling module is only known to name resolution, so
+    // `expand_eager_macro` expands `module_path!` calls itself.
+    Err(mbe::ExpandError::UnexpectedToken)
+}
+
+fn cfg_expand(
+    db: &dyn AstDatabase,
+    arg_id: EagerMacroId,
+    tt: &tt::Subtree,
+) -> Result<(tt::Subtree, FragmentKind), mbe::ExpandError> {
+    let call_id: MacroCallId = arg_id.into();
+    let original_file = call_id.as_file().original_file(db);
+
+    let enabled = db
+        .relevant_crates(original_file)
+        .get(0)
+        .and_then(|&krate| db.crate_graph()[krate].cfg_options.is_cfg_enabled(tt))
+        .unwrap_or(false);
+    let value = tt::Ident {
+        text: if enabled { "true" } else { "false" }.into(),
+        id: tt::TokenId::unspecified(),
+    };
+
+    Ok((quote!(#value), FragmentKind::Expr))
+}
+
+fn trace_macros_expand(
+    db: &dyn AstDatabase,
+    id: LazyMacroId,
+    _tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    // `trace_macros!` only affects compiler diagnostics, so it expands to nothing: no
+    // items in item position, and `()` in expression position.
+    let expanded = match to_fragment_kind(db, id.into()) {
+        FragmentKind::Items => tt::Subtree::default(),
+        _ => quote!(()),
+    };
+
+    Ok(expanded)
+}
+
 fn get_env_inner(db: &dyn AstDatabase, arg_id: EagerMacroId, key: &str) -> Option<String> {
     let call_id: MacroCallId = arg_id.into();
     let original_file = call_id.as_file().original_file(db);
@@ -520,6 +637,46 @@ mod tests {
         assert_eq!(expanded, "std::option::Option::None:: < &str>");
     }
 
+    #[test]
+    fn test_concat_idents_expand() {
+        let expanded = expand_builtin_macro(
+            r#"
+            #[rustc_builtin_macro]
+            macro_rules! concat_idents {() => {}}
+            concat_idents!(foo, bar)
+            "#,
+        );
+
+        assert_eq!(expanded, "foobar");
+    }
+
+    #[test]
+    fn test_cfg_expand() {
+        let expanded = expand_builtin_macro(
+            r#"
+            #[rustc_builtin_macro]
+            macro_rules! cfg {() => {}}
+            cfg!(feature = "foo")
+            "#,
+        );
+
+        assert_eq!(expanded, "false");
+    }
+
+    #[test]
+    fn test_cfg_expand_enabled() {
+        let expanded = expand_builtin_macro(
+            r#"
+            //- /main.rs cfg:feature=foo
+            #[rustc_builtin_macro]
+            macro_rules! cfg {() => {}}
+            cfg!(feature = "foo")
+            "#,
+        );
+
+        assert_eq!(expanded, "true");
+    }
+
     #[test]
     fn test_file_expand() {
         let expanded = expand_builtin_macro(
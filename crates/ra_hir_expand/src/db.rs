@@ -277,7 +277,7 @@ pub fn parse_macro_with_arg(
 
 /// Given a `MacroCallId`, return what `FragmentKind` it belongs to.
 /// FIXME: Not completed
-fn to_fragment_kind(db: &dyn AstDatabase, id: MacroCallId) -> FragmentKind {
+pub(crate) fn to_fragment_kind(db: &dyn AstDatabase, id: MacroCallId) -> FragmentKind {
     let lazy_id = match id {
         MacroCallId::LazyMacro(id) => id,
         MacroCallId::EagerMacro(id) => {
@@ -21,20 +21,33 @@
 
 use crate::{
     ast::{self, AstNode},
+    builtin_macro::EagerExpander,
     db::AstDatabase,
-    EagerCallLoc, EagerMacroId, InFile, MacroCallId, MacroCallKind, MacroDefId, MacroDefKind,
+    quote, EagerCallLoc, EagerMacroId, InFile, MacroCallId, MacroCallKind, MacroDefId,
+    MacroDefKind,
 };
 
 use ra_parser::FragmentKind;
 use ra_syntax::{algo::replace_descendants, SyntaxElement, SyntaxNode};
 use std::{collections::HashMap, sync::Arc};
 
+/// Expands a call of an eager builtin macro, after expanding the macro calls in
+/// its arguments.
+///
+/// `module_path` is the path of the module containing the call, which only name
+/// resolution knows. `module_path!` calls, including the ones in the arguments,
+/// can't be expanded without it.
 pub fn expand_eager_macro(
     db: &dyn AstDatabase,
     macro_call: InFile<ast::MacroCall>,
     def: MacroDefId,
+    module_path: Option<&str>,
     resolver: &dyn Fn(ast::Path) -> Option<MacroDefId>,
 ) -> Option<EagerMacroId> {
+    if let MacroDefKind::BuiltInEager(EagerExpander::ModulePath) = def.kind {
+        return Some(expand_module_path(db, macro_call, def, module_path?));
+    }
+
     let args = macro_call.value.token_tree()?;
     let parsed_args = mbe::ast_to_token_tree(&args)?.0;
 
@@ -56,6 +69,7 @@ pub fn expand_eager_macro(
     let result = eager_macro_recur(
         db,
         InFile::new(arg_file_id.as_file(), parsed_args.syntax_node()),
+        module_path,
         resolver,
     )?;
     let subtree = to_subtree(&result)?;
@@ -71,6 +85,23 @@ pub fn expand_eager_macro(
     }
 }
 
+/// Expands a `module_path!` call to `module_path`, the path of the module containing it.
+fn expand_module_path(
+    db: &dyn AstDatabase,
+    macro_call: InFile<ast::MacroCall>,
+    def: MacroDefId,
+    module_path: &str,
+) -> EagerMacroId {
+    let subtree = quote!(#module_path);
+    let eager = EagerCallLoc {
+        def,
+        fragment: FragmentKind::Expr,
+        subtree: Arc::new(subtree),
+        file_id: macro_call.file_id,
+    };
+    db.intern_eager_expansion(eager)
+}
+
 fn to_subtree(node: &SyntaxNode) -> Option<tt::Subtree> {
     let mut subtree = mbe::syntax_node_to_token_tree(node)?.0;
     subtree.delimiter = None;
@@ -93,6 +124,7 @@ fn lazy_expand(
 fn eager_macro_recur(
     db: &dyn AstDatabase,
     curr: InFile<SyntaxNode>,
+    module_path: Option<&str>,
     macro_resolver: &dyn Fn(ast::Path) -> Option<MacroDefId>,
 ) -> Option<SyntaxNode> {
     let mut original = curr.value.clone();
@@ -105,9 +137,14 @@ fn eager_macro_recur(
         let def: MacroDefId = macro_resolver(child.path()?)?;
         let insert = match def.kind {
             MacroDefKind::BuiltInEager(_) => {
-                let id: MacroCallId =
-                    expand_eager_macro(db, curr.with_value(child.clone()), def, macro_resolver)?
-                        .into();
+                let id: MacroCallId = expand_eager_macro(
+                    db,
+                    curr.with_value(child.clone()),
+                    def,
+                    module_path,
+                    macro_resolver,
+                )?
+                .into();
                 db.parse_or_expand(id.as_file())?
             }
             MacroDefKind::Declarative
@@ -115,7 +152,7 @@ fn eager_macro_recur(
             | MacroDefKind::BuiltInDerive(_) => {
                 let expanded = lazy_expand(db, &def, curr.with_value(child.clone()))?;
                 // replace macro inside
-                eager_macro_recur(db, expanded, macro_resolver)?
+                eager_macro_recur(db, expanded, module_path, macro_resolver)?
             }
         };
 
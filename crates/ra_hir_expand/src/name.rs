@@ -148,6 +148,7 @@ pub mod known {
         future,
         result,
         boxed,
+        convert,
         // Components of known path (type name)
         IntoIterator,
         Item,
@@ -167,6 +168,7 @@ pub mod known {
         Neg,
         Not,
         Index,
+        From,
         // Builtin macros
         file,
         column,
@@ -180,6 +182,12 @@ pub mod known {
         format_args_nl,
         env,
         option_env,
+        include_str,
+        include_bytes,
+        concat_idents,
+        module_path,
+        cfg,
+        trace_macros,
         // Builtin derives
         Copy,
         Clone,
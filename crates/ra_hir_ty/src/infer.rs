@@ -131,6 +131,10 @@ pub struct InferenceResult {
     variant_resolutions: FxHashMap<ExprOrPatId, VariantId>,
     /// For each associated item record what it resolves to
     assoc_resolutions: FxHashMap<ExprOrPatId, AssocItemId>,
+    /// For each overloaded operator expr (`a + b`, `*a`, `a[b]`, `a?`,
+    /// `a.await`), records the trait method it desugars to, together with
+    /// the substitution for the trait's parameters (`Self` first).
+    operator_resolutions: FxHashMap<ExprId, (FunctionId, Substs)>,
     diagnostics: Vec<InferenceDiagnostic>,
     pub type_of_expr: ArenaMap<ExprId, Ty>,
     pub type_of_pat: ArenaMap<PatId, Ty>,
@@ -159,6 +163,14 @@ impl InferenceResult {
     pub fn assoc_resolutions_for_pat(&self, id: PatId) -> Option<AssocItemId> {
         self.assoc_resolutions.get(&id.into()).copied()
     }
+    pub fn operator_resolution(&self, expr: ExprId) -> Option<(FunctionId, Substs)> {
+        self.operator_resolutions.get(&expr).cloned()
+    }
+    pub fn operator_resolutions(
+        &self,
+    ) -> impl Iterator<Item = (ExprId, &(FunctionId, Substs))> + '_ {
+        self.operator_resolutions.iter().map(|(expr, it)| (*expr, it))
+    }
     pub fn type_mismatch_for_expr(&self, expr: ExprId) -> Option<&TypeMismatch> {
         self.type_mismatches.get(expr)
     }
@@ -234,6 +246,10 @@ impl<'a> InferenceContext<'a> {
             let resolved = self.table.resolve_ty_completely(mem::replace(ty, Ty::Unknown));
             *ty = resolved;
         }
+        for (_, substs) in result.operator_resolutions.values_mut() {
+            let resolved = substs.iter().map(|ty| self.table.resolve_ty_completely(ty.clone()));
+            *substs = Substs(resolved.collect());
+        }
         result
     }
 
@@ -245,6 +261,12 @@ impl<'a> InferenceContext<'a> {
         self.result.method_resolutions.insert(expr, func);
     }
 
+    fn write_operator_resolution(&mut self, expr: ExprId, func: Option<FunctionId>, tys: Vec<Ty>) {
+        if let Some(func) = func {
+            self.result.operator_resolutions.insert(expr, (func, Substs(tys.into())));
+        }
+    }
+
     fn write_field_resolution(&mut self, expr: ExprId, field: StructFieldId) {
         self.result.field_resolutions.insert(expr, field);
     }
@@ -498,6 +520,24 @@ impl<'a> InferenceContext<'a> {
         self.db.lang_item(krate, name)
     }
 
+    fn resolve_lang_trait_method(&self, lang_item: &str, method: &str) -> Option<FunctionId> {
+        let trait_ = self.resolve_lang_item(lang_item)?.as_trait()?;
+        self.resolve_trait_method(trait_, method)
+    }
+
+    fn resolve_trait_method(&self, trait_: TraitId, method: &str) -> Option<FunctionId> {
+        self.db.trait_data(trait_).items.iter().find_map(|(name, item)| match item {
+            AssocItemId::FunctionId(f) if name.to_string() == method => Some(*f),
+            _ => None,
+        })
+    }
+
+    fn resolve_convert_from(&self) -> Option<FunctionId> {
+        let path = path![std::convert::From];
+        let trait_ = self.resolver.resolve_known_trait(self.db.upcast(), &path)?;
+        self.resolve_trait_method(trait_, "from")
+    }
+
     fn resolve_into_iter_item(&self) -> Option<TypeAliasId> {
         let path = path![std::iter::IntoIterator];
         let trait_ = self.resolver.resolve_known_trait(self.db.upcast(), &path)?;
@@ -525,6 +565,12 @@ impl<'a> InferenceContext<'a> {
         self.db.trait_data(trait_).associated_type_by_name(&name![Output])
     }
 
+    fn resolve_result(&self) -> Option<AdtId> {
+        let path = path![std::result::Result];
+        let enum_ = self.resolver.resolve_known_enum(self.db.upcast(), &path)?;
+        Some(enum_.into())
+    }
+
     fn resolve_boxed_box(&self) -> Option<AdtId> {
         let struct_ = self.resolve_lang_item("owned_box")?.as_struct()?;
         Some(struct_.into())
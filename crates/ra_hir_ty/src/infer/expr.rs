@@ -276,10 +276,13 @@ impl<'a> InferenceContext<'a> {
             }
             Expr::Await { expr } => {
                 let inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
+                let poll = self.resolve_lang_trait_method("future_trait", "poll");
+                self.write_operator_resolution(tgt_expr, poll, vec![inner_ty.clone()]);
                 self.resolve_associated_type(inner_ty, self.resolve_future_future_output())
             }
             Expr::Try { expr } => {
                 let inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
+                self.write_try_conversion(tgt_expr, &inner_ty);
                 self.resolve_associated_type(inner_ty, self.resolve_ops_try_ok())
             }
             Expr::Cast { expr, type_ref } => {
@@ -313,25 +316,28 @@ impl<'a> InferenceContext<'a> {
             Expr::UnaryOp { expr, op } => {
                 let inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
                 match op {
-                    UnaryOp::Deref => match self.resolver.krate() {
-                        Some(krate) => {
-                            let canonicalized = self.canonicalizer().canonicalize_ty(inner_ty);
-                            match autoderef::deref(
-                                self.db,
-                                krate,
-                                InEnvironment {
-                                    value: &canonicalized.value,
-                                    environment: self.trait_env.clone(),
-                                },
-                            ) {
-                                Some(derefed_ty) => {
-                                    canonicalized.decanonicalize_ty(derefed_ty.value)
+                    UnaryOp::Deref => {
+                        self.write_deref_resolution(tgt_expr, &inner_ty);
+                        match self.resolver.krate() {
+                            Some(krate) => {
+                                let canonicalized = self.canonicalizer().canonicalize_ty(inner_ty);
+                                match autoderef::deref(
+                                    self.db,
+                                    krate,
+                                    InEnvironment {
+                                        value: &canonicalized.value,
+                                        environment: self.trait_env.clone(),
+                                    },
+                                ) {
+                                    Some(derefed_ty) => {
+                                        canonicalized.decanonicalize_ty(derefed_ty.value)
+                                    }
+                                    None => Ty::Unknown,
                                 }
-                                None => Ty::Unknown,
                             }
+                            None => Ty::Unknown,
                         }
-                        None => Ty::Unknown,
-                    },
+                    }
                     UnaryOp::Neg => {
                         match &inner_ty {
                             // Fast path for builtins
@@ -351,8 +357,18 @@ impl<'a> InferenceContext<'a> {
                             | Ty::Infer(InferTy::IntVar(..))
                             | Ty::Infer(InferTy::FloatVar(..)) => inner_ty,
                             // Otherwise we resolve via the std::ops::Neg trait
-                            _ => self
-                                .resolve_associated_type(inner_ty, self.resolve_ops_neg_output()),
+                            _ => {
+                                let neg = self.resolve_lang_trait_method("neg", "neg");
+                                self.write_operator_resolution(
+                                    tgt_expr,
+                                    neg,
+                                    vec![inner_ty.clone()],
+                                );
+                                self.resolve_associated_type(
+                                    inner_ty,
+                                    self.resolve_ops_neg_output(),
+                                )
+                            }
                         }
                     }
                     UnaryOp::Not => {
@@ -362,8 +378,18 @@ impl<'a> InferenceContext<'a> {
                             | Ty::Apply(ApplicationTy { ctor: TypeCtor::Int(_), .. })
                             | Ty::Infer(InferTy::IntVar(..)) => inner_ty,
                             // Otherwise we resolve via the std::ops::Not trait
-                            _ => self
-                                .resolve_associated_type(inner_ty, self.resolve_ops_not_output()),
+                            _ => {
+                                let not = self.resolve_lang_trait_method("not", "not");
+                                self.write_operator_resolution(
+                                    tgt_expr,
+                                    not,
+                                    vec![inner_ty.clone()],
+                                );
+                                self.resolve_associated_type(
+                                    inner_ty,
+                                    self.resolve_ops_not_output(),
+                                )
+                            }
                         }
                     }
                 }
@@ -380,6 +406,15 @@ impl<'a> InferenceContext<'a> {
                     let rhs_expectation = op::binary_op_rhs_expectation(*op, lhs_ty.clone());
                     let rhs_ty = self.infer_expr(*rhs, &Expectation::has_type(rhs_expectation));
 
+                    if let Some((lang_item, method)) = op::binary_op_method(*op) {
+                        let func = self.resolve_lang_trait_method(lang_item, method);
+                        self.write_operator_resolution(
+                            tgt_expr,
+                            func,
+                            vec![lhs_ty.clone(), rhs_ty.clone()],
+                        );
+                    }
+
                     // FIXME: similar as above, return ty is often associated trait type
                     op::binary_op_return_ty(*op, lhs_ty, rhs_ty)
                 }
@@ -440,6 +475,12 @@ impl<'a> InferenceContext<'a> {
                     );
                     let self_ty =
                         self_ty.map_or(Ty::Unknown, |t| canonicalized.decanonicalize_ty(t.value));
+                    let index = self.resolve_trait_method(index_trait, "index");
+                    self.write_operator_resolution(
+                        tgt_expr,
+                        index,
+                        vec![self_ty.clone(), index_ty.clone()],
+                    );
                     self.resolve_associated_type_with_params(
                         self_ty,
                         self.resolve_ops_index_output(),
@@ -716,4 +757,43 @@ impl<'a> InferenceContext<'a> {
             }
         }
     }
+
+    /// Records `Deref::deref` for `*a`, unless `a` is a reference or a raw
+    /// pointer, which are dereferenced by the compiler itself.
+    fn write_deref_resolution(&mut self, expr: ExprId, inner_ty: &Ty) {
+        let inner_ty = self.resolve_ty_shallow(inner_ty).into_owned();
+        match &inner_ty {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Ref(_), .. })
+            | Ty::Apply(ApplicationTy { ctor: TypeCtor::RawPtr(_), .. }) => {}
+            Ty::Apply(_) => {
+                let deref = self.resolve_lang_trait_method("deref", "deref");
+                self.write_operator_resolution(expr, deref, vec![inner_ty]);
+            }
+            _ => {}
+        }
+    }
+
+    /// Records the `From::from` call `a?` uses to convert the error of `a`
+    /// into the error of the returned `Result`.
+    fn write_try_conversion(&mut self, expr: ExprId, inner_ty: &Ty) {
+        let (from, result) = match (self.resolve_convert_from(), self.resolve_result()) {
+            (Some(from), Some(result)) => (from, result),
+            _ => return,
+        };
+        let inner_ty = self.resolve_ty_shallow(inner_ty).into_owned();
+        let return_ty = self.return_ty.clone();
+        let return_ty = self.resolve_ty_shallow(&return_ty).into_owned();
+        match (&inner_ty, &return_ty) {
+            (
+                ty_app!(TypeCtor::Adt(inner), inner_params),
+                ty_app!(TypeCtor::Adt(ret), ret_params),
+            ) if *inner == result && *ret == result => {
+                if let (Some(source), Some(target)) = (inner_params.get(1), ret_params.get(1)) {
+                    let tys = vec![target.clone(), source.clone()];
+                    self.write_operator_resolution(expr, Some(from), tys);
+                }
+            }
+            _ => {}
+        }
+    }
 }
@@ -9,7 +9,7 @@ use hir_def::{
     lang_item::LangItemTarget, type_ref::Mutability, AssocContainerId, AssocItemId, FunctionId,
     HasModule, ImplId, Lookup, TraitId,
 };
-use hir_expand::name::Name;
+use hir_expand::name::{name, Name};
 use ra_db::CrateId;
 use ra_prof::profile;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -521,6 +521,120 @@ pub(crate) fn inherent_impl_substs(
     substs.map(|s| fallback_bound_vars(s.suffix(vars.len()), self_ty.num_vars))
 }
 
+/// Looks up the method implementing the trait method `func` for the given
+/// trait parameters (`Self` first), e.g. `<Foo as Add>::add` for `Add::add`
+/// with `[Foo, Foo]`. Falls back to `func` itself if no impl in `krate` or its
+/// dependencies applies.
+pub fn lookup_impl_method(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    func: FunctionId,
+    trait_substs: &Substs,
+) -> FunctionId {
+    let trait_ = match func.lookup(db.upcast()).container {
+        AssocContainerId::TraitId(it) => it,
+        _ => return func,
+    };
+    if trait_substs.is_empty() || trait_substs[0] == Ty::Unknown {
+        return func;
+    }
+    let cardinality = trait_substs.len() as u16;
+    let trait_ty = Canonical {
+        num_vars: 0,
+        value: Ty::apply(TypeCtor::Tuple { cardinality }, trait_substs.clone()),
+    };
+    let name = &db.function_data(func).name;
+    for &impl_id in db.impls_for_trait(krate, trait_).iter() {
+        let impl_trait = match db.impl_trait(impl_id) {
+            Some(it) => it,
+            None => continue,
+        };
+        let vars = Substs::build_for_def(db, impl_id).fill_with_bound_vars(0).build();
+        let impl_substs = impl_trait.subst(&vars).substs;
+        if impl_substs.len() != trait_substs.len() {
+            continue;
+        }
+        let impl_ty = Canonical {
+            num_vars: vars.len(),
+            value: Ty::apply(TypeCtor::Tuple { cardinality }, impl_substs),
+        };
+        if super::infer::unify(&impl_ty, &trait_ty).is_none() {
+            continue;
+        }
+        let method = db.impl_data(impl_id).items.iter().find_map(|item| match item {
+            AssocItemId::FunctionId(f) if db.function_data(*f).name == *name => Some(*f),
+            _ => None,
+        });
+        if let Some(method) = method {
+            return method;
+        }
+    }
+    func
+}
+
+/// Lang items of the traits whose methods overloaded operators desugar to,
+/// with the text of the tokens of these operators.
+const OPERATOR_TRAITS: &[(&str, &[&str])] = &[
+    ("add", &["+"]),
+    ("sub", &["-"]),
+    ("mul", &["*"]),
+    ("div", &["/"]),
+    ("rem", &["%"]),
+    ("shl", &["<<"]),
+    ("shr", &[">>"]),
+    ("bitxor", &["^"]),
+    ("bitor", &["|"]),
+    ("bitand", &["&"]),
+    ("add_assign", &["+="]),
+    ("sub_assign", &["-="]),
+    ("mul_assign", &["*="]),
+    ("div_assign", &["/="]),
+    ("rem_assign", &["%="]),
+    ("shl_assign", &["<<="]),
+    ("shr_assign", &[">>="]),
+    ("bitxor_assign", &["^="]),
+    ("bitor_assign", &["|="]),
+    ("bitand_assign", &["&="]),
+    ("eq", &["==", "!="]),
+    ("partial_ord", &["<", ">"]),
+    ("neg", &["-"]),
+    ("not", &["!"]),
+    ("deref", &["*"]),
+    ("index", &["["]),
+    ("future_trait", &["await"]),
+];
+
+/// Checks whether `func` is a method of a trait overloading an operator, or
+/// implements one, so that operator expressions may resolve to it.
+pub fn is_operator_method(db: &dyn HirDatabase, func: FunctionId) -> bool {
+    !operator_tokens(db, func).is_empty()
+}
+
+/// Returns the text of the tokens of the operators which may call `func`, or
+/// an empty slice if it isn't an operator method. `From` is not a lang item,
+/// so it is recognized by its name; `a?` calls it.
+pub fn operator_tokens(db: &dyn HirDatabase, func: FunctionId) -> &'static [&'static str] {
+    let trait_ = match func.lookup(db.upcast()).container {
+        AssocContainerId::TraitId(it) => it,
+        AssocContainerId::ImplId(it) => match db.impl_trait(it) {
+            Some(trait_ref) => trait_ref.value.trait_,
+            None => return &[],
+        },
+        AssocContainerId::ContainerId(_) => return &[],
+    };
+    if db.trait_data(trait_).name == name![From] {
+        return &["?"];
+    }
+    let krate = trait_.lookup(db.upcast()).container.module(db.upcast()).krate;
+    OPERATOR_TRAITS
+        .iter()
+        .find(|(lang_item, _)| {
+            db.lang_item(krate, (*lang_item).into()) == Some(LangItemTarget::TraitId(trait_))
+        })
+        .map(|(_, tokens)| *tokens)
+        .unwrap_or(&[])
+}
+
 /// This replaces any 'free' Bound vars in `s` (i.e. those with indices past
 /// num_vars_to_keep) by `Ty::Unknown`.
 fn fallback_bound_vars(s: Substs, num_vars_to_keep: usize) -> Substs {
@@ -1,5 +1,5 @@
 //! Helper functions for binary operator type inference.
-use hir_def::expr::{ArithOp, BinaryOp, CmpOp};
+use hir_def::expr::{ArithOp, BinaryOp, CmpOp, Ordering};
 
 use super::{InferTy, Ty, TypeCtor};
 use crate::ApplicationTy;
@@ -55,3 +55,44 @@ pub(super) fn binary_op_rhs_expectation(op: BinaryOp, lhs_ty: Ty) -> Ty {
         },
     }
 }
+
+/// Returns the lang item of the trait overloading `op`, and the name of the
+/// trait method it calls.
+pub(super) fn binary_op_method(op: BinaryOp) -> Option<(&'static str, &'static str)> {
+    let res = match op {
+        BinaryOp::LogicOp(_) | BinaryOp::Assignment { op: None } => return None,
+        BinaryOp::ArithOp(op) => match op {
+            ArithOp::Add => ("add", "add"),
+            ArithOp::Sub => ("sub", "sub"),
+            ArithOp::Mul => ("mul", "mul"),
+            ArithOp::Div => ("div", "div"),
+            ArithOp::Rem => ("rem", "rem"),
+            ArithOp::Shl => ("shl", "shl"),
+            ArithOp::Shr => ("shr", "shr"),
+            ArithOp::BitXor => ("bitxor", "bitxor"),
+            ArithOp::BitOr => ("bitor", "bitor"),
+            ArithOp::BitAnd => ("bitand", "bitand"),
+        },
+        BinaryOp::Assignment { op: Some(op) } => match op {
+            ArithOp::Add => ("add_assign", "add_assign"),
+            ArithOp::Sub => ("sub_assign", "sub_assign"),
+            ArithOp::Mul => ("mul_assign", "mul_assign"),
+            ArithOp::Div => ("div_assign", "div_assign"),
+            ArithOp::Rem => ("rem_assign", "rem_assign"),
+            ArithOp::Shl => ("shl_assign", "shl_assign"),
+            ArithOp::Shr => ("shr_assign", "shr_assign"),
+            ArithOp::BitXor => ("bitxor_assign", "bitxor_assign"),
+            ArithOp::BitOr => ("bitor_assign", "bitor_assign"),
+            ArithOp::BitAnd => ("bitand_assign", "bitand_assign"),
+        },
+        BinaryOp::CmpOp(CmpOp::Eq { negated: false }) => ("eq", "eq"),
+        BinaryOp::CmpOp(CmpOp::Eq { negated: true }) => ("eq", "ne"),
+        BinaryOp::CmpOp(CmpOp::Ord { ordering, strict }) => match (ordering, strict) {
+            (Ordering::Less, true) => ("partial_ord", "lt"),
+            (Ordering::Less, false) => ("partial_ord", "le"),
+            (Ordering::Greater, true) => ("partial_ord", "gt"),
+            (Ordering::Greater, false) => ("partial_ord", "ge"),
+        },
+    };
+    Some(res)
+}
@@ -17,7 +17,7 @@ use hir_def::{
     item_scope::ItemScope,
     keys,
     nameres::CrateDefMap,
-    AssocItemId, DefWithBodyId, LocalModuleId, Lookup, ModuleDefId,
+    AssocContainerId, AssocItemId, DefWithBodyId, LocalModuleId, Lookup, ModuleDefId,
 };
 use hir_expand::InFile;
 use insta::assert_snapshot;
@@ -27,7 +27,9 @@ use ra_syntax::{
     ast::{self, AstNode},
 };
 
-use crate::{db::HirDatabase, display::HirDisplay, test_db::TestDB, InferenceResult};
+use crate::{
+    db::HirDatabase, display::HirDisplay, method_resolution, test_db::TestDB, InferenceResult,
+};
 
 // These tests compare the inference results for all expressions in a file
 // against snapshots of the expected results using insta. Use cargo-insta to
@@ -51,6 +53,36 @@ fn type_at_pos(db: &TestDB, pos: FilePosition) -> String {
     panic!("Can't find expression")
 }
 
+/// Describes the trait method the operator expression at the position
+/// desugars to, and the impl method it calls.
+fn operator_resolution_at(content: &str) -> String {
+    let (db, pos) = TestDB::with_position(content);
+    let file = db.parse(pos.file_id).ok().unwrap();
+    let expr = algo::find_node_at_offset::<ast::Expr>(file.syntax(), pos.offset).unwrap();
+    let fn_def = expr.syntax().ancestors().find_map(ast::FnDef::cast).unwrap();
+    let module = db.module_for_file(pos.file_id);
+    let func = *module.child_by_source(&db)[keys::FUNCTION]
+        .get(&InFile::new(pos.file_id.into(), fn_def))
+        .unwrap();
+
+    let (_body, source_map) = db.body_with_source_map(func.into());
+    let expr_id = source_map.node_expr(InFile::new(pos.file_id.into(), &expr)).unwrap();
+    let (method, substs) = match db.infer(func.into()).operator_resolution(expr_id) {
+        Some(it) => it,
+        None => return "none".to_string(),
+    };
+    let impl_method = method_resolution::lookup_impl_method(&db, module.krate, method, &substs);
+    let container = match impl_method.lookup(&db).container {
+        AssocContainerId::ImplId(it) => {
+            format!("impl for {}", db.impl_self_ty(it).value.display(&db))
+        }
+        AssocContainerId::TraitId(it) => format!("trait {}", db.trait_data(it).name),
+        AssocContainerId::ContainerId(_) => unreachable!(),
+    };
+    let substs = substs.iter().map(|ty| ty.display(&db).to_string()).collect::<Vec<_>>();
+    format!("{}<{}> in {}", db.function_data(method).name, substs.join(", "), container)
+}
+
 fn type_at(content: &str) -> String {
     let (db, file_pos) = TestDB::with_position(content);
     type_at_pos(&db, file_pos)
@@ -548,6 +548,129 @@ fn main() {
     assert_eq!("i32", type_at_pos(&db, pos));
 }
 
+#[test]
+fn infer_builtin_macros_include_str() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! include_str {() => {}}
+
+const DATA: &str = include_str!("foo.rs");
+
+fn main() {
+    DATA<|>;
+}
+
+//- /foo.rs
+fn bar() -> u32 {0}
+"#,
+    );
+    assert_eq!("&str", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_builtin_macros_include_str_unresolved_should_failed() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! include_str {() => {}}
+
+fn main() {
+    include_str!("foo.txt")<|>;
+}
+"#,
+    );
+    assert_eq!("{unknown}", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_builtin_macros_cfg() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs cfg:foo
+#[rustc_builtin_macro]
+macro_rules! cfg {() => {}}
+
+fn main() {
+    let x = cfg!(foo);
+    x<|>;
+}
+"#,
+    );
+    assert_eq!("bool", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_builtin_macros_cfg_enabled() {
+    assert_snapshot!(
+        infer(r#"
+//- /main.rs cfg:foo
+#[rustc_builtin_macro]
+macro_rules! cfg {() => {}}
+
+fn main() {
+    let x = cfg!(foo);
+    let y = cfg!(bar);
+}
+"#),
+        @r###"
+    ![0; 4) 'true': bool
+    ![0; 5) 'false': bool
+    [84; 133) '{     ...ar); }': ()
+    [94; 95) 'x': bool
+    [117; 118) 'y': bool
+    "###
+    );
+}
+
+#[test]
+fn infer_builtin_macros_module_path_in_nested_module() {
+    assert_snapshot!(
+        infer(r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! module_path {() => {}}
+
+mod foo {
+    mod bar {
+        fn baz() {
+            let x = module_path!();
+        }
+    }
+}
+"#),
+        @r###"
+    ![0; 17) '"crate...::bar"': &str
+    [115; 162) '{     ...     }': ()
+    [133; 134) 'x': &str
+    "###
+    );
+}
+
+#[test]
+fn infer_builtin_macros_trace_macros() {
+    assert_snapshot!(
+        infer(r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! trace_macros {() => {}}
+
+trace_macros!(true);
+
+fn main() {
+    let x = trace_macros!(false);
+}
+"#),
+        @r###"
+    ![0; 2) '()': ()
+    [107; 144) '{     ...se); }': ()
+    [117; 118) 'x': ()
+    "###
+    );
+}
+
 #[test]
 fn infer_builtin_macros_concat_with_lazy() {
     assert_snapshot!(
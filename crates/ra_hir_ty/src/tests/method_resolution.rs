@@ -1,5 +1,6 @@
-use super::{infer, type_at, type_at_pos};
-use crate::test_db::TestDB;
+use super::{infer, operator_resolution_at, type_at, type_at_pos};
+use crate::{db::HirDatabase, method_resolution, test_db::TestDB};
+use hir_def::{db::DefDatabase, AssocItemId, ModuleDefId};
 use insta::assert_snapshot;
 use ra_db::fixture::WithFixture;
 
@@ -1095,3 +1096,106 @@ fn test() { (S {}).method()<|>; }
     );
     assert_eq!(t, "()");
 }
+
+#[test]
+fn operator_resolutions() {
+    let fixture = |expr: &str| {
+        format!(
+            r#"
+//- /main.rs
+#[lang = "add"]
+pub trait Add<Rhs = Self> {{
+    type Output;
+    fn add(self, rhs: Rhs) -> Self::Output;
+}}
+#[lang = "neg"]
+pub trait Neg {{
+    type Output;
+    fn neg(self) -> Self::Output;
+}}
+struct Foo;
+struct Bar;
+impl Add for Foo {{
+    type Output = Foo;
+    fn add(self, rhs: Foo) -> Foo {{ rhs }}
+}}
+impl Add<Bar> for Foo {{
+    type Output = Bar;
+    fn add(self, rhs: Bar) -> Bar {{ rhs }}
+}}
+fn test(foo: Foo, bar: Bar) {{
+    {};
+}}
+"#,
+            expr
+        )
+    };
+    assert_eq!(operator_resolution_at(&fixture("foo <|>+ Foo")), "add<Foo, Foo> in impl for Foo");
+    assert_eq!(operator_resolution_at(&fixture("foo <|>+ bar")), "add<Foo, Bar> in impl for Foo");
+    assert_eq!(operator_resolution_at(&fixture("<|>-bar")), "neg<Bar> in trait Neg");
+}
+
+#[test]
+fn operator_methods() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+#[lang = "add"]
+pub trait Add<Rhs = Self> {
+    type Output;
+    fn add(self, rhs: Rhs) -> Self::Output;
+}
+pub trait Plus {
+    fn add(self);
+}
+struct Foo;
+impl Add for Foo {
+    type Output = Foo;
+    fn add(self, rhs: Foo) -> Foo { rhs }
+}
+impl Plus for Foo {
+    fn add(self) {}
+}
+fn add() {}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let crate_def_map = db.crate_def_map(module.krate);
+    let scope = &crate_def_map[module.local_id].scope;
+    let mut methods: Vec<(String, bool)> = Vec::new();
+    for decl in scope.declarations() {
+        match decl {
+            ModuleDefId::TraitId(it) => {
+                for (_, item) in db.trait_data(it).items.iter() {
+                    if let AssocItemId::FunctionId(f) = item {
+                        let name = format!("trait {}", db.trait_data(it).name);
+                        methods.push((name, method_resolution::is_operator_method(&db, *f)));
+                    }
+                }
+            }
+            ModuleDefId::FunctionId(it) => {
+                methods.push(("fn".to_string(), method_resolution::is_operator_method(&db, it)))
+            }
+            _ => (),
+        }
+    }
+    for impl_id in scope.impls() {
+        let trait_ = db.impl_trait(impl_id).unwrap().value.trait_;
+        for item in db.impl_data(impl_id).items.iter() {
+            if let AssocItemId::FunctionId(f) = item {
+                let name = format!("impl {}", db.trait_data(trait_).name);
+                methods.push((name, method_resolution::is_operator_method(&db, *f)));
+            }
+        }
+    }
+    methods.sort();
+    assert_eq!(
+        methods,
+        vec![
+            ("fn".to_string(), false),
+            ("impl Add".to_string(), true),
+            ("impl Plus".to_string(), false),
+            ("trait Add".to_string(), true),
+            ("trait Plus".to_string(), false),
+        ]
+    );
+}
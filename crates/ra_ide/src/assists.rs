@@ -1,12 +1,12 @@
 //! FIXME: write short doc here
 
-use ra_assists::{resolved_assists, AssistAction, AssistLabel};
+use ra_assists::{resolve_assist, resolved_assists, unresolved_assists, AssistAction, AssistLabel};
 use ra_db::{FilePosition, FileRange};
 use ra_ide_db::RootDatabase;
 
 use crate::{FileId, SourceChange, SourceFileEdit};
 
-pub use ra_assists::AssistId;
+pub use ra_assists::{AssistId, AssistOptions};
 
 #[derive(Debug)]
 pub struct Assist {
@@ -14,10 +14,24 @@ pub struct Assist {
     pub label: String,
     pub group_label: Option<String>,
     pub source_change: SourceChange,
+    /// Whether the text of `source_change` uses snippet syntax.
+    pub is_snippet: bool,
 }
 
-pub(crate) fn assists(db: &RootDatabase, frange: FileRange) -> Vec<Assist> {
-    resolved_assists(db, frange)
+/// An assist without its edit, see `Analysis::resolve_assist`.
+#[derive(Debug)]
+pub struct UnresolvedAssist {
+    pub id: AssistId,
+    pub label: String,
+    pub group_label: Option<String>,
+}
+
+pub(crate) fn assists(
+    db: &RootDatabase,
+    options: &AssistOptions,
+    frange: FileRange,
+) -> Vec<Assist> {
+    resolved_assists(db, options, frange)
         .into_iter()
         .map(|assist| {
             let file_id = frange.file_id;
@@ -26,12 +40,46 @@ pub(crate) fn assists(db: &RootDatabase, frange: FileRange) -> Vec<Assist> {
                 id: assist_label.id,
                 label: assist_label.label.clone(),
                 group_label: assist.group_label.map(|it| it.0),
+                is_snippet: assist.action.is_snippet,
                 source_change: action_to_edit(assist.action, file_id, assist_label),
             }
         })
         .collect()
 }
 
+pub(crate) fn unresolved(
+    db: &RootDatabase,
+    options: &AssistOptions,
+    frange: FileRange,
+) -> Vec<UnresolvedAssist> {
+    unresolved_assists(db, options, frange)
+        .into_iter()
+        .map(|assist| UnresolvedAssist {
+            id: assist.label.id,
+            label: assist.label.label,
+            group_label: assist.group_label.map(|it| it.0),
+        })
+        .collect()
+}
+
+pub(crate) fn resolve(
+    db: &RootDatabase,
+    options: &AssistOptions,
+    frange: FileRange,
+    id: &str,
+    label: &str,
+) -> Option<Assist> {
+    let assist = resolve_assist(db, options, frange, id, label)?;
+    let assist_label = &assist.label;
+    Some(Assist {
+        id: assist_label.id,
+        label: assist_label.label.clone(),
+        group_label: assist.group_label.map(|it| it.0),
+        is_snippet: assist.action.is_snippet,
+        source_change: action_to_edit(assist.action, frange.file_id, assist_label),
+    })
+}
+
 fn action_to_edit(
     action: AssistAction,
     file_id: FileId,
@@ -16,6 +16,8 @@ mod complete_scope;
 mod complete_postfix;
 mod complete_macro_in_item_position;
 mod complete_trait_impl;
+mod complete_flyimport;
+mod complete_attribute;
 #[cfg(test)]
 mod test_utils;
 
@@ -30,7 +32,7 @@ use crate::{
 };
 
 pub use crate::completion::completion_item::{
-    CompletionItem, CompletionItemKind, InsertTextFormat,
+    CompletionItem, CompletionItemKind, CompletionScore, InsertTextFormat,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -38,6 +40,9 @@ pub struct CompletionOptions {
     pub enable_postfix_completions: bool,
     pub add_call_parenthesis: bool,
     pub add_call_argument_snippets: bool,
+    pub enable_flyimport_completions: bool,
+    /// User-defined snippets, offered next to the built-in ones.
+    pub snippets: Vec<Snippet>,
 }
 
 impl Default for CompletionOptions {
@@ -46,10 +51,39 @@ impl Default for CompletionOptions {
             enable_postfix_completions: true,
             add_call_parenthesis: true,
             add_call_argument_snippets: true,
+            enable_flyimport_completions: true,
+            snippets: Vec::new(),
         }
     }
 }
 
+/// A user-defined snippet, like `.arc` for `Arc::new(expr)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snippet {
+    pub label: String,
+    pub description: Option<String>,
+    /// The text to insert, in LSP snippet syntax (`$0`, `${1:placeholder}`).
+    /// In postfix snippets, `${receiver}` stands for the expression before
+    /// the dot.
+    pub body: String,
+    /// Whether the snippet is completed after a dot, like `expr.arc`. Postfix
+    /// snippets always replace an expression, so their scope is ignored.
+    pub postfix: bool,
+    pub scope: SnippetScope,
+    /// Paths of the items used by the snippet, like `std::sync::Arc`. They are
+    /// imported when the snippet is inserted, and the snippet is not offered if
+    /// one of them can't be resolved.
+    pub requires: Vec<String>,
+}
+
+/// Where a (non-postfix) snippet is offered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnippetScope {
+    Expr,
+    Item,
+    Type,
+}
+
 /// Main entry point for completion. We run completion as a two-phase process.
 ///
 /// First, we look at the position and collect a so-called `CompletionContext.
@@ -86,6 +120,7 @@ pub(crate) fn completions(
     complete_keyword::complete_use_tree_keyword(&mut acc, &ctx);
     complete_snippet::complete_expr_snippet(&mut acc, &ctx);
     complete_snippet::complete_item_snippet(&mut acc, &ctx);
+    complete_snippet::complete_type_snippet(&mut acc, &ctx);
     complete_path::complete_path(&mut acc, &ctx);
     complete_scope::complete_scope(&mut acc, &ctx);
     complete_dot::complete_dot(&mut acc, &ctx);
@@ -95,6 +130,8 @@ pub(crate) fn completions(
     complete_postfix::complete_postfix(&mut acc, &ctx);
     complete_macro_in_item_position::complete_macro_in_item_position(&mut acc, &ctx);
     complete_trait_impl::complete_trait_impl(&mut acc, &ctx);
+    complete_flyimport::complete_flyimport(&mut acc, &ctx);
+    complete_attribute::complete_attribute(&mut acc, &ctx);
 
     Some(acc)
 }
@@ -0,0 +1,528 @@
+//! Completion for attributes
+//!
+//! This module uses a bit of static metadata to provide completions for
+//! built-in attributes, derives, lints and feature gates.
+//!
+//! Only the standard derives are completed: procedural derive macros are not
+//! known to the IDE yet.
+
+use ra_syntax::{ast, AstNode, SyntaxKind};
+use rustc_hash::FxHashSet;
+
+use crate::completion::{
+    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
+};
+
+pub(super) fn complete_attribute(acc: &mut Completions, ctx: &CompletionContext) {
+    let attribute = match &ctx.attribute_under_caret {
+        Some(it) => it,
+        None => return,
+    };
+
+    if let Some(ast::AttrInput::TokenTree(token_tree)) = attribute.input() {
+        if token_tree.syntax().text_range().start() < ctx.offset {
+            let name = attribute.simple_name();
+            match name.as_ref().map(|it| it.as_str()) {
+                Some("derive") => complete_derive(acc, ctx, &token_tree),
+                Some("allow") | Some("warn") | Some("deny") | Some("forbid") => {
+                    complete_from_list(acc, ctx, &token_tree, DEFAULT_LINT_COMPLETIONS)
+                }
+                Some("feature") => {
+                    complete_from_list(acc, ctx, &token_tree, DEFAULT_FEATURE_COMPLETIONS)
+                }
+                _ => (),
+            }
+            return;
+        }
+    }
+
+    complete_attribute_start(acc, ctx, attribute);
+}
+
+fn complete_attribute_start(acc: &mut Completions, ctx: &CompletionContext, attribute: &ast::Attr) {
+    let is_inner = attribute.kind() == ast::AttrKind::Inner;
+    for attr_completion in ATTRIBUTES.iter().filter(|it| is_inner || !it.should_be_inner) {
+        let mut item = CompletionItem::new(
+            CompletionKind::Attribute,
+            ctx.source_range(),
+            attr_completion.label,
+        )
+        .kind(CompletionItemKind::Attribute);
+
+        if let Some(lookup) = attr_completion.lookup {
+            item = item.lookup_by(lookup);
+        }
+        if let Some(snippet) = attr_completion.snippet {
+            item = item.insert_snippet(snippet);
+        }
+
+        acc.add(item);
+    }
+}
+
+struct AttrCompletion {
+    label: &'static str,
+    lookup: Option<&'static str>,
+    snippet: Option<&'static str>,
+    /// Whether the attribute is only valid at the crate level, i.e. `#![...]`.
+    should_be_inner: bool,
+}
+
+impl AttrCompletion {
+    const fn new(label: &'static str) -> AttrCompletion {
+        AttrCompletion { label, lookup: None, snippet: None, should_be_inner: false }
+    }
+
+    const fn with_snippet(
+        label: &'static str,
+        lookup: &'static str,
+        snippet: &'static str,
+    ) -> AttrCompletion {
+        AttrCompletion {
+            label,
+            lookup: Some(lookup),
+            snippet: Some(snippet),
+            should_be_inner: false,
+        }
+    }
+
+    const fn inner(self) -> AttrCompletion {
+        AttrCompletion { should_be_inner: true, ..self }
+    }
+}
+
+const ATTRIBUTES: &[AttrCompletion] = &[
+    AttrCompletion::with_snippet("allow(…)", "allow", "allow(${0:lint})"),
+    AttrCompletion::new("automatically_derived"),
+    AttrCompletion::with_snippet("cfg_attr(…)", "cfg_attr", "cfg_attr(${1:predicate}, ${0:attr})"),
+    AttrCompletion::with_snippet("cfg(…)", "cfg", "cfg(${0:predicate})"),
+    AttrCompletion::new("cold"),
+    AttrCompletion::with_snippet(
+        r#"crate_name = "…""#,
+        "crate_name",
+        r#"crate_name = "${0:crate_name}""#,
+    )
+    .inner(),
+    AttrCompletion::with_snippet("deny(…)", "deny", "deny(${0:lint})"),
+    AttrCompletion::with_snippet(
+        r#"deprecated = "…""#,
+        "deprecated",
+        r#"deprecated = "${0:reason}""#,
+    ),
+    AttrCompletion::with_snippet("derive(…)", "derive", "derive(${0:Debug})"),
+    AttrCompletion::with_snippet(r#"doc = "…""#, "doc", r#"doc = "${0:docs}""#),
+    AttrCompletion::with_snippet(
+        r#"export_name = "…""#,
+        "export_name",
+        r#"export_name = "${0:exported_symbol_name}""#,
+    ),
+    AttrCompletion::with_snippet("feature(…)", "feature", "feature(${0:flag})").inner(),
+    AttrCompletion::with_snippet("forbid(…)", "forbid", "forbid(${0:lint})"),
+    AttrCompletion::new("global_allocator"),
+    AttrCompletion::new("ignore"),
+    AttrCompletion::new("inline"),
+    AttrCompletion::with_snippet(
+        r#"link_name = "…""#,
+        "link_name",
+        r#"link_name = "${0:symbol_name}""#,
+    ),
+    AttrCompletion::with_snippet(
+        r#"link_section = "…""#,
+        "link_section",
+        r#"link_section = "${0:section_name}""#,
+    ),
+    AttrCompletion::new("link"),
+    AttrCompletion::new("macro_export"),
+    AttrCompletion::new("macro_use"),
+    AttrCompletion::new("must_use"),
+    AttrCompletion::new("no_implicit_prelude"),
+    AttrCompletion::new("no_main").inner(),
+    AttrCompletion::new("no_mangle"),
+    AttrCompletion::new("no_std").inner(),
+    AttrCompletion::new("non_exhaustive"),
+    AttrCompletion::new("panic_handler"),
+    AttrCompletion::with_snippet(r#"path = "…""#, "path", r#"path = "${0:path}""#),
+    AttrCompletion::new("proc_macro"),
+    AttrCompletion::new("proc_macro_attribute"),
+    AttrCompletion::with_snippet(
+        "proc_macro_derive(…)",
+        "proc_macro_derive",
+        "proc_macro_derive(${0:Trait})",
+    ),
+    AttrCompletion::with_snippet(
+        "recursion_limit = …",
+        "recursion_limit",
+        "recursion_limit = ${0:128}",
+    )
+    .inner(),
+    AttrCompletion::with_snippet("repr(…)", "repr", "repr(${0:C})"),
+    AttrCompletion::new("should_panic"),
+    AttrCompletion::with_snippet(
+        r#"target_feature = "…""#,
+        "target_feature",
+        r#"target_feature = "${0:feature}""#,
+    ),
+    AttrCompletion::new("test"),
+    AttrCompletion::with_snippet(
+        "type_length_limit = …",
+        "type_length_limit",
+        "type_length_limit = ${0:128}",
+    )
+    .inner(),
+    AttrCompletion::new("used"),
+    AttrCompletion::with_snippet("warn(…)", "warn", "warn(${0:lint})"),
+    AttrCompletion::with_snippet(
+        r#"windows_subsystem = "…""#,
+        "windows_subsystem",
+        r#"windows_subsystem = "${0:subsystem}""#,
+    )
+    .inner(),
+];
+
+fn complete_derive(acc: &mut Completions, ctx: &CompletionContext, derive_input: &ast::TokenTree) {
+    let existing_derives = existing_names(ctx, derive_input);
+
+    for derive_completion in
+        DEFAULT_DERIVE_COMPLETIONS.iter().filter(|it| !existing_derives.contains(it.label))
+    {
+        // Also add the traits the derive depends on, unless they are already
+        // derived, so that `Eq` completes to `Eq, PartialEq`.
+        let mut components = vec![derive_completion.label];
+        components.extend(
+            derive_completion
+                .dependencies
+                .iter()
+                .filter(|&&dependency| !existing_derives.contains(dependency)),
+        );
+        let label = components.join(", ");
+        CompletionItem::new(CompletionKind::Attribute, ctx.source_range(), label)
+            .kind(CompletionItemKind::Attribute)
+            .lookup_by(derive_completion.label)
+            .add_to(acc);
+    }
+}
+
+fn complete_from_list(
+    acc: &mut Completions,
+    ctx: &CompletionContext,
+    input: &ast::TokenTree,
+    completions: &[LintCompletion],
+) {
+    let existing = existing_names(ctx, input);
+    for completion in completions.iter().filter(|it| !existing.contains(it.label)) {
+        CompletionItem::new(CompletionKind::Attribute, ctx.source_range(), completion.label)
+            .kind(CompletionItemKind::Attribute)
+            .detail(completion.description)
+            .add_to(acc);
+    }
+}
+
+/// Collects the names already listed in `input`, except for the one being
+/// completed.
+fn existing_names(ctx: &CompletionContext, input: &ast::TokenTree) -> FxHashSet<String> {
+    input
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| it.kind() == SyntaxKind::IDENT)
+        .filter(|it| !it.text_range().contains_inclusive(ctx.offset))
+        .map(|it| it.text().to_string())
+        .collect()
+}
+
+struct DeriveCompletion {
+    label: &'static str,
+    dependencies: &'static [&'static str],
+}
+
+/// Standard Rust derives with the information about their dependencies
+/// (the dependencies are needed so that the main derive don't break the
+/// compilation when added)
+const DEFAULT_DERIVE_COMPLETIONS: &[DeriveCompletion] = &[
+    DeriveCompletion { label: "Clone", dependencies: &[] },
+    DeriveCompletion { label: "Copy", dependencies: &["Clone"] },
+    DeriveCompletion { label: "Debug", dependencies: &[] },
+    DeriveCompletion { label: "Default", dependencies: &[] },
+    DeriveCompletion { label: "Hash", dependencies: &[] },
+    DeriveCompletion { label: "PartialEq", dependencies: &[] },
+    DeriveCompletion { label: "Eq", dependencies: &["PartialEq"] },
+    DeriveCompletion { label: "PartialOrd", dependencies: &["PartialEq"] },
+    DeriveCompletion { label: "Ord", dependencies: &["PartialOrd", "Eq", "PartialEq"] },
+];
+
+struct LintCompletion {
+    label: &'static str,
+    description: &'static str,
+}
+
+#[rustfmt::skip]
+const DEFAULT_LINT_COMPLETIONS: &[LintCompletion] = &[
+    LintCompletion { label: "absolute_paths_not_starting_with_crate", description: r#"fully qualified paths that start with a module name instead of `crate`, `self`, or an extern crate name"# },
+    LintCompletion { label: "anonymous_parameters", description: r#"detects anonymous parameters"# },
+    LintCompletion { label: "box_pointers", description: r#"use of owned (Box type) heap memory"# },
+    LintCompletion { label: "deprecated_in_future", description: r#"detects use of items that will be deprecated in a future version"# },
+    LintCompletion { label: "elided_lifetimes_in_paths", description: r#"hidden lifetime parameters in types are deprecated"# },
+    LintCompletion { label: "explicit_outlives_requirements", description: r#"outlives requirements can be inferred"# },
+    LintCompletion { label: "keyword_idents", description: r#"detects edition keywords being used as an identifier"# },
+    LintCompletion { label: "macro_use_extern_crate", description: r#"the `#[macro_use]` attribute is now deprecated in favor of using macros via the module system"# },
+    LintCompletion { label: "meta_variable_misuse", description: r#"possible meta-variable misuse at macro definition"# },
+    LintCompletion { label: "missing_copy_implementations", description: r#"detects potentially-forgotten implementations of `Copy`"# },
+    LintCompletion { label: "missing_debug_implementations", description: r#"detects missing implementations of fmt::Debug"# },
+    LintCompletion { label: "missing_docs", description: r#"detects missing documentation for public members"# },
+    LintCompletion { label: "missing_doc_code_examples", description: r#"detects publicly-exported items without code samples in their documentation"# },
+    LintCompletion { label: "single_use_lifetimes", description: r#"detects lifetime parameters that are only used once"# },
+    LintCompletion { label: "trivial_casts", description: r#"detects trivial casts which could be removed"# },
+    LintCompletion { label: "trivial_numeric_casts", description: r#"detects trivial casts of numeric types which could be removed"# },
+    LintCompletion { label: "unreachable_pub", description: r#"`pub` items not reachable from crate root"# },
+    LintCompletion { label: "unsafe_code", description: r#"usage of `unsafe` code"# },
+    LintCompletion { label: "unused_extern_crates", description: r#"extern crates that are never used"# },
+    LintCompletion { label: "unused_import_braces", description: r#"unnecessary braces around an imported item"# },
+    LintCompletion { label: "unused_lifetimes", description: r#"detects lifetime parameters that are never used"# },
+    LintCompletion { label: "unused_qualifications", description: r#"detects unnecessarily qualified names"# },
+    LintCompletion { label: "unused_results", description: r#"unused result of an expression in a statement"# },
+    LintCompletion { label: "variant_size_differences", description: r#"detects enums with widely varying variant sizes"# },
+    LintCompletion { label: "array_into_iter", description: r#"detects calling `into_iter` on arrays"# },
+    LintCompletion { label: "bare_trait_objects", description: r#"suggest using `dyn Trait` for trait objects"# },
+    LintCompletion { label: "bindings_with_variant_name", description: r#"detects pattern bindings with the same name as one of the matched variants"# },
+    LintCompletion { label: "dead_code", description: r#"detect unused, unexported items"# },
+    LintCompletion { label: "deprecated", description: r#"detects use of deprecated items"# },
+    LintCompletion { label: "ellipsis_inclusive_range_patterns", description: r#"`...` range patterns are deprecated"# },
+    LintCompletion { label: "exported_private_dependencies", description: r#"public interface leaks type from a private dependency"# },
+    LintCompletion { label: "illegal_floating_point_literal_pattern", description: r#"floating-point literals cannot be used in patterns"# },
+    LintCompletion { label: "improper_ctypes", description: r#"proper use of libc types in foreign modules"# },
+    LintCompletion { label: "irrefutable_let_patterns", description: r#"detects irrefutable patterns in if-let and while-let statements"# },
+    LintCompletion { label: "late_bound_lifetime_arguments", description: r#"detects generic lifetime arguments in path segments with late bound lifetime parameters"# },
+    LintCompletion { label: "non_camel_case_types", description: r#"types, variants, traits and type parameters should have camel case names"# },
+    LintCompletion { label: "non_shorthand_field_patterns", description: r#"using `Struct { x: x }` instead of `Struct { x }` in a pattern"# },
+    LintCompletion { label: "non_snake_case", description: r#"variables, methods, functions, lifetime parameters and modules should have snake case names"# },
+    LintCompletion { label: "non_upper_case_globals", description: r#"static constants should have uppercase identifiers"# },
+    LintCompletion { label: "no_mangle_generic_items", description: r#"generic items must be mangled"# },
+    LintCompletion { label: "overlapping_patterns", description: r#"detects overlapping patterns"# },
+    LintCompletion { label: "path_statements", description: r#"path statements with no effect"# },
+    LintCompletion { label: "private_in_public", description: r#"detect private items in public interfaces not caught by the old implementation"# },
+    LintCompletion { label: "redundant_semicolon", description: r#"detects unnecessary trailing semicolons"# },
+    LintCompletion { label: "renamed_and_removed_lints", description: r#"lints that have been renamed or removed"# },
+    LintCompletion { label: "stable_features", description: r#"stable features found in `#[feature]` directive"# },
+    LintCompletion { label: "trivial_bounds", description: r#"these bounds don't depend on an type parameters"# },
+    LintCompletion { label: "type_alias_bounds", description: r#"bounds in type aliases are not enforced"# },
+    LintCompletion { label: "tyvar_behind_raw_pointer", description: r#"raw pointer to an inference variable"# },
+    LintCompletion { label: "unconditional_recursion", description: r#"functions that cannot return without calling themselves"# },
+    LintCompletion { label: "unknown_lints", description: r#"unrecognized lint attribute"# },
+    LintCompletion { label: "unnameable_test_items", description: r#"detects an item that cannot be named being marked as `#[test_case]`"# },
+    LintCompletion { label: "unreachable_code", description: r#"detects unreachable code paths"# },
+    LintCompletion { label: "unreachable_patterns", description: r#"detects unreachable patterns"# },
+    LintCompletion { label: "unstable_name_collisions", description: r#"detects name collision with an existing but unstable method"# },
+    LintCompletion { label: "unused_allocation", description: r#"detects unnecessary allocations that can be eliminated"# },
+    LintCompletion { label: "unused_assignments", description: r#"detect assignments that will never be read"# },
+    LintCompletion { label: "unused_attributes", description: r#"detects attributes that were not used by the compiler"# },
+    LintCompletion { label: "unused_comparisons", description: r#"comparisons made useless by limits of the types involved"# },
+    LintCompletion { label: "unused_doc_comments", description: r#"detects doc comments that aren't used by rustdoc"# },
+    LintCompletion { label: "unused_features", description: r#"unused features found in crate-level `#[feature]` directives"# },
+    LintCompletion { label: "unused_imports", description: r#"imports that are never used"# },
+    LintCompletion { label: "unused_labels", description: r#"detects labels that are never used"# },
+    LintCompletion { label: "unused_macros", description: r#"detects macros that were not used"# },
+    LintCompletion { label: "unused_must_use", description: r#"unused result of a type flagged as `#[must_use]`"# },
+    LintCompletion { label: "unused_mut", description: r#"detect mut variables which don't need to be mutable"# },
+    LintCompletion { label: "unused_parens", description: r#"`if`, `match`, `while` and `return` do not need parentheses"# },
+    LintCompletion { label: "unused_unsafe", description: r#"unnecessary use of an `unsafe` block"# },
+    LintCompletion { label: "unused_variables", description: r#"detect variables which are not used in any way"# },
+    LintCompletion { label: "where_clauses_object_safety", description: r#"checks the object safety of where clauses"# },
+    LintCompletion { label: "while_true", description: r#"suggest using `loop { }` instead of `while true { }`"# },
+    LintCompletion { label: "ambiguous_associated_items", description: r#"ambiguous associated items"# },
+    LintCompletion { label: "arithmetic_overflow", description: r#"arithmetic operation overflows"# },
+    LintCompletion { label: "conflicting_repr_hints", description: r#"conflicts between `#[repr(..)]` hints that were previously accepted and used in practice"# },
+    LintCompletion { label: "const_err", description: r#"constant evaluation detected erroneous expression"# },
+    LintCompletion { label: "ill_formed_attribute_input", description: r#"ill-formed attribute inputs that were previously accepted and used in practice"# },
+    LintCompletion { label: "invalid_type_param_default", description: r#"type parameter default erroneously allowed in invalid location"# },
+    LintCompletion { label: "macro_expanded_macro_exports_accessed_by_absolute_paths", description: r#"macro-expanded `macro_export` macros from the current crate cannot be referred to by absolute paths"# },
+    LintCompletion { label: "missing_fragment_specifier", description: r#"detects missing fragment specifiers in unused `macro_rules!` patterns"# },
+    LintCompletion { label: "mutable_transmutes", description: r#"mutating transmuted &mut T from &T may cause undefined behavior"# },
+    LintCompletion { label: "no_mangle_const_items", description: r#"const items will not have their symbols exported"# },
+    LintCompletion { label: "order_dependent_trait_objects", description: r#"trait-object types were treated as different depending on marker-trait order"# },
+    LintCompletion { label: "overflowing_literals", description: r#"literal out of range for its type"# },
+    LintCompletion { label: "patterns_in_fns_without_body", description: r#"patterns in functions without body were erroneously allowed"# },
+    LintCompletion { label: "pub_use_of_private_extern_crate", description: r#"detect public re-exports of private extern crates"# },
+    LintCompletion { label: "soft_unstable", description: r#"a feature gate that doesn't break dependent crates"# },
+    LintCompletion { label: "unconditional_panic", description: r#"operation will cause a panic at runtime"# },
+    LintCompletion { label: "unknown_crate_types", description: r#"unknown crate type found in `#[crate_type]` directive"# },
+    LintCompletion { label: "future_incompatible", description: r#"lint group for lints that will become hard errors in a future release"# },
+    LintCompletion { label: "nonstandard_style", description: r#"lint group for violations of the Rust naming conventions"# },
+    LintCompletion { label: "rust_2018_compatibility", description: r#"lint group for lints that will become hard errors in the 2018 edition"# },
+    LintCompletion { label: "rust_2018_idioms", description: r#"lint group for idioms that are discouraged in the 2018 edition"# },
+    LintCompletion { label: "unused", description: r#"lint group for lints that detect things being declared but not used, or excess syntax"# },
+    LintCompletion { label: "warnings", description: r#"all lints that are set to issue warnings"# },
+];
+
+#[rustfmt::skip]
+const DEFAULT_FEATURE_COMPLETIONS: &[LintCompletion] = &[
+    LintCompletion { label: "allocator_api", description: r#"the `Alloc` trait and custom allocators for collections"# },
+    LintCompletion { label: "arbitrary_self_types", description: r#"methods with arbitrary `self` types like `self: Rc<Self>`"# },
+    LintCompletion { label: "asm", description: r#"inline assembly with the `asm!` macro"# },
+    LintCompletion { label: "associated_type_defaults", description: r#"default values for associated types in traits"# },
+    LintCompletion { label: "async_closure", description: r#"`async` closures"# },
+    LintCompletion { label: "box_patterns", description: r#"`box` patterns"# },
+    LintCompletion { label: "box_syntax", description: r#"`box` expressions"# },
+    LintCompletion { label: "const_fn", description: r#"extended capabilities of `const fn`"# },
+    LintCompletion { label: "const_generics", description: r#"const generic parameters"# },
+    LintCompletion { label: "core_intrinsics", description: r#"compiler intrinsics in `core::intrinsics`"# },
+    LintCompletion { label: "crate_visibility_modifier", description: r#"the `crate` visibility modifier"# },
+    LintCompletion { label: "custom_test_frameworks", description: r#"custom test harnesses with `#[test_case]`"# },
+    LintCompletion { label: "decl_macro", description: r#"declarative macros 2.0 (`macro`)"# },
+    LintCompletion { label: "doc_cfg", description: r#"`#[doc(cfg(...))]` for documenting platform availability"# },
+    LintCompletion { label: "exclusive_range_pattern", description: r#"exclusive range patterns like `0..10`"# },
+    LintCompletion { label: "external_doc", description: r#"`#[doc(include = "...")]` for external documentation"# },
+    LintCompletion { label: "fn_traits", description: r#"implementing the `Fn*` traits manually"# },
+    LintCompletion { label: "generators", description: r#"generators and the `yield` expression"# },
+    LintCompletion { label: "global_asm", description: r#"module level inline assembly with the `global_asm!` macro"# },
+    LintCompletion { label: "impl_trait_in_bindings", description: r#"`impl Trait` in `let` bindings"# },
+    LintCompletion { label: "in_band_lifetimes", description: r#"lifetimes which are not declared in the generic parameters"# },
+    LintCompletion { label: "label_break_value", description: r#"`break` with a value out of labeled blocks"# },
+    LintCompletion { label: "naked_functions", description: r#"functions without a prologue or epilogue"# },
+    LintCompletion { label: "never_type", description: r#"the `!` type"# },
+    LintCompletion { label: "optin_builtin_traits", description: r#"auto traits and negative impls"# },
+    LintCompletion { label: "or_patterns", description: r#"nested `|` patterns"# },
+    LintCompletion { label: "plugin", description: r#"compiler plugins"# },
+    LintCompletion { label: "proc_macro_hygiene", description: r#"procedural macros in expression and statement position"# },
+    LintCompletion { label: "rustc_private", description: r#"the compiler's internal crates"# },
+    LintCompletion { label: "specialization", description: r#"overlapping impls with `default` items"# },
+    LintCompletion { label: "test", description: r#"the unstable `test` crate with benchmarks"# },
+    LintCompletion { label: "trait_alias", description: r#"`trait Alias = Bound;` declarations"# },
+    LintCompletion { label: "try_blocks", description: r#"`try` blocks"# },
+    LintCompletion { label: "type_alias_impl_trait", description: r#"`impl Trait` in type aliases"# },
+    LintCompletion { label: "unboxed_closures", description: r#"the `extern "rust-call"` ABI and `Fn*` trait sugar"# },
+    LintCompletion { label: "untagged_unions", description: r#"unions with fields that are not `Copy`"# },
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+
+    fn do_attr_completion(ra_fixture: &str) -> Vec<CompletionItem> {
+        do_completion(ra_fixture, CompletionKind::Attribute)
+    }
+
+    fn labels(completions: &[CompletionItem]) -> Vec<&str> {
+        completions.iter().map(|it| it.label()).collect()
+    }
+
+    #[test]
+    fn completes_outer_attributes() {
+        let completions = do_attr_completion(
+            r"
+            #[<|>]
+            struct Test {}
+            ",
+        );
+        let labels = labels(&completions);
+        assert!(labels.contains(&"derive(…)"));
+        assert!(labels.contains(&"must_use"));
+        assert!(!labels.contains(&"no_std"));
+
+        let derive = completions.iter().find(|it| it.label() == "derive(…)").unwrap();
+        assert_eq!(derive.lookup(), "derive");
+        assert_eq!(derive.text_edit().as_atoms()[0].insert, "derive(${0:Debug})");
+    }
+
+    #[test]
+    fn completes_inner_attributes() {
+        let completions = do_attr_completion(
+            r"
+            #![<|>]
+            ",
+        );
+        let labels = labels(&completions);
+        assert!(labels.contains(&"no_std"));
+        assert!(labels.contains(&"feature(…)"));
+        assert!(labels.contains(&"allow(…)"));
+    }
+
+    #[test]
+    fn does_not_complete_scope_in_attributes() {
+        let completions = do_completion(
+            r"
+            struct Derive;
+            #[de<|>]
+            struct Test {}
+            ",
+            CompletionKind::Reference,
+        );
+        assert!(completions.is_empty(), "{:#?}", completions);
+    }
+
+    #[test]
+    fn completes_derives() {
+        let completions = do_attr_completion(
+            r"
+            #[derive(<|>)]
+            struct Test {}
+            ",
+        );
+        assert_eq!(
+            labels(&completions),
+            vec![
+                "Clone",
+                "Copy, Clone",
+                "Debug",
+                "Default",
+                "Eq, PartialEq",
+                "Hash",
+                "Ord, PartialOrd, Eq, PartialEq",
+                "PartialEq",
+                "PartialOrd, PartialEq",
+            ]
+        );
+    }
+
+    #[test]
+    fn completes_derives_skipping_existing_ones() {
+        let completions = do_attr_completion(
+            r"
+            #[derive(PartialEq, Clone, <|>)]
+            struct Test {}
+            ",
+        );
+        assert_eq!(
+            labels(&completions),
+            vec!["Copy", "Debug", "Default", "Eq", "Hash", "Ord, PartialOrd, Eq", "PartialOrd"]
+        );
+    }
+
+    #[test]
+    fn does_not_complete_macros_in_derive() {
+        let completions = do_attr_completion(
+            r"
+            macro_rules! Serialize { () => {} }
+
+            #[derive(<|>)]
+            struct Test {}
+            ",
+        );
+        assert!(!labels(&completions).contains(&"Serialize"));
+    }
+
+    #[test]
+    fn completes_lints() {
+        let completions = do_attr_completion(
+            r"
+            #[allow(dead_code, unused_<|>)]
+            struct Test {}
+            ",
+        );
+        let labels = labels(&completions);
+        assert!(labels.contains(&"unused_variables"));
+        assert!(labels.contains(&"non_snake_case"));
+        assert!(!labels.contains(&"dead_code"));
+    }
+
+    #[test]
+    fn completes_features() {
+        let completions = do_attr_completion(
+            r"
+            #![feature(<|>)]
+            ",
+        );
+        let labels = labels(&completions);
+        assert!(labels.contains(&"never_type"));
+        assert!(!labels.contains(&"unused_variables"));
+    }
+}
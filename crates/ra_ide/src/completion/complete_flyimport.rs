@@ -0,0 +1,166 @@
+//! Completion of items which are not yet in scope, adding the `use` for them
+//! when the completion is accepted ("flyimport").
+
+use hir::{ModuleDef, ScopeDef};
+use ra_assists::utils::insert_use_statement;
+use ra_ide_db::imports_locator::ImportsLocator;
+use ra_text_edit::TextEditBuilder;
+use rustc_hash::FxHashSet;
+
+use crate::completion::{CompletionContext, Completions};
+
+/// Searching the whole world is expensive, so we only do it once the user
+/// has typed a couple of characters, and cap the number of candidates.
+const MIN_NAME_LEN: usize = 2;
+const MAX_CANDIDATES: usize = 40;
+
+pub(super) fn complete_flyimport(acc: &mut Completions, ctx: &CompletionContext) {
+    if !ctx.options.enable_flyimport_completions
+        || !ctx.is_trivial_path
+        || ctx.use_item_syntax.is_some()
+    {
+        return;
+    }
+    let name_ref = match &ctx.name_ref_syntax {
+        Some(it) => it,
+        None => return,
+    };
+    let potential_import_name = name_ref.text().to_string();
+    if potential_import_name.chars().count() < MIN_NAME_LEN {
+        return;
+    }
+    let current_module = match ctx.scope().module() {
+        Some(it) => it,
+        None => return,
+    };
+
+    let mut in_scope = FxHashSet::default();
+    ctx.scope().process_all_names(&mut |_, res| {
+        if let ScopeDef::ModuleDef(def) = res {
+            in_scope.insert(def);
+        }
+    });
+
+    let mut seen = FxHashSet::default();
+    let candidates = ImportsLocator::new(ctx.db)
+        .find_similar_imports(&potential_import_name, MAX_CANDIDATES)
+        .into_iter()
+        .filter(|def| !in_scope.contains(def) && seen.insert(*def));
+
+    for def in candidates {
+        let import_path = match current_module.find_use_path(ctx.db, def) {
+            Some(it) if it.segments.len() > 1 => it,
+            _ => continue,
+        };
+        let name = match def.name(ctx.db) {
+            Some(it) => it.to_string(),
+            None => continue,
+        };
+
+        let mut import_edit = TextEditBuilder::default();
+        insert_use_statement(&ctx.original_token.parent(), &import_path, &mut import_edit);
+        let import_edit = import_edit.finish();
+
+        let mut items = Completions::default();
+        items.add_resolution(ctx, name, &ScopeDef::ModuleDef(def));
+        let items: Vec<_> = items.into();
+        for item in items {
+            if let Some(item) = item.with_import(&import_path.to_string(), import_edit.clone()) {
+                acc.add(item);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{
+        test_utils::{do_completion, do_completion_with_options},
+        CompletionItem, CompletionKind, CompletionOptions,
+    };
+
+    fn do_import_completion(ra_fixture: &str) -> Vec<CompletionItem> {
+        do_completion(ra_fixture, CompletionKind::Import)
+    }
+
+    fn inserts_use(item: &CompletionItem, use_item: &str) -> bool {
+        item.text_edit().as_atoms().iter().any(|atom| atom.insert.contains(use_item))
+    }
+
+    #[test]
+    fn completes_not_imported_items() {
+        let completions = do_import_completion(
+            r"
+            //- /main.rs
+            fn main() {
+                ThirdStr<|>
+            }
+
+            //- /foo/lib.rs
+            pub mod bar {
+                pub struct ThirdStruct;
+            }
+            ",
+        );
+        let item = completions
+            .iter()
+            .find(|it| it.label() == "ThirdStruct (use foo::bar::ThirdStruct)")
+            .expect("no flyimport completion");
+        assert_eq!(item.lookup(), "ThirdStruct");
+        assert!(inserts_use(item, "use foo::bar::ThirdStruct;"));
+    }
+
+    #[test]
+    fn does_not_complete_items_in_scope() {
+        let completions = do_import_completion(
+            r"
+            //- /main.rs
+            use foo::bar::ThirdStruct;
+
+            fn main() {
+                ThirdStr<|>
+            }
+
+            //- /foo/lib.rs
+            pub mod bar {
+                pub struct ThirdStruct;
+            }
+            ",
+        );
+        assert!(completions.is_empty(), "{:#?}", completions);
+    }
+
+    #[test]
+    fn does_not_complete_short_names() {
+        let completions = do_import_completion(
+            r"
+            //- /main.rs
+            fn main() {
+                T<|>
+            }
+
+            //- /foo/lib.rs
+            pub struct ThirdStruct;
+            ",
+        );
+        assert!(completions.is_empty(), "{:#?}", completions);
+    }
+
+    #[test]
+    fn respects_the_option() {
+        let completions = do_completion_with_options(
+            r"
+            //- /main.rs
+            fn main() {
+                ThirdStr<|>
+            }
+
+            //- /foo/lib.rs
+            pub struct ThirdStruct;
+            ",
+            CompletionKind::Import,
+            &CompletionOptions { enable_flyimport_completions: false, ..Default::default() },
+        );
+        assert!(completions.is_empty(), "{:#?}", completions);
+    }
+}
@@ -5,6 +5,7 @@ use ra_text_edit::TextEdit;
 
 use crate::{
     completion::{
+        complete_snippet::custom_snippet_edit,
         completion_context::CompletionContext,
         completion_item::{Builder, CompletionKind, Completions},
     },
@@ -63,30 +64,63 @@ pub(super) fn complete_postfix(acc: &mut Completions, ctx: &CompletionContext) {
 
     postfix_snippet(ctx, "box", "Box::new(expr)", &format!("Box::new({})", receiver_text))
         .add_to(acc);
+
+    for custom in ctx.options.snippets.iter().filter(|it| it.postfix) {
+        let body = custom.body.replace("${receiver}", &receiver_text);
+        let edit = match custom_snippet_edit(ctx, postfix_range(ctx), &body, &custom.requires) {
+            Some(it) => it,
+            None => continue,
+        };
+        CompletionItem::new(CompletionKind::Postfix, ctx.source_range(), custom.label.clone())
+            .set_detail(custom.description.clone())
+            .snippet_edit(edit)
+            .add_to(acc);
+    }
 }
 
 fn postfix_snippet(ctx: &CompletionContext, label: &str, detail: &str, snippet: &str) -> Builder {
-    let edit = {
-        let receiver_syntax = ctx.dot_receiver.as_ref().expect("no receiver available").syntax();
-        let receiver_range = ctx.sema.original_range(receiver_syntax).range;
-        let delete_range = TextRange::from_to(receiver_range.start(), ctx.source_range().end());
-        TextEdit::replace(delete_range, snippet.to_string())
-    };
+    let edit = TextEdit::replace(postfix_range(ctx), snippet.to_string());
     CompletionItem::new(CompletionKind::Postfix, ctx.source_range(), label)
         .detail(detail)
         .snippet_edit(edit)
 }
 
+/// The range replaced by a postfix snippet, from the receiver to the cursor.
+fn postfix_range(ctx: &CompletionContext) -> TextRange {
+    let receiver_syntax = ctx.dot_receiver.as_ref().expect("no receiver available").syntax();
+    let receiver_range = ctx.sema.original_range(receiver_syntax).range;
+    TextRange::from_to(receiver_range.start(), ctx.source_range().end())
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
 
-    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+    use crate::completion::{
+        test_utils::{do_completion, do_completion_with_options},
+        CompletionItem, CompletionKind, CompletionOptions, Snippet, SnippetScope,
+    };
 
     fn do_postfix_completion(code: &str) -> Vec<CompletionItem> {
         do_completion(code, CompletionKind::Postfix)
     }
 
+    fn do_custom_postfix_completion(code: &str, snippets: Vec<Snippet>) -> Vec<CompletionItem> {
+        let options = CompletionOptions { snippets, ..Default::default() };
+        do_completion_with_options(code, CompletionKind::Postfix, &options)
+    }
+
+    fn postfix(label: &str, body: &str, requires: &[&str]) -> Snippet {
+        Snippet {
+            label: label.to_string(),
+            description: None,
+            body: body.to_string(),
+            postfix: true,
+            scope: SnippetScope::Expr,
+            requires: requires.iter().map(|it| it.to_string()).collect(),
+        }
+    }
+
     #[test]
     fn postfix_completion_works_for_trivial_path_expression() {
         assert_debug_snapshot!(
@@ -340,4 +374,39 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn completes_custom_postfix_snippets() {
+        let completions = do_custom_postfix_completion(
+            r#"
+            //- /main.rs
+            fn main() {
+                let bar = 92;
+                bar.<|>
+            }
+
+            //- /std/lib.rs
+            pub mod sync {
+                pub struct Arc<T>(T);
+            }
+            "#,
+            vec![
+                postfix("arc", "Arc::new(${receiver})", &["std::sync::Arc"]),
+                postfix("ok", "Ok(${receiver})", &[]),
+                postfix("rc", "Rc::new(${receiver})", &["std::rc::Rc"]),
+            ],
+        );
+
+        let ok = completions.iter().find(|it| it.label() == "ok").unwrap();
+        assert_eq!(ok.text_edit().as_atoms().len(), 1);
+        assert_eq!(ok.text_edit().as_atoms()[0].insert, "Ok(bar)");
+
+        let arc = completions.iter().find(|it| it.label() == "arc").unwrap();
+        let inserts: Vec<_> = arc.text_edit().as_atoms().iter().map(|it| &it.insert).collect();
+        assert!(inserts.iter().any(|it| it.contains("use std::sync::Arc;")), "{:?}", inserts);
+        assert!(inserts.iter().any(|it| it.as_str() == "Arc::new(bar)"), "{:?}", inserts);
+
+        // `std::rc::Rc` doesn't exist, so the snippet is not offered.
+        assert!(completions.iter().all(|it| it.label() != "rc"));
+    }
 }
@@ -1,8 +1,16 @@
 //! FIXME: write short doc here
 
-use crate::completion::{
-    completion_item::Builder, CompletionContext, CompletionItem, CompletionItemKind,
-    CompletionKind, Completions,
+use hir::PathResolution;
+use ra_assists::utils::insert_use_statement;
+use ra_syntax::TextRange;
+use ra_text_edit::{TextEdit, TextEditBuilder};
+
+use crate::{
+    completion::{
+        completion_item::Builder, CompletionContext, CompletionItem, CompletionItemKind,
+        CompletionKind, Completions, SnippetScope,
+    },
+    doc_links::parse_path,
 };
 
 fn snippet(ctx: &CompletionContext, label: &str, snippet: &str) -> Builder {
@@ -18,12 +26,24 @@ pub(super) fn complete_expr_snippet(acc: &mut Completions, ctx: &CompletionConte
 
     snippet(ctx, "pd", "eprintln!(\"$0 = {:?}\", $0);").add_to(acc);
     snippet(ctx, "ppd", "eprintln!(\"$0 = {:#?}\", $0);").add_to(acc);
+
+    if !ctx.is_path_type {
+        add_custom_snippets(acc, ctx, SnippetScope::Expr);
+    }
+}
+
+pub(super) fn complete_type_snippet(acc: &mut Completions, ctx: &CompletionContext) {
+    if !(ctx.is_trivial_path && ctx.is_path_type) {
+        return;
+    }
+    add_custom_snippets(acc, ctx, SnippetScope::Type);
 }
 
 pub(super) fn complete_item_snippet(acc: &mut Completions, ctx: &CompletionContext) {
     if !ctx.is_new_item {
         return;
     }
+    add_custom_snippets(acc, ctx, SnippetScope::Item);
     snippet(
         ctx,
         "Test function",
@@ -40,15 +60,88 @@ fn ${1:feature}() {
     snippet(ctx, "pub(crate)", "pub(crate) $0").add_to(acc);
 }
 
+fn add_custom_snippets(acc: &mut Completions, ctx: &CompletionContext, scope: SnippetScope) {
+    for custom in ctx.options.snippets.iter().filter(|it| !it.postfix && it.scope == scope) {
+        let edit =
+            match custom_snippet_edit(ctx, ctx.source_range(), &custom.body, &custom.requires) {
+                Some(it) => it,
+                None => continue,
+            };
+        CompletionItem::new(CompletionKind::Snippet, ctx.source_range(), custom.label.clone())
+            .kind(CompletionItemKind::Snippet)
+            .set_detail(custom.description.clone())
+            .snippet_edit(edit)
+            .add_to(acc);
+    }
+}
+
+/// Builds the edit for a user-defined snippet: `body` replaces `range`, and
+/// `use` items are added for the paths the snippet `requires`.
+///
+/// Returns `None` if one of the required paths doesn't resolve to an item.
+pub(super) fn custom_snippet_edit(
+    ctx: &CompletionContext,
+    range: TextRange,
+    body: &str,
+    requires: &[String],
+) -> Option<TextEdit> {
+    let mut builder = TextEditBuilder::default();
+    builder.replace(range, body.to_string());
+    if requires.is_empty() {
+        return Some(builder.finish());
+    }
+
+    let scope = ctx.scope();
+    let module = scope.module()?;
+    for path in requires {
+        let def = match scope.resolve_hir_path(&parse_path(path)?)? {
+            PathResolution::Def(def) => def,
+            _ => return None,
+        };
+        let use_path = module.find_use_path(ctx.db, def)?;
+        if use_path.segments.len() > 1 {
+            insert_use_statement(&ctx.original_token.parent(), &use_path, &mut builder);
+        }
+    }
+    Some(builder.finish())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+    use crate::completion::{
+        test_utils::{do_completion, do_completion_with_options},
+        CompletionItem, CompletionKind, CompletionOptions, Snippet, SnippetScope,
+    };
     use insta::assert_debug_snapshot;
 
     fn do_snippet_completion(code: &str) -> Vec<CompletionItem> {
         do_completion(code, CompletionKind::Snippet)
     }
 
+    fn custom_snippet_labels(code: &str) -> Vec<String> {
+        let snippet = |label: &str, scope| Snippet {
+            label: label.to_string(),
+            description: None,
+            body: format!("{}$0", label),
+            postfix: false,
+            scope,
+            requires: Vec::new(),
+        };
+        let options = CompletionOptions {
+            snippets: vec![
+                snippet("my_expr", SnippetScope::Expr),
+                snippet("my_item", SnippetScope::Item),
+                snippet("my_type", SnippetScope::Type),
+            ],
+            ..Default::default()
+        };
+        do_completion_with_options(code, CompletionKind::Snippet, &options)
+            .into_iter()
+            .map(|it| it.label().to_string())
+            .filter(|it| it.starts_with("my_"))
+            .collect()
+    }
+
     #[test]
     fn completes_snippets_in_expressions() {
         assert_debug_snapshot!(
@@ -125,4 +218,11 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn completes_custom_snippets_in_their_scope() {
+        assert_eq!(custom_snippet_labels(r"fn foo(x: i32) { <|> }"), vec!["my_expr"]);
+        assert_eq!(custom_snippet_labels(r"fn foo(x: i32) { let x: <|> }"), vec!["my_type"]);
+        assert_eq!(custom_snippet_labels(r"mod tests { <|> }"), vec!["my_item"]);
+    }
 }
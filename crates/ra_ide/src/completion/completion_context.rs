@@ -1,17 +1,18 @@
 //! FIXME: write short doc here
 
-use hir::{Semantics, SemanticsScope};
+use hir::{Semantics, SemanticsScope, Type};
 use ra_db::SourceDatabase;
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
     algo::{find_covering_element, find_node_at_offset},
-    ast, AstNode,
+    ast::{self, TypeAscriptionOwner},
+    AstNode,
     SyntaxKind::*,
     SyntaxNode, SyntaxToken, TextRange, TextUnit,
 };
 use ra_text_edit::AtomTextEdit;
 
-use crate::{completion::CompletionOptions, FilePosition};
+use crate::{completion::CompletionOptions, display::FunctionSignature, FilePosition};
 
 /// `CompletionContext` is created early during completion to figure out, where
 /// exactly is the cursor, syntax-wise.
@@ -32,6 +33,8 @@ pub(crate) struct CompletionContext<'a> {
     pub(super) record_lit_syntax: Option<ast::RecordLit>,
     pub(super) record_lit_pat: Option<ast::RecordPat>,
     pub(super) impl_def: Option<ast::ImplDef>,
+    /// The attribute the cursor is in, e.g. `#[deri<|>]` or `#[allow(<|>)]`.
+    pub(super) attribute_under_caret: Option<ast::Attr>,
     pub(super) is_param: bool,
     /// If a name-binding or reference to a const in a pattern.
     /// Irrefutable patterns (like let) are excluded.
@@ -52,6 +55,12 @@ pub(crate) struct CompletionContext<'a> {
     pub(super) is_call: bool,
     pub(super) is_path_type: bool,
     pub(super) has_type_args: bool,
+    /// The type expected at the cursor, e.g. the type of the parameter if we
+    /// are completing a function argument.
+    pub(super) expected_type: Option<Type>,
+    /// The name of the thing with the expected type (binding, parameter or
+    /// field), if any.
+    pub(super) expected_name: Option<String>,
 }
 
 impl<'a> CompletionContext<'a> {
@@ -93,6 +102,7 @@ impl<'a> CompletionContext<'a> {
             record_lit_syntax: None,
             record_lit_pat: None,
             impl_def: None,
+            attribute_under_caret: None,
             is_param: false,
             is_pat_binding: false,
             is_trivial_path: false,
@@ -105,6 +115,8 @@ impl<'a> CompletionContext<'a> {
             is_path_type: false,
             has_type_args: false,
             dot_receiver_is_ambiguous_float_literal: false,
+            expected_type: None,
+            expected_name: None,
         };
 
         let mut original_file = original_file.syntax().clone();
@@ -171,6 +183,13 @@ impl<'a> CompletionContext<'a> {
         file_with_fake_ident: SyntaxNode,
         offset: TextUnit,
     ) {
+        // Attributes are completed from static metadata only, see
+        // `complete_attribute`.
+        self.attribute_under_caret = find_node_at_offset(original_file, offset);
+        if self.attribute_under_caret.is_some() {
+            return;
+        }
+
         // First, let's try to complete a reference to some declaration.
         if let Some(name_ref) = find_node_at_offset::<ast::NameRef>(&file_with_fake_ident, offset) {
             // Special case, `trait T { fn foo(i_am_a_name_ref) {} }`.
@@ -265,6 +284,10 @@ impl<'a> CompletionContext<'a> {
             self.is_path_type = path.syntax().parent().and_then(ast::PathType::cast).is_some();
             self.has_type_args = segment.type_arg_list().is_some();
 
+            if let Some(path_expr) = path.syntax().parent().and_then(ast::PathExpr::cast) {
+                self.fill_expected_type(original_file, &ast::Expr::from(path_expr));
+            }
+
             if let Some(path) = hir::Path::from_ast(path.clone()) {
                 if let Some(path_prefix) = path.qualifier() {
                     self.path_prefix = Some(path_prefix);
@@ -335,6 +358,141 @@ impl<'a> CompletionContext<'a> {
             self.is_call = true;
         }
     }
+
+    fn fill_expected_type(&mut self, original_file: &SyntaxNode, expr: &ast::Expr) {
+        let parent = match expr.syntax().parent() {
+            Some(it) => it,
+            None => return,
+        };
+        // Everything we look at in the original file starts before the cursor,
+        // so it has the same range as in the file with the fake ident.
+        let (ty, name) = if let Some(let_stmt) = ast::LetStmt::cast(parent.clone()) {
+            // Without an annotation, the type of the binding is inferred from
+            // the very expression being completed, so nothing is expected.
+            if let_stmt.ascribed_type().is_none() {
+                return;
+            }
+            let pat = match let_stmt.pat() {
+                Some(it) => it,
+                None => return,
+            };
+            let name = match &pat {
+                ast::Pat::BindPat(bind_pat) => bind_pat.name().map(|it| it.text().to_string()),
+                _ => None,
+            };
+            let pat: ast::Pat = match find_node_with_range(original_file, pat.syntax().text_range())
+            {
+                Some(it) => it,
+                None => return,
+            };
+            (self.sema.type_of_pat(&pat), name)
+        } else if let Some(arg_list) = ast::ArgList::cast(parent.clone()) {
+            let idx = match arg_list.args().position(|arg| arg.syntax() == expr.syntax()) {
+                Some(it) => it,
+                None => return,
+            };
+            match arg_list.syntax().parent() {
+                Some(it) => match self.expected_argument(original_file, &it, idx) {
+                    Some(it) => it,
+                    None => return,
+                },
+                None => return,
+            }
+        } else if let Some(record_field) = ast::RecordField::cast(parent.clone()) {
+            let field_name = match record_field.name_ref() {
+                Some(it) => it,
+                None => return,
+            };
+            let record_field: ast::RecordField =
+                match find_node_with_range(original_file, field_name.syntax().text_range()) {
+                    Some(it) => it,
+                    None => return,
+                };
+            match self.sema.resolve_record_field(&record_field) {
+                Some((field, _)) => {
+                    (Some(field.signature_ty(self.db)), Some(field.name(self.db).to_string()))
+                }
+                None => return,
+            }
+        } else if is_return_value(expr, &parent) {
+            // There is nothing interesting to rank for functions returning `()`.
+            let function = self
+                .function_syntax
+                .as_ref()
+                .filter(|it| it.ret_type().is_some())
+                .and_then(|it| self.sema.to_def(it));
+            (function.map(|it| it.ret_type(self.db)), None)
+        } else {
+            return;
+        };
+
+        self.expected_type = ty.filter(|it| !it.is_unknown());
+        self.expected_name = name;
+    }
+
+    fn expected_argument(
+        &self,
+        original_file: &SyntaxNode,
+        call: &SyntaxNode,
+        idx: usize,
+    ) -> Option<(Option<Type>, Option<String>)> {
+        if let Some(call) = ast::CallExpr::cast(call.clone()) {
+            let callee: ast::Expr =
+                find_node_with_range(original_file, call.expr()?.syntax().text_range())?;
+            let callee_ty = self.sema.type_of_expr(&callee)?;
+            let ty = callee_ty.callable_params(self.db)?.into_iter().nth(idx);
+            let name = match callee_ty.as_callable() {
+                Some(hir::CallableDef::FunctionId(it)) => {
+                    FunctionSignature::from_hir(self.db, it.into())
+                        .parameter_names
+                        .get(idx)
+                        .cloned()
+                }
+                _ => None,
+            };
+            return Some((ty, name));
+        }
+        if let Some(method_call) = ast::MethodCallExpr::cast(call.clone()) {
+            let method_name = method_call.name_ref()?;
+            let method_call: ast::MethodCallExpr =
+                find_node_with_range(original_file, method_name.syntax().text_range())?;
+            let method = self.sema.resolve_method_call(&method_call)?;
+            // The receiver is the first parameter.
+            let idx = idx + 1;
+            let ty = method.param_types(self.db).into_iter().nth(idx);
+            let name =
+                FunctionSignature::from_hir(self.db, method).parameter_names.get(idx).cloned();
+            return Some((ty, name));
+        }
+        None
+    }
+}
+
+/// Checks if `expr` is the operand of a `return` or the tail expression of a
+/// function body (but not of a closure inside it).
+fn is_return_value(expr: &ast::Expr, parent: &SyntaxNode) -> bool {
+    if ast::ReturnExpr::cast(parent.clone()).is_none() {
+        let block = match ast::Block::cast(parent.clone()) {
+            Some(it) => it,
+            None => return false,
+        };
+        if block.expr().as_ref() != Some(expr) {
+            return false;
+        }
+        let is_fn_body = block
+            .syntax()
+            .parent()
+            .and_then(|it| it.parent())
+            .map_or(false, |it| it.kind() == FN_DEF);
+        if !is_fn_body {
+            return false;
+        }
+    }
+    !expr
+        .syntax()
+        .ancestors()
+        .take_while(|it| it.kind() != FN_DEF)
+        .any(|it| it.kind() == LAMBDA_EXPR)
 }
 
 fn find_node_with_range<N: AstNode>(syntax: &SyntaxNode, range: TextRange) -> Option<N> {
@@ -4,7 +4,7 @@ use std::fmt;
 
 use hir::Documentation;
 use ra_syntax::TextRange;
-use ra_text_edit::TextEdit;
+use ra_text_edit::{TextEdit, TextEditBuilder};
 
 /// `CompletionItem` describes a single completion variant in the editor pop-up.
 /// It is basically a POD with various properties. To construct a
@@ -51,6 +51,10 @@ pub struct CompletionItem {
     /// If completing a function call, ask the editor to show parameter popup
     /// after completion.
     trigger_call_info: bool,
+
+    /// Score is useful to pre select or display in better order completion
+    /// items.
+    score: Option<CompletionScore>,
 }
 
 // We use custom debug for CompletionItem to make `insta`'s diffs more readable.
@@ -83,10 +87,22 @@ impl fmt::Debug for CompletionItem {
         if self.trigger_call_info {
             s.field("trigger_call_info", &true);
         }
+        if let Some(score) = &self.score {
+            s.field("score", score);
+        }
         s.finish()
     }
 }
 
+/// How well an item fits the type (and the name) expected at the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompletionScore {
+    /// The type of the item matches the expected type.
+    TypeMatch,
+    /// Both the type and the name of the item match the expected ones.
+    TypeAndNameMatch,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompletionItemKind {
     Snippet,
@@ -106,6 +122,7 @@ pub enum CompletionItemKind {
     Method,
     TypeParam,
     Macro,
+    Attribute,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -119,6 +136,9 @@ pub(crate) enum CompletionKind {
     Snippet,
     Postfix,
     BuiltinType,
+    /// Items which are not in scope yet and are imported on completion.
+    Import,
+    Attribute,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -147,6 +167,7 @@ impl CompletionItem {
             text_edit: None,
             deprecated: None,
             trigger_call_info: None,
+            score: None,
         }
     }
     /// What user sees in pop-up in the UI.
@@ -189,6 +210,42 @@ impl CompletionItem {
     pub fn trigger_call_info(&self) -> bool {
         self.trigger_call_info
     }
+
+    pub fn score(&self) -> Option<CompletionScore> {
+        self.score
+    }
+
+    /// Turns this item into a completion of a not yet imported item, which also
+    /// applies `import_edit` (usually an insertion of a `use` statement).
+    ///
+    /// Returns `None` if `import_edit` overlaps with the completion edit.
+    pub(crate) fn with_import(
+        mut self,
+        import_path: &str,
+        import_edit: TextEdit,
+    ) -> Option<CompletionItem> {
+        let mut builder = TextEditBuilder::default();
+        for atom in self.text_edit.as_atoms() {
+            let overlaps = import_edit.as_atoms().iter().any(|import_atom| {
+                import_atom.delete.start() < atom.delete.end()
+                    && atom.delete.start() < import_atom.delete.end()
+            });
+            if overlaps {
+                return None;
+            }
+            builder.replace(atom.delete, atom.insert.clone());
+        }
+        for atom in import_edit.as_atoms() {
+            builder.replace(atom.delete, atom.insert.clone());
+        }
+        self.text_edit = builder.finish();
+        if self.lookup.is_none() {
+            self.lookup = Some(self.label.clone());
+        }
+        self.label = format!("{} (use {})", self.label, import_path);
+        self.completion_kind = CompletionKind::Import;
+        Some(self)
+    }
 }
 
 /// A helper to make `CompletionItem`s.
@@ -206,6 +263,7 @@ pub(crate) struct Builder {
     text_edit: Option<TextEdit>,
     deprecated: Option<bool>,
     trigger_call_info: Option<bool>,
+    score: Option<CompletionScore>,
 }
 
 impl Builder {
@@ -235,6 +293,7 @@ impl Builder {
             completion_kind: self.completion_kind,
             deprecated: self.deprecated.unwrap_or(false),
             trigger_call_info: self.trigger_call_info.unwrap_or(false),
+            score: self.score,
         }
     }
     pub(crate) fn lookup_by(mut self, lookup: impl Into<String>) -> Builder {
@@ -289,6 +348,10 @@ impl Builder {
         self.trigger_call_info = Some(true);
         self
     }
+    pub(crate) fn set_score(mut self, score: Option<CompletionScore>) -> Builder {
+        self.score = score;
+        self
+    }
 }
 
 impl<'a> Into<CompletionItem> for Builder {
@@ -6,7 +6,8 @@ use ra_syntax::ast::NameOwner;
 use test_utils::tested_by;
 
 use crate::completion::{
-    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
+    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, CompletionScore,
+    Completions,
 };
 
 use crate::{
@@ -22,16 +23,14 @@ impl Completions {
         ty: &Type,
     ) {
         let is_deprecated = is_deprecated(field, ctx.db);
-        CompletionItem::new(
-            CompletionKind::Reference,
-            ctx.source_range(),
-            field.name(ctx.db).to_string(),
-        )
-        .kind(CompletionItemKind::Field)
-        .detail(ty.display(ctx.db).to_string())
-        .set_documentation(field.docs(ctx.db))
-        .set_deprecated(is_deprecated)
-        .add_to(self);
+        let name = field.name(ctx.db).to_string();
+        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.clone())
+            .kind(CompletionItemKind::Field)
+            .detail(ty.display(ctx.db).to_string())
+            .set_documentation(field.docs(ctx.db))
+            .set_deprecated(is_deprecated)
+            .set_score(compute_score(ctx, ty, &name))
+            .add_to(self);
     }
 
     pub(crate) fn add_tuple_field(&mut self, ctx: &CompletionContext, field: usize, ty: &Type) {
@@ -104,6 +103,7 @@ impl Completions {
             if !ty.is_unknown() {
                 completion_item = completion_item.detail(ty.display(ctx.db).to_string());
             }
+            completion_item = completion_item.set_score(compute_score(ctx, &ty, &local_name));
         };
 
         // Add `<>` for generic types
@@ -209,7 +209,8 @@ impl Completions {
                 })
                 .set_documentation(func.docs(ctx.db))
                 .set_deprecated(is_deprecated(func, ctx.db))
-                .detail(function_signature.to_string());
+                .detail(function_signature.to_string())
+                .set_score(compute_score(ctx, &func.ret_type(ctx.db), &name));
 
         // If not an import, add parenthesis automatically.
         if ctx.use_item_syntax.is_none() && !ctx.is_call && ctx.options.add_call_parenthesis {
@@ -303,6 +304,20 @@ impl Completions {
     }
 }
 
+/// Scores an item of type `ty` named `name` against the type (and name)
+/// expected at the cursor.
+fn compute_score(ctx: &CompletionContext, ty: &Type, name: &str) -> Option<CompletionScore> {
+    let expected_type = ctx.expected_type.as_ref()?;
+    if !ty.is_same_type(expected_type) {
+        return None;
+    }
+    if ctx.expected_name.as_ref().map_or(false, |it| it == name) {
+        Some(CompletionScore::TypeAndNameMatch)
+    } else {
+        Some(CompletionScore::TypeMatch)
+    }
+}
+
 fn is_deprecated(node: impl HasAttrs, db: &RootDatabase) -> bool {
     node.attrs(db).by_key("deprecated").exists()
 }
@@ -314,13 +329,20 @@ mod tests {
 
     use crate::completion::{
         test_utils::{do_completion, do_completion_with_options},
-        CompletionItem, CompletionKind, CompletionOptions,
+        CompletionItem, CompletionKind, CompletionOptions, CompletionScore,
     };
 
     fn do_reference_completion(ra_fixture: &str) -> Vec<CompletionItem> {
         do_completion(ra_fixture, CompletionKind::Reference)
     }
 
+    fn scored_items(ra_fixture: &str) -> Vec<(String, CompletionScore)> {
+        do_reference_completion(ra_fixture)
+            .into_iter()
+            .filter_map(|it| Some((it.label().to_string(), it.score()?)))
+            .collect()
+    }
+
     fn do_reference_completion_with_options(
         ra_fixture: &str,
         options: CompletionOptions,
@@ -850,4 +872,105 @@ mod tests {
         "###
         )
     }
+
+    #[test]
+    fn scores_function_arguments() {
+        let scored = scored_items(
+            r"
+            struct A;
+            struct B;
+            fn foo(a: A, b: B) {}
+            fn main() {
+                let a = A;
+                let b = B;
+                foo(a, <|>)
+            }
+            ",
+        );
+        assert_eq!(scored, vec![("b".to_string(), CompletionScore::TypeAndNameMatch)]);
+    }
+
+    #[test]
+    fn scores_method_arguments() {
+        let scored = scored_items(
+            r"
+            struct A;
+            struct B;
+            impl A {
+                fn foo(&self, bar: B) {}
+            }
+            fn main() {
+                let a = A;
+                let b = B;
+                a.foo(<|>)
+            }
+            ",
+        );
+        assert_eq!(scored, vec![("b".to_string(), CompletionScore::TypeMatch)]);
+    }
+
+    #[test]
+    fn scores_let_with_type_annotation() {
+        let scored = scored_items(
+            r"
+            struct A;
+            struct B;
+            fn main() {
+                let a = A;
+                let b = B;
+                let x: A = <|>
+            }
+            ",
+        );
+        assert_eq!(scored, vec![("a".to_string(), CompletionScore::TypeMatch)]);
+    }
+
+    #[test]
+    fn does_not_score_let_without_type_annotation() {
+        let scored = scored_items(
+            r"
+            struct A;
+            fn main() {
+                let a = A;
+                let x = <|>
+            }
+            ",
+        );
+        assert_eq!(scored, vec![]);
+    }
+
+    #[test]
+    fn scores_record_literal_fields() {
+        let scored = scored_items(
+            r"
+            struct A;
+            struct S { a: A }
+            fn main() {
+                let a = A;
+                let s = S { a: <|> };
+            }
+            ",
+        );
+        assert_eq!(scored, vec![("a".to_string(), CompletionScore::TypeAndNameMatch)]);
+    }
+
+    #[test]
+    fn scores_return_position() {
+        let scored = scored_items(
+            r"
+            struct A;
+            fn make_a() -> A {
+                let a = A;
+                <|>
+            }
+            ",
+        );
+        assert_eq!(
+            scored,
+            vec![
+                ("a".to_string(), CompletionScore::TypeMatch),
+                ("make_a()".to_string(), CompletionScore::TypeMatch),
+            ]
+        );
+    }
 }
@@ -5,6 +5,7 @@ use hir::{original_range, AssocItem, FieldSource, HasSource, InFile, ModuleSourc
 use ra_db::{FileId, SourceDatabase};
 use ra_ide_db::{defs::Definition, RootDatabase};
 use ra_syntax::{
+    algo::find_covering_element,
     ast::{self, DocCommentsOwner, NameOwner},
     match_ast, AstNode, SmolStr,
     SyntaxKind::{self, BIND_PAT, TYPE_PARAM},
@@ -194,6 +195,21 @@ impl TryToNav for Definition {
             Definition::SelfType(it) => Some(it.to_nav(db)),
             Definition::Local(it) => Some(it.to_nav(db)),
             Definition::TypeParam(it) => Some(it.to_nav(db)),
+            Definition::LifetimeParam(it)
+            | Definition::Label(it)
+            | Definition::MacroMetavar(it) => {
+                let file = db.parse(it.file_id).tree();
+                let token = find_covering_element(file.syntax(), it.range).into_token()?;
+                Some(NavigationTarget::from_syntax(
+                    it.file_id,
+                    token.text().clone(),
+                    Some(it.range),
+                    it.range,
+                    token.parent().kind(),
+                    None,
+                    None,
+                ))
+            }
         }
     }
 }
@@ -0,0 +1,387 @@
+//! Resolution of intra-doc links, like `[Foo]` or `[text](crate::foo::Bar)`,
+//! in doc comments.
+
+use std::ops::Range;
+
+use hir::{
+    Adt, AsAssocItem, AssocItem, AssocItemContainer, HasSource, Module, ModuleDef, PathResolution,
+    Semantics, SemanticsScope,
+};
+use ra_db::{SourceDatabase, SourceDatabaseExt};
+use ra_ide_db::{defs::Definition, RootDatabase};
+use ra_syntax::{algo::find_node_at_offset, ast, AstNode, AstToken, TextRange, TextUnit};
+
+use crate::{display::TryToNav, NavigationTarget};
+
+/// A markdown link whose target looks like a Rust path.
+struct IntraDocLink {
+    /// The whole link, brackets included
+    range: Range<usize>,
+    /// The text shown for the link
+    text: Range<usize>,
+    /// The path the link points to, without disambiguators
+    path: String,
+}
+
+/// An intra-doc link to an item without a known documentation URL, like the
+/// items of the local crates. It can point to the source of the item instead.
+#[derive(Debug)]
+pub(crate) struct LocalDocLink {
+    /// The whole link in the rewritten markdown, brackets included
+    pub(crate) range: Range<usize>,
+    /// The text shown for the link
+    pub(crate) text: String,
+    pub(crate) target: NavigationTarget,
+}
+
+/// Rewrites the intra-doc links in the `markdown` docs of `definition` into
+/// links to the documentation of their targets. Links are resolved in the
+/// scope of the item itself, so `Self` and generic parameters work. Links
+/// which don't resolve are kept as is, as are the links to targets without
+/// a known documentation URL, which are returned alongside.
+pub(crate) fn rewrite_links(
+    sema: &Semantics<RootDatabase>,
+    markdown: &str,
+    definition: &Definition,
+) -> (String, Vec<LocalDocLink>) {
+    let scope = match item_scope(sema, definition) {
+        Some(it) => it,
+        None => return (markdown.to_string(), Vec::new()),
+    };
+
+    let mut res = String::with_capacity(markdown.len());
+    let mut local_links = Vec::new();
+    let mut last = 0;
+    for link in intra_doc_links(markdown) {
+        let def = match resolve_doc_path(sema.db, &scope, &link.path) {
+            Some(it) => it,
+            None => continue,
+        };
+        match doc_url(sema.db, &def, &link.path) {
+            Some(url) => {
+                res.push_str(&markdown[last..link.range.start]);
+                res.push_str(&format!("[{}]({})", &markdown[link.text], url));
+            }
+            None => {
+                let target = match def.try_to_nav(sema.db) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                res.push_str(&markdown[last..link.range.start]);
+                let start = res.len();
+                res.push_str(&markdown[link.range.clone()]);
+                let text = markdown[link.text].to_string();
+                local_links.push(LocalDocLink { range: start..res.len(), text, target });
+            }
+        }
+        last = link.range.end;
+    }
+    res.push_str(&markdown[last..]);
+    (res, local_links)
+}
+
+/// The scope inside of the documented item, falling back to the scope of its
+/// module for items without a name, like the crate root.
+fn item_scope<'db>(
+    sema: &Semantics<'db, RootDatabase>,
+    definition: &Definition,
+) -> Option<SemanticsScope<'db, RootDatabase>> {
+    let name = definition.try_to_nav(sema.db).and_then(|nav| {
+        let focus_range = nav.focus_range()?;
+        let file = sema.parse(nav.file_id());
+        find_node_at_offset::<ast::Name>(file.syntax(), focus_range.start())
+            .filter(|it| it.syntax().text_range() == focus_range)
+    });
+    match name {
+        Some(name) => Some(sema.scope(name.syntax())),
+        None => Some(sema.scope_for_module(definition.module(sema.db)?)),
+    }
+}
+
+/// Resolves the intra-doc link at `offset` in a doc comment, in the scope inside
+/// of the documented item. Returns the range of the link text as well.
+pub(crate) fn doc_link_at(
+    sema: &Semantics<RootDatabase>,
+    comment: &ast::Comment,
+    offset: TextUnit,
+) -> Option<(TextRange, Definition)> {
+    comment.kind().doc?;
+    let text = comment.text().as_str();
+    let start = comment.syntax().text_range().start();
+    let to_range = |range: &Range<usize>| {
+        TextRange::from_to(
+            start + TextUnit::of_str(&text[..range.start]),
+            start + TextUnit::of_str(&text[..range.end]),
+        )
+    };
+
+    let link = intra_doc_links(text)
+        .into_iter()
+        .find(|link| to_range(&link.range).contains_inclusive(offset))?;
+    let item = comment.syntax().parent();
+    let scope = match item.children().find_map(ast::Name::cast) {
+        Some(name) => sema.scope(name.syntax()),
+        None => sema.scope(&item),
+    };
+    let def = resolve_doc_path(sema.db, &scope, &link.path)?;
+    Some((to_range(&link.text), def))
+}
+
+/// Parses `text` as a path, returning `None` if it is anything else.
+pub(crate) fn parse_path(text: &str) -> Option<hir::Path> {
+    let text = text.trim();
+    let parse = ast::SourceFile::parse(&format!("use {};", text));
+    if !parse.errors().is_empty() {
+        return None;
+    }
+    let path = parse.tree().syntax().descendants().find_map(ast::Path::cast)?;
+    if path.syntax().to_string() != text {
+        return None;
+    }
+    hir::Path::from_ast(path)
+}
+
+fn resolve_doc_path(
+    db: &RootDatabase,
+    scope: &SemanticsScope<'_, RootDatabase>,
+    path: &str,
+) -> Option<Definition> {
+    let resolution = match scope.resolve_hir_path(&parse_path(path)?) {
+        Some(it) => it,
+        None => return resolve_assoc_item(db, scope, path),
+    };
+    let def = match resolution {
+        PathResolution::Def(def) => Definition::ModuleDef(def),
+        PathResolution::Macro(def) => Definition::Macro(def),
+        PathResolution::SelfType(impl_def) => {
+            Definition::ModuleDef(impl_def.target_ty(db).as_adt()?.into())
+        }
+        _ => return None,
+    };
+    Some(def)
+}
+
+/// Resolves paths like `Foo::new` to the associated item of the type or trait
+/// they are qualified with.
+fn resolve_assoc_item(
+    db: &RootDatabase,
+    scope: &SemanticsScope<'_, RootDatabase>,
+    path: &str,
+) -> Option<Definition> {
+    let mut segments = path.trim().rsplitn(2, "::");
+    let name = segments.next()?;
+    let qualifier = segments.next()?;
+    let is_named = |item: &AssocItem| match item {
+        AssocItem::Function(it) => it.name(db).to_string() == name,
+        AssocItem::Const(it) => it.name(db).map_or(false, |it| it.to_string() == name),
+        AssocItem::TypeAlias(it) => it.name(db).to_string() == name,
+    };
+    let item = match scope.resolve_hir_path(&parse_path(qualifier)?)? {
+        PathResolution::Def(ModuleDef::Trait(it)) => it.items(db).into_iter().find(is_named)?,
+        PathResolution::Def(ModuleDef::Adt(it)) => {
+            let krate = it.module(db).krate();
+            it.ty(db).iterate_impl_items(db, krate, |it| Some(it).filter(is_named))?
+        }
+        _ => return None,
+    };
+    let def = match item {
+        AssocItem::Function(it) => ModuleDef::Function(it),
+        AssocItem::Const(it) => ModuleDef::Const(it),
+        AssocItem::TypeAlias(it) => ModuleDef::TypeAlias(it),
+    };
+    Some(Definition::ModuleDef(def))
+}
+
+/// Returns the URL of the documentation of `def`. Only the crates from the
+/// sysroot and crates.io have one; the crates of the workspace don't.
+fn doc_url(db: &RootDatabase, def: &Definition, path: &str) -> Option<String> {
+    let (module, file) = match def {
+        Definition::ModuleDef(ModuleDef::Module(module)) => (*module, "index.html".to_string()),
+        Definition::ModuleDef(ModuleDef::BuiltinType(ty)) => {
+            return Some(format!("https://doc.rust-lang.org/nightly/std/primitive.{}.html", ty));
+        }
+        Definition::ModuleDef(ModuleDef::EnumVariant(variant)) => {
+            let parent = variant.parent_enum(db);
+            let file = format!("enum.{}.html#variant.{}", parent.name(db), variant.name(db));
+            (parent.module(db), file)
+        }
+        Definition::ModuleDef(def) => {
+            let assoc_item = match def {
+                ModuleDef::Function(it) => it.as_assoc_item(db),
+                ModuleDef::Const(it) => it.as_assoc_item(db),
+                ModuleDef::TypeAlias(it) => it.as_assoc_item(db),
+                _ => None,
+            };
+            match assoc_item {
+                Some(it) => assoc_item_location(db, it)?,
+                None => {
+                    let kind = match def {
+                        ModuleDef::Function(_) => "fn",
+                        ModuleDef::Adt(it) => adt_kind(*it),
+                        ModuleDef::Const(_) => "constant",
+                        ModuleDef::Static(_) => "static",
+                        ModuleDef::Trait(_) => "trait",
+                        ModuleDef::TypeAlias(_) => "type",
+                        _ => return None,
+                    };
+                    (def.module(db)?, format!("{}.{}.html", kind, def.name(db)?))
+                }
+            }
+        }
+        // `macro_rules!` macros are documented at the root of the crate which
+        // exports them
+        Definition::Macro(it) => {
+            let root = it.module(db)?.krate().root_module(db)?;
+            (root, format!("macro.{}.html", path.rsplit("::").next()?))
+        }
+        _ => return None,
+    };
+
+    let krate = module.krate();
+    let root_file = krate.root_file(db);
+    if !db.source_root(db.file_source_root(root_file)).is_library {
+        return None;
+    }
+    let crate_name = db.crate_graph()[krate.into()].display_name.as_ref()?.to_string();
+    let mut url = match crate_name.as_str() {
+        "std" | "core" | "alloc" => format!("https://doc.rust-lang.org/nightly/{}", crate_name),
+        _ => format!("https://docs.rs/{}/*/{}", crate_name, crate_name),
+    };
+    for module in module.path_to_root(db).into_iter().rev() {
+        if let Some(name) = module.name(db) {
+            url.push('/');
+            url.push_str(&name.to_string());
+        }
+    }
+    url.push('/');
+    url.push_str(&file);
+    Some(url)
+}
+
+/// Associated items are documented on the page of their trait, or of the type
+/// their impl is for. Methods a trait only declares are `tymethod`s there.
+fn assoc_item_location(db: &RootDatabase, item: AssocItem) -> Option<(Module, String)> {
+    let (module, page, in_trait) = match item.container(db) {
+        AssocItemContainer::Trait(it) => {
+            (it.module(db), format!("trait.{}.html", it.name(db)), true)
+        }
+        AssocItemContainer::ImplDef(it) => {
+            let adt = it.target_ty(db).as_adt()?;
+            (adt.module(db), format!("{}.{}.html", adt_kind(adt), adt.name(db)), false)
+        }
+    };
+    let anchor = match item {
+        AssocItem::Function(it) => {
+            let kind = if in_trait && it.source(db).value.body().is_none() {
+                "tymethod"
+            } else {
+                "method"
+            };
+            format!("{}.{}", kind, it.name(db))
+        }
+        AssocItem::Const(it) => format!("associatedconstant.{}", it.name(db)?),
+        AssocItem::TypeAlias(it) => format!("associatedtype.{}", it.name(db)),
+    };
+    Some((module, format!("{}#{}", page, anchor)))
+}
+
+fn adt_kind(adt: Adt) -> &'static str {
+    match adt {
+        Adt::Struct(_) => "struct",
+        Adt::Union(_) => "union",
+        Adt::Enum(_) => "enum",
+    }
+}
+
+fn intra_doc_links(markdown: &str) -> Vec<IntraDocLink> {
+    let mut res = Vec::new();
+    let mut in_code_block = false;
+    let mut line_start = 0;
+    for line in markdown.split('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code_block ^= true;
+        } else if !in_code_block {
+            collect_links(line, line_start, &mut res);
+        }
+        line_start += line.len() + 1;
+    }
+    res
+}
+
+fn collect_links(line: &str, line_start: usize, acc: &mut Vec<IntraDocLink>) {
+    let mut pos = 0;
+    while let Some(open) = line[pos..].find('[').map(|it| pos + it) {
+        let close = match line[open..].find(']') {
+            Some(it) => open + it,
+            None => return,
+        };
+        pos = close + 1;
+
+        let rest = &line[close + 1..];
+        let (end, target) = if rest.starts_with('(') {
+            // `[text](target)`
+            match closing_paren(rest) {
+                Some(len) => (close + 1 + len + 1, &rest[1..len]),
+                None => continue,
+            }
+        } else if rest.starts_with('[') || rest.starts_with(':') {
+            // Reference links and their definitions
+            continue;
+        } else {
+            // `[target]`
+            (close + 1, &line[open + 1..close])
+        };
+        pos = end;
+
+        if let Some(path) = doc_path(target) {
+            acc.push(IntraDocLink {
+                range: line_start + open..line_start + end,
+                text: line_start + open + 1..line_start + close,
+                path: path.to_string(),
+            });
+        }
+    }
+}
+
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Extracts the path from the target of a link, stripping the backticks and
+/// the disambiguators rustdoc accepts (`struct@Foo`, `foo()`, `foo!`).
+fn doc_path(target: &str) -> Option<&str> {
+    let target = target.trim().trim_matches('`');
+    let target = match target.find('@') {
+        Some(idx) => &target[idx + 1..],
+        None => target,
+    };
+    let target = target.trim_end_matches("()").trim_end_matches('!');
+
+    let is_ident = |segment: &str| {
+        let mut chars = segment.chars();
+        match chars.next() {
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                chars.all(|c| c.is_alphanumeric() || c == '_')
+            }
+            _ => false,
+        }
+    };
+    if target.split("::").all(is_ident) {
+        Some(target)
+    } else {
+        None
+    }
+}
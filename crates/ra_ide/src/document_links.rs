@@ -0,0 +1,215 @@
+//! Computes the links of a file: `mod foo;` declarations to the files of the
+//! modules, `#[path]` and `include!` strings to the files they name, and URLs
+//! in comments to their pages.
+
+use hir::Semantics;
+use ra_db::{FileLoader, RelativePath};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast::{self, AttrsOwner, HasStringValue},
+    match_ast, AstNode, AstToken, NodeOrToken, SyntaxKind, SyntaxToken, TextRange, TextUnit,
+};
+
+use crate::FileId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocumentLinkTarget {
+    File(FileId),
+    Url(String),
+}
+
+#[derive(Debug)]
+pub struct DocumentLink {
+    pub range: TextRange,
+    pub target: DocumentLinkTarget,
+}
+
+const INCLUDE_MACROS: &[&str] = &["include", "include_str", "include_bytes"];
+
+pub(crate) fn document_links(db: &RootDatabase, file_id: FileId) -> Vec<DocumentLink> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(file_id);
+
+    let mut res = Vec::new();
+    for element in file.syntax().descendants_with_tokens() {
+        match element {
+            NodeOrToken::Node(node) => {
+                match_ast! {
+                    match node {
+                        ast::Module(it) => { module_links(&mut res, &sema, it); },
+                        ast::MacroCall(it) => { include_link(&mut res, db, file_id, it); },
+                        _ => (),
+                    }
+                }
+            }
+            NodeOrToken::Token(token) => {
+                if is_doc_text(&token) {
+                    url_links(&mut res, &token);
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Links the name of an out-of-line module, and its `#[path]` if any, to the
+/// file of the module.
+fn module_links(
+    acc: &mut Vec<DocumentLink>,
+    sema: &Semantics<RootDatabase>,
+    module: ast::Module,
+) -> Option<()> {
+    if module.item_list().is_some() {
+        return None;
+    }
+    let file_id = sema.to_def(&module)?.definition_source(sema.db).file_id.original_file(sema.db);
+    let target = DocumentLinkTarget::File(file_id);
+
+    acc.push(DocumentLink { range: module.name()?.syntax().text_range(), target: target.clone() });
+    let path_attr = module.attrs().find(|it| it.simple_name().as_deref() == Some("path"));
+    if let Some(ast::AttrInput::Literal(lit)) = path_attr.and_then(|it| it.input()) {
+        acc.push(DocumentLink { range: lit.syntax().text_range(), target });
+    }
+    Some(())
+}
+
+fn include_link(
+    acc: &mut Vec<DocumentLink>,
+    db: &RootDatabase,
+    file_id: FileId,
+    macro_call: ast::MacroCall,
+) -> Option<()> {
+    let name = macro_call.path()?.segment()?.name_ref()?;
+    if !INCLUDE_MACROS.contains(&name.text().as_str()) {
+        return None;
+    }
+    let string = macro_call
+        .token_tree()?
+        .syntax()
+        .children_with_tokens()
+        .filter_map(NodeOrToken::into_token)
+        .find_map(ast::String::cast)?;
+
+    let included = db.resolve_relative_path(file_id, RelativePath::new(&string.value()?))?;
+    acc.push(DocumentLink {
+        range: string.syntax().text_range(),
+        target: DocumentLinkTarget::File(included),
+    });
+    Some(())
+}
+
+/// Comments, and the strings of `#[doc = "..."]` attributes.
+fn is_doc_text(token: &SyntaxToken) -> bool {
+    match token.kind() {
+        SyntaxKind::COMMENT => true,
+        SyntaxKind::STRING => token
+            .parent()
+            .ancestors()
+            .find_map(ast::Attr::cast)
+            .and_then(|it| it.simple_name())
+            .map_or(false, |it| it.as_str() == "doc"),
+        _ => false,
+    }
+}
+
+fn url_links(acc: &mut Vec<DocumentLink>, token: &SyntaxToken) {
+    let text = token.text().as_str();
+    let start = token.text_range().start();
+    let mut pos = 0;
+    while let Some(url_start) = ["https://", "http://"]
+        .iter()
+        .filter_map(|scheme| text[pos..].find(scheme))
+        .min()
+        .map(|it| pos + it)
+    {
+        let len = text[url_start..]
+            .find(|c: char| c.is_whitespace() || "<>\"'`()[]".contains(c))
+            .unwrap_or(text.len() - url_start);
+        let url = text[url_start..url_start + len].trim_end_matches(|c: char| ".,;:!?".contains(c));
+        pos = url_start + len;
+
+        if url.trim_end_matches('/').ends_with(':') {
+            continue;
+        }
+        let range_start = start + TextUnit::of_str(&text[..url_start]);
+        acc.push(DocumentLink {
+            range: TextRange::offset_len(range_start, TextUnit::of_str(url)),
+            target: DocumentLinkTarget::Url(url.to_string()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mock_analysis::MockAnalysis, DocumentLinkTarget, FileId};
+
+    fn check_links(ra_fixture: &str, expected: &[(&str, DocumentLinkTarget)]) {
+        let analysis = MockAnalysis::with_files(ra_fixture).analysis();
+        let file_id = FileId(1);
+        let text = analysis.file_text(file_id).unwrap();
+        let links: Vec<_> = analysis
+            .document_links(file_id)
+            .unwrap()
+            .into_iter()
+            .map(|link| (&text[link.range], link.target))
+            .collect();
+        let expected: Vec<_> =
+            expected.iter().map(|(text, target)| (*text, target.clone())).collect();
+        assert_eq!(links, expected);
+    }
+
+    #[test]
+    fn links_modules_and_included_files() {
+        check_links(
+            r#"
+            //- /main.rs
+            mod foo;
+            #[path = "bar_impl.rs"]
+            mod bar;
+            mod inline {}
+            include!("generated.rs");
+
+            //- /foo.rs
+            struct Foo;
+
+            //- /bar_impl.rs
+            struct Bar;
+
+            //- /generated.rs
+            struct Generated;
+            "#,
+            &[
+                ("foo", DocumentLinkTarget::File(FileId(2))),
+                ("bar", DocumentLinkTarget::File(FileId(3))),
+                ("\"bar_impl.rs\"", DocumentLinkTarget::File(FileId(3))),
+                ("\"generated.rs\"", DocumentLinkTarget::File(FileId(4))),
+            ],
+        );
+    }
+
+    #[test]
+    fn links_urls_in_comments() {
+        check_links(
+            r#"
+            //- /main.rs
+            /// See <https://rust-lang.github.io/rfcs/> for details.
+            #[doc = "Or http://example.com/a?b=c."]
+            fn main() {
+                // https://example.com
+                let _ = "https://example.org";
+            }
+            "#,
+            &[
+                (
+                    "https://rust-lang.github.io/rfcs/",
+                    DocumentLinkTarget::Url("https://rust-lang.github.io/rfcs/".to_string()),
+                ),
+                (
+                    "http://example.com/a?b=c",
+                    DocumentLinkTarget::Url("http://example.com/a?b=c".to_string()),
+                ),
+                ("https://example.com", DocumentLinkTarget::Url("https://example.com".to_string())),
+            ],
+        );
+    }
+}
@@ -284,4 +284,49 @@ fn some_thing() -> u32 {
         assert_eq!(res.name, "foo");
         assert_snapshot!(res.expansion, @r###"0"###);
     }
+
+    #[test]
+    fn macro_expand_module_path() {
+        let res = check_expand_macro(
+            r#"
+        //- /lib.rs
+        #[rustc_builtin_macro]
+        macro_rules! module_path {() => {}}
+
+        mod foo {
+            mod bar {
+                fn main() {
+                    let path = module_pa<|>th!();
+                }
+            }
+        }
+        "#,
+        );
+
+        assert_eq!(res.name, "module_path");
+        assert_snapshot!(res.expansion, @r###""crate::foo::bar""###);
+    }
+
+    #[test]
+    fn macro_expand_module_path_in_concat() {
+        let res = check_expand_macro(
+            r#"
+        //- /lib.rs
+        #[rustc_builtin_macro]
+        macro_rules! module_path {() => {}}
+
+        #[rustc_builtin_macro]
+        macro_rules! concat {() => {}}
+
+        mod foo {
+            fn main() {
+                let path = con<|>cat!(module_path!(), "::x");
+            }
+        }
+        "#,
+        );
+
+        assert_eq!(res.name, "concat");
+        assert_snapshot!(res.expansion, @r###""crate::foo::x""###);
+    }
 }
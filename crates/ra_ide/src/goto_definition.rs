@@ -2,18 +2,19 @@
 
 use hir::Semantics;
 use ra_ide_db::{
-    defs::{classify_name, classify_name_ref},
+    defs::{classify_name, classify_name_ref, classify_operator},
     symbol_index, RootDatabase,
 };
 use ra_syntax::{
     ast::{self},
-    match_ast, AstNode,
+    match_ast, AstNode, AstToken,
     SyntaxKind::*,
     SyntaxToken, TokenAtOffset,
 };
 
 use crate::{
     display::{ToNav, TryToNav},
+    doc_links::doc_link_at,
     FilePosition, NavigationTarget, RangeInfo,
 };
 
@@ -24,6 +25,10 @@ pub(crate) fn goto_definition(
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id).syntax().clone();
     let original_token = pick_best(file.token_at_offset(position.offset))?;
+    if let Some(comment) = ast::Comment::cast(original_token.clone()) {
+        let (range, def) = doc_link_at(&sema, &comment, position.offset)?;
+        return Some(RangeInfo::new(range, vec![def.try_to_nav(db)?]));
+    }
     let token = sema.descend_into_macros(original_token.clone());
 
     let nav_targets = match_ast! {
@@ -36,7 +41,10 @@ pub(crate) fn goto_definition(
                 let nav = def.try_to_nav(sema.db)?;
                 vec![nav]
             },
-            _ => return None,
+            _ => {
+                let def = classify_operator(&sema, &token)?;
+                vec![def.try_to_nav(sema.db)?]
+            },
         }
     };
 
@@ -47,7 +55,8 @@ fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
     return tokens.max_by_key(priority);
     fn priority(n: &SyntaxToken) -> usize {
         match n.kind() {
-            IDENT | INT_NUMBER => 2,
+            IDENT | INT_NUMBER => 3,
+            QUESTION => 2,
             kind if kind.is_trivia() => 0,
             _ => 1,
         }
@@ -355,6 +364,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn goto_def_for_operators() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            #[lang = "add"]
+            trait Add<Rhs = Self> {
+                type Output;
+                fn add(self, rhs: Rhs) -> Self::Output;
+            }
+            struct Foo;
+            impl Add for Foo {
+                type Output = Foo;
+                fn add(self, rhs: Foo) -> Foo { rhs }
+            }
+            fn bar(a: Foo, b: Foo) -> Foo {
+                a +<|> b
+            }
+            "#,
+            "add FN_DEF FileId(1) [161; 198) [164; 167)",
+            "fn add(self, rhs: Foo) -> Foo { rhs }|add",
+        );
+    }
+
     #[test]
     fn goto_def_for_fields() {
         covers!(ra_ide_db::goto_def_for_fields);
@@ -818,4 +851,28 @@ mod tests {
             "x",
         )
     }
+
+    #[test]
+    fn goto_def_for_intra_doc_links() {
+        check_goto(
+            "
+            //- /lib.rs
+            /// See [`Ba<|>r`] and [crate::Bar].
+            struct Foo;
+            struct Bar;
+            ",
+            "Bar STRUCT_DEF FileId(1) [46; 57) [53; 56)",
+            "struct Bar;|Bar",
+        );
+        check_goto(
+            "
+            //- /lib.rs
+            /// See [`Bar`] and [crate::B<|>ar].
+            struct Foo;
+            struct Bar;
+            ",
+            "Bar STRUCT_DEF FileId(1) [46; 57) [53; 56)",
+            "struct Bar;|Bar",
+        );
+    }
 }
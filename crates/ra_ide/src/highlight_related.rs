@@ -0,0 +1,394 @@
+//! Highlights the keywords and expressions related to the one under the
+//! cursor: the exit points of a function, the `break`s and `continue`s of a
+//! loop and the await points of an async function or block.
+
+use hir::{DefWithBody, Semantics};
+use ra_db::FileId;
+use ra_ide_db::{
+    defs::{child_token, is_body_boundary, label_lifetime},
+    RootDatabase,
+};
+use ra_prof::profile;
+use ra_syntax::{
+    algo::find_covering_element,
+    ast::{self, AstNode},
+    match_ast, NodeOrToken,
+    SyntaxKind::{self, *},
+    SyntaxNode, TextRange, T,
+};
+
+use crate::FilePosition;
+
+const KINDS: &[SyntaxKind] = &[
+    T![fn],
+    T![return],
+    T![?],
+    T![loop],
+    T![while],
+    T![for],
+    T![break],
+    T![continue],
+    LIFETIME,
+    T![async],
+    T![await],
+];
+
+pub(crate) fn highlight_related(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<TextRange>> {
+    let _p = profile("highlight_related");
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let token =
+        file.syntax().token_at_offset(position.offset).find(|it| KINDS.contains(&it.kind()))?;
+    let parent = token.parent();
+    let file_id = position.file_id;
+
+    let mut res = match token.kind() {
+        T![fn] if parent.kind() == FN_DEF => highlight_exit_points(&sema, file_id, &parent),
+        T![return] => highlight_exit_points(&sema, file_id, &body_owner(&parent)?),
+        // `?` also appears in `?Sized` bounds
+        T![?] if parent.kind() == TRY_EXPR => {
+            highlight_exit_points(&sema, file_id, &body_owner(&parent)?)
+        }
+        T![loop] | T![while] | T![for] if is_loop(&parent) => highlight_break_points(&parent),
+        T![break] | T![continue] => highlight_break_points(&break_target(&parent)?),
+        LIFETIME => match parent.kind() {
+            LABEL => highlight_break_points(&parent.parent()?),
+            BREAK_EXPR | CONTINUE_EXPR => highlight_break_points(&break_target(&parent)?),
+            _ => return None,
+        },
+        T![async] if is_body_boundary(&parent) => highlight_yield_points(&sema, file_id, &parent),
+        T![await] => highlight_yield_points(&sema, file_id, &body_owner(&parent)?),
+        _ => return None,
+    };
+    res.sort_by_key(|range| range.start());
+    // Several exit points of one macro call map to the same range
+    res.dedup();
+    Some(res)
+}
+
+/// Highlights the function (or closure, or async block) keyword, its
+/// `return`s, `?`s and tail expressions.
+fn highlight_exit_points(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    owner: &SyntaxNode,
+) -> Vec<TextRange> {
+    let mut res = Vec::new();
+    res.extend(child_token(owner, T![fn]).map(|it| it.text_range()));
+    res.extend(exit_and_await_points(sema, file_id, owner, &[RETURN_EXPR, TRY_EXPR]));
+
+    let tail = match_ast! {
+        match owner {
+            ast::FnDef(it) => it.body().map(ast::Expr::from),
+            ast::LambdaExpr(it) => it.body(),
+            ast::BlockExpr(it) => it.block().and_then(|it| it.expr()),
+            _ => None,
+        }
+    };
+    if let Some(tail) = tail {
+        for_each_tail_expr(&tail, &mut |expr| res.push(expr.syntax().text_range()));
+    }
+    res
+}
+
+/// Highlights the loop keyword and label, and the `break`s and `continue`s
+/// targeting the loop.
+fn highlight_break_points(target: &SyntaxNode) -> Vec<TextRange> {
+    let mut res = Vec::new();
+    res.extend(label_lifetime(target).map(|it| it.text_range()));
+    res.extend(
+        [T![loop], T![while], T![for]]
+            .iter()
+            .find_map(|&kind| child_token(target, kind))
+            .map(|it| it.text_range()),
+    );
+    for_each_in_body(target, &mut |node| {
+        let keyword = match node.kind() {
+            BREAK_EXPR => child_token(node, T![break]),
+            CONTINUE_EXPR => child_token(node, T![continue]),
+            _ => return,
+        };
+        if break_target(node).as_ref() != Some(target) {
+            return;
+        }
+        res.extend(keyword.map(|it| it.text_range()));
+        res.extend(child_token(node, LIFETIME).map(|it| it.text_range()));
+    });
+    res
+}
+
+/// Highlights the `async` keyword of a function, closure or block, and its
+/// `.await`s.
+fn highlight_yield_points(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    owner: &SyntaxNode,
+) -> Vec<TextRange> {
+    let mut res = Vec::new();
+    res.extend(child_token(owner, T![async]).map(|it| it.text_range()));
+    res.extend(exit_and_await_points(sema, file_id, owner, &[AWAIT_EXPR]));
+    res
+}
+
+/// Returns the keywords of the `return`, `?` and `.await` expressions of the
+/// given `kinds` which belong to `owner`. The expressions come from the body
+/// of the enclosing item, so those produced by a macro call are found too and
+/// map to the macro call.
+fn exit_and_await_points(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    owner: &SyntaxNode,
+    kinds: &[SyntaxKind],
+) -> Vec<TextRange> {
+    let def = match owner.ancestors().find_map(|it| def_with_body(sema, &it)) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let root = match owner.ancestors().last() {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    def.exit_and_await_exprs(sema.db)
+        .into_iter()
+        .filter(|expr| kinds.contains(&expr.value.syntax().kind()))
+        .filter_map(|expr| {
+            let range = if expr.file_id == file_id.into() {
+                let keyword = match expr.value.syntax().kind() {
+                    RETURN_EXPR => T![return],
+                    TRY_EXPR => T![?],
+                    _ => T![await],
+                };
+                child_token(expr.value.syntax(), keyword)?.text_range()
+            } else {
+                // Closures and async blocks in the expansion have exit points
+                // of their own
+                let call = expr
+                    .map(|it| it.syntax().clone())
+                    .ancestors_with_macros(sema.db)
+                    .find(|it| it.file_id == file_id.into() || is_body_boundary(&it.value))?;
+                if call.file_id != file_id.into() {
+                    return None;
+                }
+                call.value.text_range()
+            };
+            let node = match find_covering_element(&root, range) {
+                NodeOrToken::Node(it) => it,
+                NodeOrToken::Token(it) => it.parent(),
+            };
+            if body_owner(&node).as_ref() != Some(owner) {
+                return None;
+            }
+            Some(range)
+        })
+        .collect()
+}
+
+fn def_with_body(sema: &Semantics<RootDatabase>, node: &SyntaxNode) -> Option<DefWithBody> {
+    match_ast! {
+        match node {
+            ast::FnDef(it) => sema.to_def(&it).map(DefWithBody::from),
+            ast::ConstDef(it) => sema.to_def(&it).map(DefWithBody::from),
+            ast::StaticDef(it) => sema.to_def(&it).map(DefWithBody::from),
+            _ => None,
+        }
+    }
+}
+
+/// Calls `cb` on the tail expressions which produce the value of `expr`,
+/// looking through blocks, `if`s and `match`es.
+fn for_each_tail_expr(expr: &ast::Expr, cb: &mut dyn FnMut(&ast::Expr)) {
+    match expr {
+        ast::Expr::BlockExpr(it) if !is_body_boundary(it.syntax()) => {
+            if let Some(tail) = it.block().and_then(|it| it.expr()) {
+                for_each_tail_expr(&tail, cb);
+            }
+        }
+        ast::Expr::IfExpr(it) => {
+            if let Some(then_branch) = it.then_branch() {
+                for_each_tail_expr(&then_branch.into(), cb);
+            }
+            match it.else_branch() {
+                Some(ast::ElseBranch::Block(it)) => for_each_tail_expr(&it.into(), cb),
+                Some(ast::ElseBranch::IfExpr(it)) => for_each_tail_expr(&it.into(), cb),
+                None => (),
+            }
+        }
+        ast::Expr::MatchExpr(it) => {
+            for arm in it.match_arm_list().into_iter().flat_map(|it| it.arms()) {
+                if let Some(expr) = arm.expr() {
+                    for_each_tail_expr(&expr, cb);
+                }
+            }
+        }
+        // Already highlighted as exit points
+        ast::Expr::ReturnExpr(_) | ast::Expr::TryExpr(_) => (),
+        _ => cb(expr),
+    }
+}
+
+/// Calls `cb` on the descendants of `node`, skipping nested functions,
+/// closures and async blocks, which have exit points of their own.
+fn for_each_in_body(node: &SyntaxNode, cb: &mut dyn FnMut(&SyntaxNode)) {
+    for child in node.children() {
+        if is_body_boundary(&child) {
+            continue;
+        }
+        cb(&child);
+        for_each_in_body(&child, cb);
+    }
+}
+
+/// Finds the function, closure or async block `node` belongs to.
+fn body_owner(node: &SyntaxNode) -> Option<SyntaxNode> {
+    node.ancestors().find(is_body_boundary)
+}
+
+/// Finds the loop (or labeled block) a `break` or `continue` jumps out of.
+fn break_target(node: &SyntaxNode) -> Option<SyntaxNode> {
+    let lifetime = child_token(node, LIFETIME);
+    node.ancestors().skip(1).take_while(|it| !is_body_boundary(it)).find(|it| match &lifetime {
+        Some(lifetime) => label_lifetime(it).map_or(false, |it| it.text() == lifetime.text()),
+        None => is_loop(it),
+    })
+}
+
+fn is_loop(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        LOOP_EXPR | WHILE_EXPR | FOR_EXPR => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::single_file_with_position;
+
+    fn check(ra_fixture: &str, expected: &[&str]) {
+        let (analysis, position) = single_file_with_position(ra_fixture);
+        let text = analysis.file_text(position.file_id).unwrap();
+        let ranges = analysis.highlight_related(position).unwrap().unwrap();
+        let actual: Vec<&str> = ranges.iter().map(|&range| &text[range]).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn highlight_exit_points() {
+        check(
+            r#"
+            fn<|> foo(x: bool) -> Result<u32, ()> {
+                if x {
+                    return Ok(0);
+                }
+                let y = bar()?;
+                let c = || { return 1; };
+                if y > 2 { Ok(1) } else { Ok(2) }
+            }
+            "#,
+            &["fn", "return", "?", "Ok(1)", "Ok(2)"],
+        );
+    }
+
+    #[test]
+    fn highlight_exit_points_from_macros() {
+        check(
+            r#"
+            macro_rules! try_it {
+                ($e:expr) => { $e? };
+            }
+            fn<|> foo() -> Option<u32> {
+                let x = try_it!(bar());
+                Some(x)
+            }
+            "#,
+            &["fn", "try_it!(bar())", "Some(x)"],
+        );
+    }
+
+    #[test]
+    fn does_not_highlight_exit_points_from_question_mark_bound() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+            fn foo<T: ?<|>Sized>(x: &T) -> Option<u32> {
+                bar()?;
+                None
+            }
+            "#,
+        );
+        assert_eq!(analysis.highlight_related(position).unwrap(), None);
+    }
+
+    #[test]
+    fn highlight_exit_points_of_closure() {
+        check(
+            r#"
+            fn foo() -> u32 {
+                let c = |x| { if x { ret<|>urn 1; } 2 };
+                3
+            }
+            "#,
+            &["return", "2"],
+        );
+    }
+
+    #[test]
+    fn highlight_break_points() {
+        check(
+            r#"
+            fn foo() {
+                'outer: lo<|>op {
+                    while true {
+                        break;
+                        break 'outer;
+                    }
+                    continue;
+                }
+            }
+            "#,
+            &["'outer", "loop", "break", "'outer", "continue"],
+        );
+    }
+
+    #[test]
+    fn highlight_break_points_of_inner_loop() {
+        check(
+            r#"
+            fn foo() {
+                'outer: loop {
+                    while true {
+                        br<|>eak;
+                        break 'outer;
+                    }
+                }
+            }
+            "#,
+            &["while", "break"],
+        );
+    }
+
+    #[test]
+    fn highlight_yield_points() {
+        check(
+            r#"
+            async fn foo() {
+                bar().await;
+                let b = async { baz().aw<|>ait };
+                qux().await
+            }
+            "#,
+            &["async", "await"],
+        );
+        check(
+            r#"
+            asy<|>nc fn foo() {
+                bar().await;
+                let b = async { baz().await };
+                qux().await
+            }
+            "#,
+            &["async", "await", "await"],
+        );
+    }
+}
@@ -3,31 +3,56 @@
 
 use hir::{
     Adt, AsAssocItem, AssocItemContainer, FieldSource, HasSource, HirDisplay, ModuleDef,
-    ModuleSource, Semantics,
+    ModuleSource, Semantics, Type,
 };
 use ra_db::SourceDatabase;
 use ra_ide_db::{
-    defs::{classify_name, classify_name_ref, Definition},
+    defs::{classify_name, classify_name_ref, classify_operator, Definition},
     RootDatabase,
 };
 use ra_syntax::{
+    algo::find_covering_element,
     ast::{self, DocCommentsOwner},
     match_ast, AstNode,
     SyntaxKind::*,
-    SyntaxToken, TokenAtOffset,
+    SyntaxToken, TextRange, TokenAtOffset,
 };
 
 use crate::{
-    display::{macro_label, rust_code_markup, rust_code_markup_with_doc, ShortLabel},
-    FilePosition, RangeInfo,
+    display::{
+        macro_label, rust_code_markup, rust_code_markup_with_doc, ShortLabel, ToNav, TryToNav,
+    },
+    doc_links::{rewrite_links, LocalDocLink},
+    runnables::{runnable, Runnable, RunnableKind},
+    FileId, FilePosition, NavigationTarget, RangeInfo,
 };
 use itertools::Itertools;
 use std::iter::once;
 
+/// An action which the client can offer next to the hover text.
+#[derive(Debug)]
+pub enum HoverAction {
+    /// Shows the implementations of the trait or type defined at the position
+    Implementation(FilePosition),
+    /// Runs or debugs the test function in the file
+    Runnable(FileId, Runnable),
+    /// Navigates to the types mentioned in the hovered type
+    GoToType(Vec<HoverGotoTypeData>),
+}
+
+#[derive(Debug)]
+pub struct HoverGotoTypeData {
+    pub mod_path: String,
+    pub nav: NavigationTarget,
+}
+
 /// Contains the results when hovering over an item
 #[derive(Debug, Default)]
 pub struct HoverResult {
     results: Vec<String>,
+    /// Links to items without documentation URL, with the index of their result
+    local_links: Vec<(usize, LocalDocLink)>,
+    actions: Vec<HoverAction>,
 }
 
 impl HoverResult {
@@ -39,6 +64,12 @@ impl HoverResult {
         self.results.extend(item);
     }
 
+    fn push_with_local_links(&mut self, item: String, local_links: Vec<LocalDocLink>) {
+        let idx = self.results.len();
+        self.results.push(item);
+        self.local_links.extend(local_links.into_iter().map(|it| (idx, it)));
+    }
+
     pub fn is_empty(&self) -> bool {
         self.results.is_empty()
     }
@@ -55,11 +86,47 @@ impl HoverResult {
         &self.results
     }
 
+    pub fn push_action(&mut self, action: HoverAction) {
+        self.actions.push(action);
+    }
+
+    pub fn actions(&self) -> &[HoverAction] {
+        &self.actions
+    }
+
     /// Returns the results converted into markup
     /// for displaying in a UI
     pub fn to_markup(&self) -> String {
         self.results.join("\n\n---\n")
     }
+
+    /// Like `to_markup`, but points the intra-doc links to items without a
+    /// documentation URL, like those of the local crates, to their source.
+    /// `file_url` gives the URL of a range of a file.
+    pub fn to_markup_with_file_urls(
+        &self,
+        file_url: impl Fn(FileId, TextRange) -> Option<String>,
+    ) -> String {
+        self.results
+            .iter()
+            .enumerate()
+            .map(|(idx, result)| {
+                let mut res = String::with_capacity(result.len());
+                let mut last = 0;
+                for (_, link) in self.local_links.iter().filter(|(it, _)| *it == idx) {
+                    let url = match file_url(link.target.file_id(), link.target.range()) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    res.push_str(&result[last..link.range.start]);
+                    res.push_str(&format!("[{}]({})", link.text, url));
+                    last = link.range.end;
+                }
+                res.push_str(&result[last..]);
+                res
+            })
+            .join("\n\n---\n")
+    }
 }
 
 fn hover_text(
@@ -148,6 +215,7 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<Strin
             // FIXME: Hover for generic param
             None
         }
+        Definition::LifetimeParam(_) | Definition::Label(_) | Definition::MacroMetavar(_) => None,
     };
 
     fn from_def_source<A, D>(db: &RootDatabase, def: D, mod_path: Option<String>) -> Option<String>
@@ -163,26 +231,37 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<Strin
 pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeInfo<HoverResult>> {
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id).syntax().clone();
-    let token = pick_best(file.token_at_offset(position.offset))?;
-    let token = sema.descend_into_macros(token);
+    let original_token = pick_best(file.token_at_offset(position.offset))?;
+    let token = sema.descend_into_macros(original_token.clone());
 
     let mut res = HoverResult::new();
 
-    if let Some((node, name_kind)) = match_ast! {
+    if let Some((range, name_kind)) = match_ast! {
         match (token.parent()) {
             ast::NameRef(name_ref) => {
-                classify_name_ref(&sema, &name_ref).map(|d| (name_ref.syntax().clone(), d.definition()))
+                classify_name_ref(&sema, &name_ref)
+                    .map(|d| (sema.original_range(name_ref.syntax()).range, d.definition()))
             },
             ast::Name(name) => {
-                classify_name(&sema, &name).map(|d| (name.syntax().clone(), d.definition()))
+                classify_name(&sema, &name)
+                    .map(|d| (sema.original_range(name.syntax()).range, d.definition()))
             },
-            _ => None,
+            _ => classify_operator(&sema, &token).map(|d| (original_token.text_range(), d)),
         }
     } {
-        let range = sema.original_range(&node).range;
-        res.extend(hover_text_from_name_kind(db, name_kind));
+        if let Some(text) = hover_text_from_name_kind(db, name_kind.clone()) {
+            let (text, local_links) = rewrite_links(&sema, &text, &name_kind);
+            res.push_with_local_links(text, local_links);
+        }
 
         if !res.is_empty() {
+            let actions = show_implementations_action(db, &name_kind)
+                .into_iter()
+                .chain(runnable_action(&sema, &name_kind))
+                .chain(goto_type_action(db, &name_kind));
+            for action in actions {
+                res.push_action(action);
+            }
             return Some(RangeInfo::new(range, res));
         }
     }
@@ -209,16 +288,94 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
     }?;
 
     res.extend(Some(rust_code_markup(&ty.display_truncated(db, None))));
+    if let Some(action) = goto_type_action_for_types(db, &[ty]) {
+        res.push_action(action);
+    }
     let range = sema.original_range(&node).range;
     Some(RangeInfo::new(range, res))
 }
 
+fn show_implementations_action(db: &RootDatabase, def: &Definition) -> Option<HoverAction> {
+    match def {
+        Definition::ModuleDef(ModuleDef::Trait(_)) | Definition::ModuleDef(ModuleDef::Adt(_)) => (),
+        _ => return None,
+    }
+    let nav = def.try_to_nav(db)?;
+    let offset = nav.focus_range().unwrap_or_else(|| nav.range()).start();
+    Some(HoverAction::Implementation(FilePosition { file_id: nav.file_id(), offset }))
+}
+
+fn runnable_action(sema: &Semantics<RootDatabase>, def: &Definition) -> Option<HoverAction> {
+    let function = match def {
+        Definition::ModuleDef(ModuleDef::Function(it)) => *it,
+        _ => return None,
+    };
+    let src = function.source(sema.db);
+    let file_id = src.file_id.original_file(sema.db);
+    if src.file_id != file_id.into() {
+        return None;
+    }
+    // `runnable` needs a node known to `sema`, so find the function again in
+    // the parsed file.
+    let file = sema.parse(file_id);
+    let fn_def = find_covering_element(file.syntax(), src.value.syntax().text_range())
+        .ancestors()
+        .find_map(ast::FnDef::cast)?;
+    let runnable = runnable(sema, fn_def.syntax().clone())?;
+    match runnable.kind {
+        RunnableKind::Test { .. } | RunnableKind::Bench { .. } => {
+            Some(HoverAction::Runnable(file_id, runnable))
+        }
+        RunnableKind::TestMod { .. } | RunnableKind::Bin => None,
+    }
+}
+
+fn goto_type_action(db: &RootDatabase, def: &Definition) -> Option<HoverAction> {
+    let types = match def {
+        Definition::Local(it) => vec![it.ty(db)],
+        Definition::StructField(it) => vec![it.ty(db)],
+        Definition::ModuleDef(ModuleDef::Function(it)) => {
+            let mut types = it.param_types(db);
+            types.push(it.ret_type(db));
+            types
+        }
+        _ => return None,
+    };
+    goto_type_action_for_types(db, &types)
+}
+
+fn goto_type_action_for_types(db: &RootDatabase, types: &[Type]) -> Option<HoverAction> {
+    let mut adts = Vec::new();
+    for adt in types.iter().flat_map(Type::referenced_adts) {
+        if !adts.contains(&adt) {
+            adts.push(adt);
+        }
+    }
+    if adts.is_empty() {
+        return None;
+    }
+    let targets = adts
+        .into_iter()
+        .map(|adt| {
+            let def = Definition::ModuleDef(adt.into());
+            let mod_path = determine_mod_path(db, &def).unwrap_or_default();
+            let mod_path = if mod_path.is_empty() {
+                adt.name(db).to_string()
+            } else {
+                format!("{}::{}", mod_path, adt.name(db))
+            };
+            HoverGotoTypeData { mod_path, nav: adt.to_nav(db) }
+        })
+        .collect();
+    Some(HoverAction::GoToType(targets))
+}
+
 fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
     return tokens.max_by_key(priority);
     fn priority(n: &SyntaxToken) -> usize {
         match n.kind() {
             IDENT | INT_NUMBER => 3,
-            L_PAREN | R_PAREN => 2,
+            QUESTION | L_PAREN | R_PAREN => 2,
             kind if kind.is_trivia() => 0,
             _ => 1,
         }
@@ -230,7 +387,10 @@ mod tests {
     use ra_db::FileLoader;
     use ra_syntax::TextRange;
 
-    use crate::mock_analysis::{analysis_and_position, single_file_with_position};
+    use crate::{
+        mock_analysis::{analysis_and_position, single_file_with_position, MockAnalysis},
+        HoverAction, Runnable, RunnableKind,
+    };
 
     fn trim_markup(s: &str) -> &str {
         s.trim_start_matches("```rust\n").trim_end_matches("\n```")
@@ -240,6 +400,15 @@ mod tests {
         s.map(trim_markup)
     }
 
+    /// Hovers in `fixture` with the files under `/foo/` being a library crate,
+    /// and returns the single result.
+    fn check_hover_result_with_library(fixture: &str) -> String {
+        let (mut mock, position) = MockAnalysis::with_files_and_position(fixture);
+        mock.add_library_dir("/foo/");
+        let hover = mock.analysis().hover(position).unwrap().unwrap();
+        trim_markup(&hover.info.to_markup()).to_string()
+    }
+
     fn check_hover_result(fixture: &str, expected: &[&str]) -> String {
         let (analysis, position) = analysis_and_position(fixture);
         let hover = analysis.hover(position).unwrap().unwrap();
@@ -348,6 +517,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hover_shows_fn_signature_on_operator() {
+        let range = check_hover_result(
+            r#"
+            //- /main.rs
+            use std::{convert::From, result::Result};
+            struct Error;
+            struct OtherError;
+            impl From<OtherError> for Error {
+                fn from(e: OtherError) -> Error { Error }
+            }
+            fn foo() -> Result<(), OtherError> { Result::Ok(()) }
+            fn bar() -> Result<(), Error> {
+                foo()?<|>;
+                Result::Ok(())
+            }
+            //- /std/lib.rs
+            pub mod convert {
+                pub trait From<T> { fn from(t: T) -> Self; }
+            }
+            pub mod result {
+                pub enum Result<T, E> { Ok(T), Err(E) }
+            }
+        "#,
+            &["Error\nfn from(e: OtherError) -> Error"],
+        );
+        assert_eq!(range, "?");
+    }
+
     #[test]
     fn hover_shows_struct_field_info() {
         // Hovering over the field when instantiating
@@ -844,4 +1042,183 @@ fn func(foo: i32) { if true { <|>foo; }; }
             &["fn foo()\n```\n\n<- `\u{3000}` here"],
         );
     }
+
+    #[test]
+    fn test_hover_rewrites_intra_doc_links() {
+        let hover = check_hover_result_with_library(
+            "
+            //- /main.rs
+            /// Made from [`Qux`](foo::Qux), a [foo::baz::Baz] and a [Bar].
+            pub struct Fo<|>o;
+            pub struct Bar;
+
+            //- /foo/lib.rs
+            pub struct Qux;
+            pub mod baz {
+                pub struct Baz;
+            }
+            ",
+        );
+        assert_eq!(
+            hover,
+            "pub struct Foo\n```\n\nMade from [`Qux`](https://docs.rs/foo/*/foo/struct.Qux.html), a [foo::baz::Baz](https://docs.rs/foo/*/foo/baz/struct.Baz.html) and a [Bar]."
+        );
+    }
+
+    #[test]
+    fn test_hover_resolves_intra_doc_links_inside_the_item() {
+        let hover = check_hover_result_with_library(
+            "
+            //- /main.rs
+            fn main() {
+                foo::Qux::ne<|>w();
+            }
+
+            //- /foo/lib.rs
+            pub struct Qux;
+            impl Qux {
+                /// Makes a [Self].
+                pub fn new() -> Qux { Qux }
+            }
+            ",
+        );
+        assert_eq!(
+            hover,
+            "foo::Qux\npub fn new() -> Qux\n```\n\nMakes a [Self](https://docs.rs/foo/*/foo/struct.Qux.html)."
+        );
+    }
+
+    #[test]
+    fn test_hover_links_associated_items_on_their_parent_page() {
+        let hover = check_hover_result_with_library(
+            "
+            //- /main.rs
+            /// Built by [foo::Qux::new], sized by [foo::Len::len] and [foo::Len::is_empty].
+            pub struct Fo<|>o;
+
+            //- /foo/lib.rs
+            pub struct Qux;
+            impl Qux {
+                pub fn new() -> Qux { Qux }
+            }
+            pub trait Len {
+                fn len(&self) -> usize;
+                fn is_empty(&self) -> bool { self.len() == 0 }
+            }
+            ",
+        );
+        assert_eq!(
+            hover,
+            "pub struct Foo\n```\n\nBuilt by [foo::Qux::new](https://docs.rs/foo/*/foo/struct.Qux.html#method.new), sized by [foo::Len::len](https://docs.rs/foo/*/foo/trait.Len.html#tymethod.len) and [foo::Len::is_empty](https://docs.rs/foo/*/foo/trait.Len.html#method.is_empty)."
+        );
+    }
+
+    #[test]
+    fn test_hover_links_macros_at_the_crate_root() {
+        let hover = check_hover_result_with_library(
+            "
+            //- /main.rs
+            /// Made with [foo::make].
+            pub struct Fo<|>o;
+
+            //- /foo/lib.rs
+            pub mod inner {
+                #[macro_export]
+                macro_rules! make { () => {} }
+            }
+            ",
+        );
+        assert_eq!(
+            hover,
+            "pub struct Foo\n```\n\nMade with [foo::make](https://docs.rs/foo/*/foo/macro.make.html)."
+        );
+    }
+
+    #[test]
+    fn test_hover_does_not_link_workspace_crates_to_docs_rs() {
+        let (analysis, position) = analysis_and_position(
+            "
+            //- /main.rs
+            /// Made from [foo::Qux].
+            pub struct Fo<|>o;
+
+            //- /foo/lib.rs
+            pub struct Qux;
+            ",
+        );
+        let hover = analysis.hover(position).unwrap().unwrap();
+        assert_eq!(
+            trim_markup(&hover.info.to_markup()),
+            "pub struct Foo\n```\n\nMade from [foo::Qux]."
+        );
+    }
+
+    #[test]
+    fn test_hover_links_local_items_to_their_source() {
+        let (analysis, position) = analysis_and_position(
+            "
+            //- /main.rs
+            /// See [Bar].
+            pub struct Fo<|>o;
+            pub struct Bar;
+            ",
+        );
+        let hover = analysis.hover(position).unwrap().unwrap();
+        assert_eq!(trim_markup(&hover.info.to_markup()), "pub struct Foo\n```\n\nSee [Bar].");
+
+        let markup = hover.info.to_markup_with_file_urls(|file_id, range| {
+            assert_eq!(file_id, position.file_id);
+            Some(format!("file:///main.rs#{}", range.start().to_usize()))
+        });
+        assert_eq!(trim_markup(&markup), "pub struct Foo\n```\n\nSee [Bar](file:///main.rs#42).");
+    }
+
+    #[test]
+    fn hover_shows_implementations_action() {
+        let (analysis, position) = single_file_with_position("trait foo<|>o {}");
+        let hover = analysis.hover(position).unwrap().unwrap();
+        match hover.info.actions() {
+            [HoverAction::Implementation(pos)] => assert_eq!(pos.offset, 6.into()),
+            actions => panic!("unexpected actions: {:?}", actions),
+        }
+    }
+
+    #[test]
+    fn hover_shows_runnable_action_for_tests() {
+        let (analysis, position) = single_file_with_position(
+            "
+            #[test]
+            fn foo_<|>test() {}
+            ",
+        );
+        let hover = analysis.hover(position).unwrap().unwrap();
+        match hover.info.actions() {
+            [HoverAction::Runnable(_, Runnable { kind: RunnableKind::Test { test_id }, .. })] => {
+                assert_eq!(test_id.to_string(), "foo_test")
+            }
+            actions => panic!("unexpected actions: {:?}", actions),
+        }
+    }
+
+    #[test]
+    fn hover_shows_goto_type_action() {
+        let (analysis, position) = single_file_with_position(
+            "
+            mod m { pub struct Foo; }
+            struct Wrapper<T>(T);
+
+            fn main() {
+                let x<|> = Wrapper(m::Foo);
+            }
+            ",
+        );
+        let hover = analysis.hover(position).unwrap().unwrap();
+        match hover.info.actions() {
+            [HoverAction::GoToType(targets)] => {
+                let paths: Vec<_> = targets.iter().map(|it| it.mod_path.as_str()).collect();
+                assert_eq!(paths, ["Wrapper", "m::Foo"]);
+            }
+            actions => panic!("unexpected actions: {:?}", actions),
+        }
+    }
 }
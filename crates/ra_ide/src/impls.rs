@@ -1,6 +1,6 @@
 //! FIXME: write short doc here
 
-use hir::{Crate, ImplDef, Semantics};
+use hir::{AsAssocItem, AssocItem, AssocItemContainer, Crate, ImplDef, Semantics};
 use ra_ide_db::RootDatabase;
 use ra_syntax::{algo::find_node_at_offset, ast, AstNode};
 
@@ -16,6 +16,12 @@ pub(crate) fn goto_implementation(
 
     let krate = sema.to_module_def(position.file_id)?.krate();
 
+    if let Some(fn_def) = find_node_at_offset::<ast::FnDef>(&syntax, position.offset) {
+        if let Some(navs) = impls_for_trait_fn(&sema, &fn_def, krate) {
+            return Some(RangeInfo::new(fn_def.syntax().text_range(), navs));
+        }
+    }
+
     if let Some(nominal_def) = find_node_at_offset::<ast::NominalDef>(&syntax, position.offset) {
         return Some(RangeInfo::new(
             nominal_def.syntax().text_range(),
@@ -65,6 +71,32 @@ fn impls_for_trait(
     Some(impls.into_iter().map(|imp| imp.to_nav(sema.db)).collect())
 }
 
+fn impls_for_trait_fn(
+    sema: &Semantics<RootDatabase>,
+    node: &ast::FnDef,
+    krate: Crate,
+) -> Option<Vec<NavigationTarget>> {
+    let function = sema.to_def(node)?;
+    let tr = match function.as_assoc_item(sema.db)?.container(sema.db) {
+        AssocItemContainer::Trait(it) => it,
+        AssocItemContainer::ImplDef(_) => return None,
+    };
+    let name = function.name(sema.db);
+
+    let impls = ImplDef::for_trait(sema.db, krate, tr);
+
+    Some(
+        impls
+            .into_iter()
+            .flat_map(|imp| imp.items(sema.db))
+            .filter_map(|item| match item {
+                AssocItem::Function(it) if it.name(sema.db) == name => Some(it.to_nav(sema.db)),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use crate::mock_analysis::analysis_and_position;
@@ -198,4 +230,24 @@ mod tests {
             &["impl IMPL_DEF FileId(1) [0; 15)"],
         );
     }
+
+    #[test]
+    fn goto_implementation_for_trait_method() {
+        check_goto(
+            "
+            //- /lib.rs
+            trait T { fn foo<|>(&self); }
+            struct A;
+            impl T for A { fn foo(&self) {} }
+            struct B;
+            impl T for B {}
+            struct C;
+            impl T for C { fn foo(&self) {} }
+            ",
+            &[
+                "foo FN_DEF FileId(1) [52; 68) [55; 58)",
+                "foo FN_DEF FileId(1) [122; 138) [125; 128)",
+            ],
+        );
+    }
 }
@@ -4,9 +4,10 @@ use hir::{Adt, HirDisplay, Semantics, Type};
 use ra_ide_db::RootDatabase;
 use ra_prof::profile;
 use ra_syntax::{
-    ast::{self, ArgListOwner, AstNode, TypeAscriptionOwner},
-    match_ast, SmolStr, TextRange,
+    ast::{self, ArgListOwner, AstNode, NameOwner, TypeAscriptionOwner, TypeParamsOwner},
+    match_ast, Direction, NodeOrToken, SmolStr, SyntaxKind, SyntaxToken, TextRange, T,
 };
+use rustc_hash::FxHashSet;
 
 use crate::{FileId, FunctionSignature};
 
@@ -14,12 +15,22 @@ use crate::{FileId, FunctionSignature};
 pub struct InlayHintsOptions {
     pub type_hints: bool,
     pub parameter_hints: bool,
+    pub chaining_hints: bool,
+    pub closure_return_type_hints: bool,
+    pub lifetime_elision_hints: bool,
     pub max_length: Option<usize>,
 }
 
 impl Default for InlayHintsOptions {
     fn default() -> Self {
-        Self { type_hints: true, parameter_hints: true, max_length: None }
+        Self {
+            type_hints: true,
+            parameter_hints: true,
+            chaining_hints: true,
+            closure_return_type_hints: false,
+            lifetime_elision_hints: false,
+            max_length: None,
+        }
     }
 }
 
@@ -27,6 +38,9 @@ impl Default for InlayHintsOptions {
 pub enum InlayKind {
     TypeHint,
     ParameterHint,
+    ChainingHint,
+    ClosureReturnTypeHint,
+    LifetimeHint,
 }
 
 #[derive(Debug)]
@@ -47,11 +61,17 @@ pub(crate) fn inlay_hints(
 
     let mut res = Vec::new();
     for node in file.syntax().descendants() {
+        if let Some(expr) = ast::Expr::cast(node.clone()) {
+            get_chaining_hints(&mut res, &sema, options, expr);
+        }
+
         match_ast! {
             match node {
                 ast::CallExpr(it) => { get_param_name_hints(&mut res, &sema, options, ast::Expr::from(it)); },
                 ast::MethodCallExpr(it) => { get_param_name_hints(&mut res, &sema, options, ast::Expr::from(it)); },
                 ast::BindPat(it) => { get_bind_pat_hints(&mut res, &sema, options, it); },
+                ast::LambdaExpr(it) => { get_closure_return_type_hints(&mut res, &sema, options, it); },
+                ast::FnDef(it) => { get_lifetime_elision_hints(&mut res, options, it); },
                 _ => (),
             }
         }
@@ -59,6 +79,280 @@ pub(crate) fn inlay_hints(
     res
 }
 
+fn get_chaining_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    options: &InlayHintsOptions,
+    expr: ast::Expr,
+) -> Option<()> {
+    if !options.chaining_hints || !ends_chain_line(&expr) {
+        return None;
+    }
+
+    let ty = sema.type_of_expr(&expr)?;
+    if ty.is_unknown() {
+        return None;
+    }
+    let label = ty.display_truncated(sema.db, options.max_length).to_string();
+
+    // Don't repeat the hint of the previous line of the chain if the type is unchanged
+    let mut receiver = chain_receiver(&expr);
+    while let Some(prev) = receiver {
+        if ends_chain_line(&prev) {
+            let prev_ty = sema.type_of_expr(&prev)?;
+            if prev_ty.display_truncated(sema.db, options.max_length).to_string() == label {
+                return None;
+            }
+            break;
+        }
+        receiver = chain_receiver(&prev);
+    }
+
+    acc.push(InlayHint {
+        range: expr.syntax().text_range(),
+        kind: InlayKind::ChainingHint,
+        label: label.into(),
+    });
+    Some(())
+}
+
+/// Checks whether `expr` is a receiver in a method chain which continues on the
+/// next line, that is, whether it is followed by a newline and a `.`, ignoring
+/// comments.
+fn ends_chain_line(expr: &ast::Expr) -> bool {
+    let mut tokens = expr
+        .syntax()
+        .siblings_with_tokens(Direction::Next)
+        .skip(1)
+        .filter_map(NodeOrToken::into_token)
+        .filter(|t| match t.kind() {
+            SyntaxKind::WHITESPACE => t.text().contains('\n'),
+            SyntaxKind::COMMENT => false,
+            _ => true,
+        });
+    match (tokens.next(), tokens.next()) {
+        (Some(ws), Some(dot)) => ws.kind() == SyntaxKind::WHITESPACE && dot.kind() == T![.],
+        _ => false,
+    }
+}
+
+fn chain_receiver(expr: &ast::Expr) -> Option<ast::Expr> {
+    match expr {
+        ast::Expr::MethodCallExpr(it) => it.expr(),
+        ast::Expr::FieldExpr(it) => it.expr(),
+        ast::Expr::TryExpr(it) => it.expr(),
+        ast::Expr::AwaitExpr(it) => it.expr(),
+        _ => None,
+    }
+}
+
+fn get_closure_return_type_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    options: &InlayHintsOptions,
+    closure: ast::LambdaExpr,
+) -> Option<()> {
+    if !options.closure_return_type_hints || closure.ret_type().is_some() {
+        return None;
+    }
+
+    let body = closure.body()?;
+    if !body.syntax().text().contains_char('\n') {
+        return None;
+    }
+    let param_list = closure.param_list()?;
+
+    // The type of the body is `!` when it ends with a `return`, so use the
+    // return type inferred for the closure instead
+    let ty = sema.type_of_expr(&ast::Expr::from(closure.clone()))?.callable_ret(sema.db)?;
+    if ty.is_unknown() {
+        return None;
+    }
+
+    acc.push(InlayHint {
+        range: param_list.syntax().text_range(),
+        kind: InlayKind::ClosureReturnTypeHint,
+        label: ty.display_truncated(sema.db, options.max_length).to_string().into(),
+    });
+    Some(())
+}
+
+enum LifetimePosition {
+    /// A reference without a lifetime, pointing to its `&`, or an anonymous
+    /// `'_` lifetime, pointing to it
+    Elided(SyntaxToken),
+    Named(String),
+}
+
+fn get_lifetime_elision_hints(
+    acc: &mut Vec<InlayHint>,
+    options: &InlayHintsOptions,
+    fn_def: ast::FnDef,
+) -> Option<()> {
+    if !options.lifetime_elision_hints {
+        return None;
+    }
+
+    let param_list = fn_def.param_list()?;
+    let type_param_list = fn_def.type_param_list();
+
+    let mut inputs = Vec::new();
+    let mut has_ref_self = false;
+    if let Some(self_param) = param_list.self_param() {
+        if let Some(amp) = self_param.amp_token() {
+            has_ref_self = true;
+            inputs.push(match self_param.lifetime_token() {
+                Some(lifetime) => lifetime_position(lifetime),
+                None => LifetimePosition::Elided(amp),
+            });
+        } else if let Some(ty) = self_param.ascribed_type() {
+            // `self: &Self` is as much a reference self as `&self`
+            has_ref_self = match ty {
+                ast::TypeRef::ReferenceType(_) => true,
+                _ => false,
+            };
+            inputs.extend(lifetime_positions(&ty));
+        }
+    }
+    for ty in param_list.params().filter_map(|param| param.ascribed_type()) {
+        inputs.extend(lifetime_positions(&ty));
+    }
+
+    // Lifetimes of the enclosing impl or trait are in scope too
+    let outer_type_param_lists = fn_def.syntax().ancestors().skip(1).filter_map(|it| {
+        match_ast! {
+            match it {
+                ast::ImplDef(it) => it.type_param_list(),
+                ast::TraitDef(it) => it.type_param_list(),
+                _ => None,
+            }
+        }
+    });
+    let used_names: FxHashSet<String> = type_param_list
+        .iter()
+        .cloned()
+        .chain(outer_type_param_lists)
+        .flat_map(|it| it.lifetime_params())
+        .filter_map(|it| it.lifetime_token())
+        .map(|it| it.text().to_string())
+        .collect();
+    // `'a` to `'z`, then `'a1` to `'z1` and so on
+    let mut fresh_names = (0..)
+        .flat_map(|round| {
+            (b'a'..=b'z').map(move |c| match round {
+                0 => format!("'{}", c as char),
+                _ => format!("'{}{}", c as char, round),
+            })
+        })
+        .filter(|it| !used_names.contains(it));
+
+    let mut hints = Vec::new();
+    let mut fresh = Vec::new();
+    let mut input_names = Vec::new();
+    for position in inputs {
+        let name = match position {
+            LifetimePosition::Named(name) => name,
+            LifetimePosition::Elided(token) => {
+                let name = fresh_names.next().unwrap();
+                hints.push(elided_lifetime_hint(&token, &name));
+                fresh.push(name.clone());
+                name
+            }
+        };
+        input_names.push(name);
+    }
+
+    // The elision rules: the lifetime of `&self` wins, otherwise the output may
+    // only borrow from the single input lifetime.
+    let output_name = if has_ref_self {
+        input_names.first().cloned()
+    } else if input_names.len() == 1 {
+        input_names.pop()
+    } else {
+        None
+    };
+    if let Some(output_name) = output_name {
+        let outputs =
+            fn_def.ret_type().and_then(|it| it.type_ref()).map(|ty| lifetime_positions(&ty));
+        for position in outputs.into_iter().flatten() {
+            if let LifetimePosition::Elided(token) = position {
+                hints.push(elided_lifetime_hint(&token, &output_name));
+            }
+        }
+    }
+
+    if !fresh.is_empty() {
+        let l_angle = type_param_list.and_then(|it| {
+            it.syntax()
+                .children_with_tokens()
+                .filter_map(NodeOrToken::into_token)
+                .find(|it| it.kind() == T![<])
+        });
+        let hint = match l_angle {
+            Some(l_angle) => lifetime_hint(l_angle.text_range(), format!("{}, ", fresh.join(", "))),
+            None => lifetime_hint(
+                fn_def.name()?.syntax().text_range(),
+                format!("<{}>", fresh.join(", ")),
+            ),
+        };
+        hints.insert(0, hint);
+    }
+
+    acc.extend(hints);
+    Some(())
+}
+
+/// Collects the lifetime positions of a signature type, skipping nested `fn`
+/// pointers and `Fn` traits, which have their own elision scope.
+fn lifetime_positions(ty: &ast::TypeRef) -> Vec<LifetimePosition> {
+    ty.syntax()
+        .descendants()
+        .filter(|node| {
+            node.ancestors().take_while(|it| it != ty.syntax()).all(|it| match it.kind() {
+                SyntaxKind::FN_POINTER_TYPE | SyntaxKind::PARAM_LIST | SyntaxKind::RET_TYPE => {
+                    false
+                }
+                _ => true,
+            })
+        })
+        .filter_map(|node| {
+            if let Some(ref_type) = ast::ReferenceType::cast(node.clone()) {
+                return match ref_type.lifetime_token() {
+                    Some(lifetime) => Some(lifetime_position(lifetime)),
+                    None => ref_type.amp_token().map(LifetimePosition::Elided),
+                };
+            }
+            if node.kind() == SyntaxKind::LIFETIME_ARG {
+                return node
+                    .children_with_tokens()
+                    .filter_map(NodeOrToken::into_token)
+                    .find(|it| it.kind() == SyntaxKind::LIFETIME)
+                    .map(lifetime_position);
+            }
+            None
+        })
+        .collect()
+}
+
+fn lifetime_position(lifetime: SyntaxToken) -> LifetimePosition {
+    if lifetime.text() == "'_" {
+        LifetimePosition::Elided(lifetime)
+    } else {
+        LifetimePosition::Named(lifetime.text().to_string())
+    }
+}
+
+fn lifetime_hint(range: TextRange, label: String) -> InlayHint {
+    InlayHint { range, kind: InlayKind::LifetimeHint, label: label.into() }
+}
+
+/// A `&` gets the name in front of the referenced type, a `'_` is followed by
+/// `= 'name`, as the hint is rendered after it.
+fn elided_lifetime_hint(token: &SyntaxToken, name: &str) -> InlayHint {
+    let label = if token.kind() == T![&] { format!("{} ", name) } else { format!("= {}", name) };
+    lifetime_hint(token.text_range(), label)
+}
+
 fn get_param_name_hints(
     acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
@@ -238,7 +532,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions{ parameter_hints: true, type_hints: false, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions{ parameter_hints: true, type_hints: false, chaining_hints: false, closure_return_type_hints: false, lifetime_elision_hints: false, max_length: None}).unwrap(), @r###"
         [
             InlayHint {
                 range: [106; 107),
@@ -262,7 +556,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions{ type_hints: false, parameter_hints: false, max_length: None}).unwrap(), @r###"[]"###);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions{ type_hints: false, parameter_hints: false, chaining_hints: false, closure_return_type_hints: false, lifetime_elision_hints: false, max_length: None}).unwrap(), @r###"[]"###);
     }
 
     #[test]
@@ -274,7 +568,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions{ type_hints: true, parameter_hints: false, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions{ type_hints: true, parameter_hints: false, chaining_hints: false, closure_return_type_hints: false, lifetime_elision_hints: false, max_length: None}).unwrap(), @r###"
         [
             InlayHint {
                 range: [97; 99),
@@ -1052,4 +1346,289 @@ fn main() {
         "###
         );
     }
+
+    #[test]
+    fn chaining_hints_for_multiline_chains() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct A(B);
+impl A { fn into_b(self) -> B { self.0 } }
+struct B(C);
+impl B { fn into_c(self) -> C { self.0 } }
+struct C;
+
+fn main() {
+    let c = A(B(C))
+        .into_b() // This is a comment
+        .into_c();
+}"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions { parameter_hints: false, type_hints: false, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [148; 173),
+                kind: ChainingHint,
+                label: "B",
+            },
+            InlayHint {
+                range: [148; 155),
+                kind: ChainingHint,
+                label: "A",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn chaining_hints_skip_repeated_types() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct A;
+impl A { fn same(self) -> A { self } fn other(self) -> B { B } }
+struct B;
+impl B { fn same(self) -> B { self } }
+
+fn main() {
+    let b = A
+        .same()
+        .other()
+        .same()
+        .same();
+}"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions { parameter_hints: false, type_hints: false, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [150; 184),
+                kind: ChainingHint,
+                label: "B",
+            },
+            InlayHint {
+                range: [150; 151),
+                kind: ChainingHint,
+                label: "A",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn closure_return_type_hints_for_multiline_closures() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let f = |x: u32| {
+        x
+    };
+    let g = |x: u32| x;
+}"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions { parameter_hints: false, type_hints: false, chaining_hints: false, closure_return_type_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [25; 33),
+                kind: ClosureReturnTypeHint,
+                label: "u32",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn lifetime_elision_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+impl S {
+    fn get(&self, key: &str) -> &str { "" }
+}
+fn first(x: &str, y: &str) -> u32 { 0 }
+fn single<T>(x: &T) -> &T { x }
+"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions { parameter_hints: false, type_hints: false, chaining_hints: false, lifetime_elision_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [27; 30),
+                kind: LifetimeHint,
+                label: "<'a, 'b>",
+            },
+            InlayHint {
+                range: [31; 32),
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+            InlayHint {
+                range: [43; 44),
+                kind: LifetimeHint,
+                label: "'b ",
+            },
+            InlayHint {
+                range: [52; 53),
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+            InlayHint {
+                range: [69; 74),
+                kind: LifetimeHint,
+                label: "<'a, 'b>",
+            },
+            InlayHint {
+                range: [78; 79),
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+            InlayHint {
+                range: [87; 88),
+                kind: LifetimeHint,
+                label: "'b ",
+            },
+            InlayHint {
+                range: [115; 116),
+                kind: LifetimeHint,
+                label: "'a, ",
+            },
+            InlayHint {
+                range: [122; 123),
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+            InlayHint {
+                range: [129; 130),
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn lifetime_elision_hints_in_impl() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S<'a>(&'a str);
+impl<'a> S<'a> {
+    fn get(&self, key: &'_ str) -> &str { "" }
+}
+"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions { parameter_hints: false, type_hints: false, chaining_hints: false, lifetime_elision_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [48; 51),
+                kind: LifetimeHint,
+                label: "<'b, 'c>",
+            },
+            InlayHint {
+                range: [52; 53),
+                kind: LifetimeHint,
+                label: "'b ",
+            },
+            InlayHint {
+                range: [65; 67),
+                kind: LifetimeHint,
+                label: "= 'c",
+            },
+            InlayHint {
+                range: [76; 77),
+                kind: LifetimeHint,
+                label: "'b ",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn closure_return_type_hints_use_the_inferred_return_type() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let f = |x: u32| {
+        return x;
+    };
+}"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions { parameter_hints: false, type_hints: false, chaining_hints: false, closure_return_type_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [25; 33),
+                kind: ClosureReturnTypeHint,
+                label: "u32",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn lifetime_elision_hints_for_typed_self() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+impl S {
+    fn get(self: &Self, key: &str) -> &str { "" }
+}
+"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsOptions { parameter_hints: false, type_hints: false, chaining_hints: false, lifetime_elision_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [27; 30),
+                kind: LifetimeHint,
+                label: "<'a, 'b>",
+            },
+            InlayHint {
+                range: [37; 38),
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+            InlayHint {
+                range: [49; 50),
+                kind: LifetimeHint,
+                label: "'b ",
+            },
+            InlayHint {
+                range: [58; 59),
+                kind: LifetimeHint,
+                label: "'a ",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn lifetime_elision_hints_past_the_alphabet() {
+        let params: Vec<String> = (0..27).map(|i| format!("x{}: &u8", i)).collect();
+        let (analysis, file_id) = single_file(&format!("fn f({}) {{}}", params.join(", ")));
+
+        let hints = analysis
+            .inlay_hints(
+                file_id,
+                &InlayHintsOptions {
+                    parameter_hints: false,
+                    type_hints: false,
+                    chaining_hints: false,
+                    lifetime_elision_hints: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let labels: Vec<&str> = hints.iter().map(|it| it.label.as_str()).collect();
+        assert_eq!(labels.len(), 28);
+        assert!(labels[0].ends_with("'y, 'z, 'a1>"));
+        assert_eq!(labels[27], "'a1 ");
+    }
 }
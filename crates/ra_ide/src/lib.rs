@@ -22,10 +22,12 @@ mod goto_type_definition;
 mod extend_selection;
 mod hover;
 mod call_hierarchy;
+mod type_hierarchy;
 mod call_info;
 mod syntax_highlighting;
 mod parent_module;
 mod references;
+mod highlight_related;
 mod impls;
 mod assists;
 mod diagnostics;
@@ -35,6 +37,8 @@ mod join_lines;
 mod typing;
 mod matching_brace;
 mod display;
+mod doc_links;
+mod document_links;
 mod inlay_hints;
 mod expand_macro;
 mod ssr;
@@ -60,19 +64,26 @@ use ra_syntax::{SourceFile, TextRange, TextUnit};
 use crate::display::ToNav;
 
 pub use crate::{
-    assists::{Assist, AssistId},
+    assists::{Assist, AssistId, AssistOptions, UnresolvedAssist},
     call_hierarchy::CallItem,
-    completion::{CompletionItem, CompletionItemKind, CompletionOptions, InsertTextFormat},
+    completion::{
+        CompletionItem, CompletionItemKind, CompletionOptions, CompletionScore, InsertTextFormat,
+        Snippet, SnippetScope,
+    },
     diagnostics::Severity,
     display::{file_structure, FunctionSignature, NavigationTarget, StructureNode},
+    document_links::{DocumentLink, DocumentLinkTarget},
     expand_macro::ExpandedMacro,
     folding_ranges::{Fold, FoldKind},
-    hover::HoverResult,
+    hover::{HoverAction, HoverGotoTypeData, HoverResult},
     inlay_hints::{InlayHint, InlayHintsOptions, InlayKind},
-    references::{Declaration, Reference, ReferenceAccess, ReferenceKind, ReferenceSearchResult},
+    references::{
+        Declaration, Reference, ReferenceAccess, ReferenceKind, ReferenceSearchResult,
+        RenameConflict, RenameResult,
+    },
     runnables::{Runnable, RunnableKind, TestId},
     source_change::{FileSystemEdit, SourceChange, SourceFileEdit},
-    ssr::SsrError,
+    ssr::{SsrError, SsrMatch},
     syntax_highlighting::{
         Highlight, HighlightModifier, HighlightModifiers, HighlightTag, HighlightedRange,
     },
@@ -138,6 +149,11 @@ impl AnalysisHost {
     pub fn new(lru_capacity: Option<usize>) -> AnalysisHost {
         AnalysisHost { db: RootDatabase::new(lru_capacity) }
     }
+
+    pub fn update_lru_capacity(&mut self, lru_capacity: Option<usize>) {
+        self.db.update_lru_capacity(lru_capacity);
+    }
+
     /// Returns a snapshot of the current state, which you can query for
     /// semantic information.
     pub fn analysis(&self) -> Analysis {
@@ -329,6 +345,11 @@ impl Analysis {
         self.with_db(|db| folding_ranges::folding_ranges(&db.parse(file_id).tree()))
     }
 
+    /// Returns the links to other files and to URLs in the file.
+    pub fn document_links(&self, file_id: FileId) -> Cancelable<Vec<DocumentLink>> {
+        self.with_db(|db| document_links::document_links(db, file_id))
+    }
+
     /// Fuzzy searches for a symbol.
     pub fn symbol_search(&self, query: Query) -> Cancelable<Vec<NavigationTarget>> {
         self.with_db(|db| {
@@ -363,6 +384,13 @@ impl Analysis {
         self.with_db(|db| goto_type_definition::goto_type_definition(db, position))
     }
 
+    /// Finds the keywords and expressions related to the one at point: the
+    /// exit points of a function, the breaks of a loop or the await points of
+    /// an async function.
+    pub fn highlight_related(&self, position: FilePosition) -> Cancelable<Option<Vec<TextRange>>> {
+        self.with_db(|db| highlight_related::highlight_related(db, position))
+    }
+
     /// Finds all usages of the reference at point.
     pub fn find_all_refs(
         &self,
@@ -400,6 +428,25 @@ impl Analysis {
         self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
     }
 
+    /// Computes the root of the type hierarchy, a trait or an ADT, for the
+    /// given file position.
+    pub fn type_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<RangeInfo<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy(db, position))
+    }
+
+    /// Computes the supertraits of a trait, or the traits implemented by an ADT.
+    pub fn supertypes(&self, position: FilePosition) -> Cancelable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::supertypes(db, position))
+    }
+
+    /// Computes the implementors of a trait.
+    pub fn subtypes(&self, position: FilePosition) -> Cancelable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::subtypes(db, position))
+    }
+
     /// Returns a `mod name;` declaration which created the current module.
     pub fn parent_module(&self, position: FilePosition) -> Cancelable<Vec<NavigationTarget>> {
         self.with_db(|db| parent_module::parent_module(db, position))
@@ -451,8 +498,29 @@ impl Analysis {
 
     /// Computes assists (aka code actions aka intentions) for the given
     /// position.
-    pub fn assists(&self, frange: FileRange) -> Cancelable<Vec<Assist>> {
-        self.with_db(|db| assists::assists(db, frange))
+    pub fn assists(&self, options: &AssistOptions, frange: FileRange) -> Cancelable<Vec<Assist>> {
+        self.with_db(|db| assists::assists(db, options, frange))
+    }
+
+    /// Computes labels of the assists applicable at the given position,
+    /// without their edits.
+    pub fn unresolved_assists(
+        &self,
+        options: &AssistOptions,
+        frange: FileRange,
+    ) -> Cancelable<Vec<UnresolvedAssist>> {
+        self.with_db(|db| assists::unresolved(db, options, frange))
+    }
+
+    /// Computes the edit of a single assist returned by `unresolved_assists`.
+    pub fn resolve_assist(
+        &self,
+        options: &AssistOptions,
+        frange: FileRange,
+        id: &str,
+        label: &str,
+    ) -> Cancelable<Option<Assist>> {
+        self.with_db(|db| assists::resolve(db, options, frange, id, label))
     }
 
     /// Computes the set of diagnostics for the given file.
@@ -460,6 +528,11 @@ impl Analysis {
         self.with_db(|db| diagnostics::diagnostics(db, file_id))
     }
 
+    /// Returns the range of the name which can be renamed at the position.
+    pub fn prepare_rename(&self, position: FilePosition) -> Cancelable<Option<RangeInfo<()>>> {
+        self.with_db(|db| references::prepare_rename(db, position))
+    }
+
     /// Returns the edit required to rename reference at the position to the new
     /// name.
     pub fn rename(
@@ -470,13 +543,33 @@ impl Analysis {
         self.with_db(|db| references::rename(db, position, new_name))
     }
 
+    /// Like `rename`, but also reports the usages generated by macro bodies
+    /// and, if `find_conflicts` is set, the places where the rename would
+    /// change the meaning of the code.
+    pub fn rename_with_conflicts(
+        &self,
+        position: FilePosition,
+        new_name: &str,
+        find_conflicts: bool,
+    ) -> Cancelable<Option<RangeInfo<RenameResult>>> {
+        self.with_db(|db| references::rename_with_conflicts(db, position, new_name, find_conflicts))
+    }
+
+    /// Finds the matches of a structural search query without replacing them.
+    pub fn structural_search(&self, query: &str) -> Cancelable<Result<Vec<SsrMatch>, SsrError>> {
+        self.with_db(|db| ssr::find_matches(query, db))
+    }
+
+    /// Replaces the matches of a structural search query. If `selected` is
+    /// given, only the matches with one of these ranges are replaced.
     pub fn structural_search_replace(
         &self,
         query: &str,
         parse_only: bool,
+        selected: Option<&[FileRange]>,
     ) -> Cancelable<Result<SourceChange, SsrError>> {
         self.with_db(|db| {
-            let edits = ssr::parse_search_replace(query, parse_only, db)?;
+            let edits = ssr::parse_search_replace(query, parse_only, selected, db)?;
             Ok(SourceChange::source_file_edits("ssr", edits))
         })
     }
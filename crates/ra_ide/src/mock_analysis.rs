@@ -16,6 +16,7 @@ use crate::{
 #[derive(Debug, Default)]
 pub struct MockAnalysis {
     files: Vec<(String, String)>,
+    library_dirs: Vec<String>,
 }
 
 impl MockAnalysis {
@@ -74,6 +75,11 @@ impl MockAnalysis {
         self.files.push((path.to_string(), text));
         FileRange { file_id, range }
     }
+    /// Puts the files in `dir` into a library source root, like the ones of
+    /// the sysroot and crates.io, rather than into the local one.
+    pub fn add_library_dir(&mut self, dir: &str) {
+        self.library_dirs.push(dir.to_string());
+    }
     pub fn id_of(&self, path: &str) -> FileId {
         let (idx, _) = self
             .files
@@ -85,13 +91,23 @@ impl MockAnalysis {
     }
     pub fn analysis_host(self) -> AnalysisHost {
         let mut host = AnalysisHost::default();
-        let source_root = SourceRootId(0);
+        let local_root = SourceRootId(0);
+        let library_root = SourceRootId(1);
         let mut change = AnalysisChange::new();
-        change.add_root(source_root, true);
+        change.add_root(local_root, true);
+        if !self.library_dirs.is_empty() {
+            change.add_root(library_root, false);
+        }
+        let library_dirs = self.library_dirs;
         let mut crate_graph = CrateGraph::default();
         let mut root_crate = None;
         for (i, (path, contents)) in self.files.into_iter().enumerate() {
             assert!(path.starts_with('/'));
+            let source_root = if library_dirs.iter().any(|dir| path.starts_with(dir.as_str())) {
+                library_root
+            } else {
+                local_root
+            };
             let path = RelativePathBuf::from_path(&path[1..]).unwrap();
             let file_id = FileId(i as u32 + 1);
             let cfg_options = CfgOptions::default();
@@ -13,7 +13,7 @@ mod rename;
 
 use hir::Semantics;
 use ra_ide_db::{
-    defs::{classify_name, classify_name_ref, Definition},
+    defs::{classify_name, classify_name_ref, classify_operator, classify_token, Definition},
     search::SearchScope,
     RootDatabase,
 };
@@ -26,7 +26,8 @@ use ra_syntax::{
 
 use crate::{display::TryToNav, FilePosition, FileRange, NavigationTarget, RangeInfo};
 
-pub(crate) use self::rename::rename;
+pub(crate) use self::rename::{prepare_rename, rename, rename_with_conflicts};
+pub use self::rename::{RenameConflict, RenameResult};
 
 pub use ra_ide_db::search::{Reference, ReferenceAccess, ReferenceKind};
 
@@ -131,10 +132,15 @@ fn find_name(
         let range = name.syntax().text_range();
         return Some(RangeInfo::new(range, def));
     }
-    let name_ref = find_node_at_offset::<ast::NameRef>(&syntax, position.offset)?;
-    let def = classify_name_ref(sema, &name_ref)?.definition();
-    let range = name_ref.syntax().text_range();
-    Some(RangeInfo::new(range, def))
+    if let Some(name_ref) = find_node_at_offset::<ast::NameRef>(&syntax, position.offset) {
+        let def = classify_name_ref(sema, &name_ref)?.definition();
+        let range = name_ref.syntax().text_range();
+        return Some(RangeInfo::new(range, def));
+    }
+    syntax.token_at_offset(position.offset).find_map(|token| {
+        let def = classify_operator(sema, &token).or_else(|| classify_token(sema, &token))?;
+        Some(RangeInfo::new(token.text_range(), def))
+    })
 }
 
 fn decl_access(def: &Definition, syntax: &SyntaxNode, range: TextRange) -> Option<ReferenceAccess> {
@@ -180,7 +186,7 @@ mod tests {
 
     use crate::{
         mock_analysis::{analysis_and_position, single_file_with_position, MockAnalysis},
-        Declaration, Reference, ReferenceSearchResult, SearchScope,
+        Declaration, FileId, Reference, ReferenceKind, ReferenceSearchResult, SearchScope,
     };
 
     #[test]
@@ -336,6 +342,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_all_refs_for_operator() {
+        let code = r#"
+            #[lang = "add"]
+            trait Add<Rhs = Self> {
+                type Output;
+                fn add(self, rhs: Rhs) -> Self::Output;
+            }
+            struct Foo;
+            impl Add for Foo {
+                type Output = Foo;
+                fn add(self, rhs: Foo) -> Foo { rhs }
+            }
+            fn bar(a: Foo, b: Foo, c: Foo, d: Foo) {
+                a + b;
+                c +<|> d;
+            }
+        "#;
+
+        let refs = get_all_refs(code);
+        check_result(
+            refs,
+            "add FN_DEF FileId(1) [270; 307) [273; 276) Other",
+            &["FileId(1) [393; 394) Operator", "FileId(1) [416; 417) Operator"],
+        );
+    }
+
+    #[test]
+    fn test_find_all_refs_for_library_operator_only_searches_local_crates() {
+        let code = r#"
+            //- /main.rs
+            fn main(a: core::Foo, b: core::Foo) {
+                a + b;
+            }
+
+            //- /core/lib.rs
+            #[lang = "add"]
+            pub trait Add<Rhs = Self> {
+                type Output;
+                fn add<|>(self, rhs: Rhs) -> Self::Output;
+            }
+            pub struct Foo;
+            impl Add for Foo {
+                type Output = Foo;
+                fn add(self, rhs: Foo) -> Foo { rhs }
+            }
+            fn bar(a: Foo, b: Foo) {
+                a + b;
+            }
+        "#;
+        let (mut mock, pos) = MockAnalysis::with_files_and_position(code);
+        mock.add_library_dir("/core/");
+        let main = mock.id_of("/main.rs");
+        let refs = mock.analysis().find_all_refs(pos, None).unwrap().unwrap();
+        let refs: Vec<(FileId, ReferenceKind)> =
+            refs.references().iter().map(|it| (it.file_range.file_id, it.kind.clone())).collect();
+        assert_eq!(refs, vec![(main, ReferenceKind::Operator)]);
+    }
+
     #[test]
     fn test_find_all_refs_field_name() {
         let code = r#"
@@ -543,6 +608,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_all_refs_generated_by_macro_body() {
+        let code = r#"
+        macro_rules! call_foo { () => { foo() } }
+
+        fn foo<|>() {}
+
+        fn main() {
+            foo();
+            let _ = call_foo!();
+        }"#;
+
+        let refs = get_all_refs(code);
+        check_result(
+            refs,
+            "foo FN_DEF FileId(1) [60; 71) [63; 66) Other",
+            &["FileId(1) [105; 108) StructLiteral", "FileId(1) [132; 143) MacroGenerated"],
+        );
+    }
+
     #[test]
     fn test_basic_highlight_read_write() {
         let code = r#"
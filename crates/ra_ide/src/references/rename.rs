@@ -1,41 +1,117 @@
 //! FIXME: write short doc here
 
-use hir::{ModuleSource, Semantics};
-use ra_db::{RelativePath, RelativePathBuf, SourceDatabaseExt};
-use ra_ide_db::RootDatabase;
+use hir::{
+    Adt, AsAssocItem, AssocItem, AssocItemContainer, ModuleDef, ModuleSource, ScopeDef, Semantics,
+    StructKind,
+};
+use ra_db::{FileId, RelativePath, RelativePathBuf, SourceDatabaseExt};
+use ra_ide_db::{
+    defs::{
+        child_token, classify_name_ref, is_body_boundary, label_lifetime, macro_rules_matcher,
+        matcher_metavars, Definition, NameRefClass,
+    },
+    RootDatabase,
+};
 use ra_syntax::{
-    algo::find_node_at_offset, ast, lex_single_valid_syntax_kind, AstNode, SyntaxKind, SyntaxNode,
+    algo::{find_covering_element, find_node_at_offset},
+    ast, lex_single_valid_syntax_kind, match_ast, AstNode, SyntaxKind,
+    SyntaxKind::{LIFETIME, LIFETIME_PARAM, TYPE_PARAM_LIST},
+    SyntaxNode, SyntaxToken,
 };
 use ra_text_edit::TextEdit;
+use rustc_hash::FxHashSet;
 
 use crate::{
-    FilePosition, FileSystemEdit, RangeInfo, Reference, ReferenceKind, SourceChange,
-    SourceFileEdit, TextRange,
+    display::TryToNav, FilePosition, FileRange, FileSystemEdit, NavigationTarget, RangeInfo,
+    Reference, ReferenceKind, SourceChange, SourceFileEdit, TextRange,
 };
 
-use super::find_all_refs;
+use super::{find_all_refs, find_name, ReferenceSearchResult};
+
+/// A place where renaming would change the meaning of the code, because the
+/// new name is already used by another item.
+#[derive(Debug)]
+pub struct RenameConflict {
+    /// The reference (or declaration) which would resolve differently.
+    pub range: FileRange,
+    /// The item already using the new name.
+    pub conflicting: NavigationTarget,
+}
+
+/// A rename, together with the places where it would change the meaning of
+/// the code. Both are computed from the same reference search.
+#[derive(Debug)]
+pub struct RenameResult {
+    pub change: SourceChange,
+    /// Empty unless conflicts were asked for.
+    pub conflicts: Vec<RenameConflict>,
+    /// The macro calls whose body uses the renamed item. They keep using the
+    /// old name, as the rename can't edit them.
+    pub macro_generated: Vec<FileRange>,
+}
 
 pub(crate) fn rename(
     db: &RootDatabase,
     position: FilePosition,
     new_name: &str,
 ) -> Option<RangeInfo<SourceChange>> {
-    match lex_single_valid_syntax_kind(new_name)? {
-        SyntaxKind::IDENT | SyntaxKind::UNDERSCORE => (),
+    let RangeInfo { range, info } = rename_with_conflicts(db, position, new_name, false)?;
+    Some(RangeInfo::new(range, info.change))
+}
+
+pub(crate) fn rename_with_conflicts(
+    db: &RootDatabase,
+    position: FilePosition,
+    new_name: &str,
+    find_conflicts: bool,
+) -> Option<RangeInfo<RenameResult>> {
+    let new_kind = match lex_single_valid_syntax_kind(new_name)? {
+        SyntaxKind::IDENT | SyntaxKind::UNDERSCORE => SyntaxKind::IDENT,
+        SyntaxKind::LIFETIME if new_name != "'static" && new_name != "'_" => SyntaxKind::LIFETIME,
         _ => return None,
-    }
+    };
 
     let sema = Semantics::new(db);
     let source_file = sema.parse(position.file_id);
-    if let Some((ast_name, ast_module)) =
+    let refs = find_all_refs(db, position, None);
+    let references = refs.as_ref().map_or(&[][..], |it| it.info.references());
+    let conflicts = if find_conflicts {
+        rename_conflicts(&sema, position, new_name, references)
+    } else {
+        Vec::new()
+    };
+    let macro_generated = references
+        .iter()
+        .filter(|it| it.kind == ReferenceKind::MacroGenerated)
+        .map(|it| it.file_range)
+        .collect();
+
+    let RangeInfo { range, info: change } = if let Some((ast_name, ast_module)) =
         find_name_and_module_at_offset(source_file.syntax(), position)
     {
         let range = ast_name.syntax().text_range();
-        rename_mod(&sema, &ast_name, &ast_module, position, new_name)
-            .map(|info| RangeInfo::new(range, info))
+        let change = rename_mod(&sema, &ast_name, &ast_module, position, new_name, refs)?;
+        RangeInfo::new(range, change)
     } else {
-        rename_reference(sema.db, position, new_name)
+        rename_reference(refs?, new_name, new_kind)?
+    };
+    Some(RangeInfo::new(range, RenameResult { change, conflicts, macro_generated }))
+}
+
+/// Returns the range of the name at `position` if it can be renamed. Unlike
+/// `rename`, this doesn't need a new name: a placeholder one can't stand in
+/// for it, as lifetimes and labels can only be renamed to other lifetimes.
+pub(crate) fn prepare_rename(db: &RootDatabase, position: FilePosition) -> Option<RangeInfo<()>> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    if let Some((ast_name, _)) = find_name_and_module_at_offset(source_file.syntax(), position) {
+        return Some(RangeInfo::new(ast_name.syntax().text_range(), ()));
     }
+    let RangeInfo { range, info: refs } = find_all_refs(db, position, None)?;
+    if !refs.into_iter().any(|it| is_editable(&it)) {
+        return None;
+    }
+    Some(RangeInfo::new(range, ()))
 }
 
 fn find_name_and_module_at_offset(
@@ -78,6 +154,7 @@ fn rename_mod(
     ast_module: &ast::Module,
     position: FilePosition,
     new_name: &str,
+    refs: Option<RangeInfo<ReferenceSearchResult>>,
 ) -> Option<SourceChange> {
     let mut source_file_edits = Vec::new();
     let mut file_system_edits = Vec::new();
@@ -116,10 +193,11 @@ fn rename_mod(
     };
     source_file_edits.push(edit);
 
-    if let Some(RangeInfo { range: _, info: refs }) = find_all_refs(sema.db, position, None) {
+    if let Some(RangeInfo { range: _, info: refs }) = refs {
         let ref_edits = refs
             .references
             .into_iter()
+            .filter(is_editable)
             .map(|reference| source_edit_from_reference(reference, new_name));
         source_file_edits.extend(ref_edits);
     }
@@ -128,14 +206,24 @@ fn rename_mod(
 }
 
 fn rename_reference(
-    db: &RootDatabase,
-    position: FilePosition,
+    refs: RangeInfo<ReferenceSearchResult>,
     new_name: &str,
+    new_kind: SyntaxKind,
 ) -> Option<RangeInfo<SourceChange>> {
-    let RangeInfo { range, info: refs } = find_all_refs(db, position, None)?;
+    let RangeInfo { range, info: refs } = refs;
+
+    // Lifetimes and labels can only be renamed to lifetimes, and everything else to identifiers
+    let kind = match refs.decl_target().kind() {
+        SyntaxKind::LIFETIME_PARAM | SyntaxKind::LABEL => SyntaxKind::LIFETIME,
+        _ => SyntaxKind::IDENT,
+    };
+    if kind != new_kind {
+        return None;
+    }
 
     let edit = refs
         .into_iter()
+        .filter(is_editable)
         .map(|reference| source_edit_from_reference(reference, new_name))
         .collect::<Vec<_>>();
 
@@ -146,6 +234,344 @@ fn rename_reference(
     Some(RangeInfo::new(range, SourceChange::source_file_edits("rename", edit)))
 }
 
+/// Occurrences produced by a macro body or by an overloaded operator have no
+/// name which could be renamed at the reference.
+fn is_editable(reference: &Reference) -> bool {
+    reference.kind != ReferenceKind::MacroGenerated && reference.kind != ReferenceKind::Operator
+}
+
+/// Finds the places where renaming the item at `position`, which has the
+/// given `references`, to `new_name` would silently change which item a name
+/// refers to: another item with the same name in the same impl, trait, struct
+/// or module, a binding in scope at a reference which would shadow the renamed
+/// item, or a reference to another item which the renamed item would shadow.
+fn rename_conflicts(
+    sema: &Semantics<RootDatabase>,
+    position: FilePosition,
+    new_name: &str,
+    references: &[Reference],
+) -> Vec<RenameConflict> {
+    let db = sema.db;
+    let syntax = sema.parse(position.file_id).syntax().clone();
+    let opt_name = find_node_at_offset::<ast::Name>(&syntax, position.offset);
+    let def = match find_name(sema, &syntax, position, opt_name) {
+        Some(it) => it.info,
+        None => return Vec::new(),
+    };
+    match def {
+        Definition::LifetimeParam(decl)
+        | Definition::Label(decl)
+        | Definition::MacroMetavar(decl) => {
+            return token_conflicts(sema, &def, decl, new_name);
+        }
+        _ => (),
+    }
+    let old_name = match def.name(db) {
+        Some(it) => it.to_string(),
+        None => return Vec::new(),
+    };
+    let decl_nav = match def.try_to_nav(db) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    if old_name == new_name {
+        return Vec::new();
+    }
+    let decl_range = FileRange {
+        file_id: decl_nav.file_id(),
+        range: decl_nav.focus_range().unwrap_or_else(|| decl_nav.range()),
+    };
+
+    let mut res: Vec<RenameConflict> = sibling_items(db, &def)
+        .into_iter()
+        .filter(|it| it != &def && it.name(db).map_or(false, |it| it.to_string() == new_name))
+        .filter(|it| share_namespace(db, it, &def))
+        .filter_map(|it| it.try_to_nav(db))
+        .map(|conflicting| RenameConflict { range: decl_range, conflicting })
+        .collect();
+
+    let mut files: FxHashSet<FileId> = references.iter().map(|it| it.file_range.file_id).collect();
+    files.insert(decl_range.file_id);
+
+    // References to the renamed item which another item would shadow
+    for reference in references.iter() {
+        if !is_editable(reference) {
+            continue;
+        }
+        let file = sema.parse(reference.file_range.file_id);
+        let name_ref = match find_node_at_offset::<ast::NameRef>(
+            file.syntax(),
+            reference.file_range.range.start(),
+        ) {
+            Some(it) => it,
+            None => continue,
+        };
+        if let Some((ours, (other, conflicting))) =
+            lookup_in_scope(sema, &name_ref, &def, &old_name, new_name)
+        {
+            if other < ours {
+                res.extend(conflicting.try_to_nav(db).map(|conflicting| RenameConflict {
+                    range: reference.file_range,
+                    conflicting,
+                }));
+            }
+        }
+    }
+
+    // References to other items which the renamed item would shadow
+    for file_id in files {
+        let file = sema.parse(file_id);
+        for name_ref in file.syntax().descendants().filter_map(ast::NameRef::cast) {
+            if name_ref.text().as_str() != new_name {
+                continue;
+            }
+            let referenced = match classify_name_ref(sema, &name_ref) {
+                Some(NameRefClass::Definition(it)) => it,
+                Some(NameRefClass::FieldShorthand { local, .. }) => Definition::Local(local),
+                None => continue,
+            };
+            if referenced == def {
+                continue;
+            }
+            if let Some((ours, (other, _))) =
+                lookup_in_scope(sema, &name_ref, &def, &old_name, new_name)
+            {
+                if ours < other {
+                    res.extend(referenced.try_to_nav(db).map(|conflicting| RenameConflict {
+                        range: FileRange { file_id, range: name_ref.syntax().text_range() },
+                        conflicting,
+                    }));
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// Finds the lifetimes, labels or metavariables named `new_name` which the
+/// lifetime, label or metavariable declared at `decl` would collide with: the
+/// other lifetime parameters of its binder, the labels of the loops and blocks
+/// around or inside its own, or the other metavariables of its macro arm.
+fn token_conflicts(
+    sema: &Semantics<RootDatabase>,
+    def: &Definition,
+    decl: FileRange,
+    new_name: &str,
+) -> Vec<RenameConflict> {
+    let file = sema.parse(decl.file_id);
+    let token = match find_covering_element(file.syntax(), decl.range).into_token() {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let others = match def {
+        Definition::LifetimeParam(_) => sibling_lifetime_params(&token),
+        Definition::Label(_) => enclosing_and_nested_labels(&token),
+        Definition::MacroMetavar(_) => arm_metavars(&token),
+        _ => Vec::new(),
+    };
+    others
+        .into_iter()
+        .filter(|it| it != &token && it.text() == new_name)
+        .filter_map(|it| {
+            let range = FileRange { file_id: decl.file_id, range: it.text_range() };
+            let other = match def {
+                Definition::LifetimeParam(_) => Definition::LifetimeParam(range),
+                Definition::Label(_) => Definition::Label(range),
+                _ => Definition::MacroMetavar(range),
+            };
+            other.try_to_nav(sema.db)
+        })
+        .map(|conflicting| RenameConflict { range: decl, conflicting })
+        .collect()
+}
+
+fn sibling_lifetime_params(token: &SyntaxToken) -> Vec<SyntaxToken> {
+    let params = match token.parent().parent() {
+        Some(it) if it.kind() == TYPE_PARAM_LIST => it,
+        _ => return Vec::new(),
+    };
+    params
+        .children()
+        .filter(|it| it.kind() == LIFETIME_PARAM)
+        .filter_map(|it| child_token(&it, LIFETIME))
+        .collect()
+}
+
+fn enclosing_and_nested_labels(token: &SyntaxToken) -> Vec<SyntaxToken> {
+    let labeled = match token.parent().parent() {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let enclosing = labeled.ancestors().skip(1).take_while(|it| !is_body_boundary(it));
+    let nested = labeled.descendants().skip(1).filter(|it| {
+        it.ancestors().take_while(|anc| anc != &labeled).all(|anc| !is_body_boundary(&anc))
+    });
+    enclosing.chain(nested).filter_map(|it| label_lifetime(&it)).collect()
+}
+
+fn arm_metavars(token: &SyntaxToken) -> Vec<SyntaxToken> {
+    match macro_rules_matcher(token) {
+        Some(matcher) => matcher_metavars(&matcher).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns the items declared next to `def`, which can't share its name.
+fn sibling_items(db: &RootDatabase, def: &Definition) -> Vec<Definition> {
+    let assoc_item = match def {
+        Definition::StructField(field) => {
+            return field
+                .parent_def(db)
+                .fields(db)
+                .into_iter()
+                .map(Definition::StructField)
+                .collect();
+        }
+        Definition::ModuleDef(ModuleDef::EnumVariant(variant)) => {
+            return variant
+                .parent_enum(db)
+                .variants(db)
+                .into_iter()
+                .map(|it| Definition::ModuleDef(it.into()))
+                .collect();
+        }
+        Definition::ModuleDef(ModuleDef::Function(it)) => it.as_assoc_item(db),
+        Definition::ModuleDef(ModuleDef::Const(it)) => it.as_assoc_item(db),
+        Definition::ModuleDef(ModuleDef::TypeAlias(it)) => it.as_assoc_item(db),
+        Definition::ModuleDef(_) => None,
+        _ => return Vec::new(),
+    };
+    let items = match assoc_item.map(|it| it.container(db)) {
+        Some(AssocItemContainer::Trait(it)) => it.items(db),
+        Some(AssocItemContainer::ImplDef(it)) => it.items(db),
+        None => {
+            return match def.module(db) {
+                Some(module) => module
+                    .scope(db, None)
+                    .into_iter()
+                    .filter_map(|(_, it)| scope_def_to_definition(it))
+                    .collect(),
+                None => Vec::new(),
+            };
+        }
+    };
+    items
+        .into_iter()
+        .map(|it| {
+            let def = match it {
+                AssocItem::Function(it) => ModuleDef::Function(it),
+                AssocItem::Const(it) => ModuleDef::Const(it),
+                AssocItem::TypeAlias(it) => ModuleDef::TypeAlias(it),
+            };
+            Definition::ModuleDef(def)
+        })
+        .collect()
+}
+
+/// Looks up the renamed item by its old name and the first other item named
+/// `new_name` in the scope of `name_ref`. Returns their positions in the
+/// scope, inner scopes first, if both are visible.
+fn lookup_in_scope(
+    sema: &Semantics<RootDatabase>,
+    name_ref: &ast::NameRef,
+    def: &Definition,
+    old_name: &str,
+    new_name: &str,
+) -> Option<(usize, (usize, Definition))> {
+    // Qualified paths, fields and method calls are unaffected by what is in scope
+    match_ast! {
+        match (name_ref.syntax().parent()?) {
+            ast::PathSegment(segment) => {
+                if segment.parent_path().qualifier().is_some() {
+                    return None;
+                }
+            },
+            ast::RecordField(_it) => (),
+            _ => return None,
+        }
+    }
+
+    let mut idx = 0;
+    let mut ours = None;
+    let mut other = None;
+    sema.scope(name_ref.syntax()).process_all_names(&mut |name, scope_def| {
+        if let Some(scope_def) = scope_def_to_definition(scope_def) {
+            let name = name.to_string();
+            if ours.is_none() && name == old_name && &scope_def == def {
+                ours = Some(idx);
+            }
+            if other.is_none()
+                && name == new_name
+                && &scope_def != def
+                && share_namespace(sema.db, &scope_def, def)
+            {
+                other = Some((idx, scope_def));
+            }
+        }
+        idx += 1;
+    });
+    Some((ours?, other?))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Namespace {
+    Types,
+    Values,
+    Macros,
+    Fields,
+}
+
+/// Returns the namespaces `def` declares its name in. Unit and tuple structs
+/// and variants are both a type and a constant or constructor.
+fn namespaces(db: &RootDatabase, def: &Definition) -> &'static [Namespace] {
+    match def {
+        Definition::Macro(_) => &[Namespace::Macros],
+        Definition::StructField(_) => &[Namespace::Fields],
+        Definition::Local(_) => &[Namespace::Values],
+        Definition::SelfType(_) | Definition::TypeParam(_) => &[Namespace::Types],
+        // These are resolved syntactically, see `token_conflicts`
+        Definition::LifetimeParam(_) | Definition::Label(_) | Definition::MacroMetavar(_) => &[],
+        Definition::ModuleDef(it) => match it {
+            ModuleDef::Function(_) | ModuleDef::Const(_) | ModuleDef::Static(_) => {
+                &[Namespace::Values]
+            }
+            ModuleDef::Adt(Adt::Struct(it)) => constructor_namespaces(it.kind(db)),
+            ModuleDef::EnumVariant(it) => constructor_namespaces(it.kind(db)),
+            ModuleDef::Module(_)
+            | ModuleDef::Adt(_)
+            | ModuleDef::Trait(_)
+            | ModuleDef::TypeAlias(_)
+            | ModuleDef::BuiltinType(_) => &[Namespace::Types],
+        },
+    }
+}
+
+fn constructor_namespaces(kind: StructKind) -> &'static [Namespace] {
+    match kind {
+        StructKind::Record => &[Namespace::Types],
+        StructKind::Tuple | StructKind::Unit => &[Namespace::Types, Namespace::Values],
+    }
+}
+
+fn share_namespace(db: &RootDatabase, def: &Definition, other: &Definition) -> bool {
+    let other = namespaces(db, other);
+    namespaces(db, def).iter().any(|it| other.contains(it))
+}
+
+fn scope_def_to_definition(def: ScopeDef) -> Option<Definition> {
+    let res = match def {
+        ScopeDef::ModuleDef(it) => Definition::ModuleDef(it),
+        ScopeDef::MacroDef(it) => Definition::Macro(it),
+        ScopeDef::GenericParam(it) => Definition::TypeParam(it),
+        ScopeDef::ImplSelfType(it) => Definition::SelfType(it),
+        ScopeDef::AdtSelfType(it) => Definition::ModuleDef(it.into()),
+        ScopeDef::Local(it) => Definition::Local(it),
+        ScopeDef::Unknown => return None,
+    };
+    Some(res)
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
@@ -154,6 +580,7 @@ mod tests {
 
     use crate::{
         mock_analysis::analysis_and_position, mock_analysis::single_file_with_position, FileId,
+        TextRange,
     };
 
     #[test]
@@ -249,6 +676,172 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rename_for_nested_macro_args() {
+        test_rename(
+            r#"
+    macro_rules! inner { ($e:expr) => { $e } }
+    macro_rules! outer { ($e:expr) => { inner!($e) } }
+    struct Foo { bar<|>: u32 }
+    fn main() {
+        let foo = Foo { bar: 1 };
+        outer!(foo.bar);
+    }"#,
+            "baz",
+            r#"
+    macro_rules! inner { ($e:expr) => { $e } }
+    macro_rules! outer { ($e:expr) => { inner!($e) } }
+    struct Foo { baz: u32 }
+    fn main() {
+        let foo = Foo { baz: 1 };
+        outer!(foo.baz);
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_for_macro_repetition_args() {
+        test_rename(
+            r#"
+    macro_rules! sum { ($($e:expr),*) => { 0 $(+ $e)* } }
+    fn main() {
+        let a<|> = 1;
+        let b = 2;
+        sum!(a, b, a);
+    }"#,
+            "c",
+            r#"
+    macro_rules! sum { ($($e:expr),*) => { 0 $(+ $e)* } }
+    fn main() {
+        let c = 1;
+        let b = 2;
+        sum!(c, b, c);
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_for_println_args() {
+        test_rename(
+            r#"
+    #[rustc_builtin_macro]
+    macro_rules! format_args {
+        ($fmt:expr) => ({ /* compiler built-in */ });
+        ($fmt:expr, $($args:tt)*) => ({ /* compiler built-in */ })
+    }
+    macro_rules! println {
+        ($($arg:tt)*) => ({ $crate::io::_print(format_args!($($arg)*)); })
+    }
+    struct Foo { bar<|>: u32 }
+    fn main() {
+        let foo = Foo { bar: 1 };
+        println!("{}", foo.bar);
+    }"#,
+            "baz",
+            r#"
+    #[rustc_builtin_macro]
+    macro_rules! format_args {
+        ($fmt:expr) => ({ /* compiler built-in */ });
+        ($fmt:expr, $($args:tt)*) => ({ /* compiler built-in */ })
+    }
+    macro_rules! println {
+        ($($arg:tt)*) => ({ $crate::io::_print(format_args!($($arg)*)); })
+    }
+    struct Foo { baz: u32 }
+    fn main() {
+        let foo = Foo { baz: 1 };
+        println!("{}", foo.baz);
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_for_assert_eq_args() {
+        test_rename(
+            r#"
+    macro_rules! assert_eq {
+        ($left:expr, $right:expr) => ({
+            match (&$left, &$right) {
+                (left_val, right_val) => if !(*left_val == *right_val) {},
+            }
+        });
+    }
+    fn foo<|>() -> u32 { 1 }
+    fn main() {
+        assert_eq!(foo(), 1);
+    }"#,
+            "bar",
+            r#"
+    macro_rules! assert_eq {
+        ($left:expr, $right:expr) => ({
+            match (&$left, &$right) {
+                (left_val, right_val) => if !(*left_val == *right_val) {},
+            }
+        });
+    }
+    fn bar() -> u32 { 1 }
+    fn main() {
+        assert_eq!(bar(), 1);
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_skips_occurrences_from_macro_body() {
+        test_rename(
+            r#"
+    macro_rules! call_foo { () => { foo() } }
+    fn foo<|>() {}
+    fn main() {
+        foo();
+        let _ = call_foo!();
+    }"#,
+            "bar",
+            r#"
+    macro_rules! call_foo { () => { foo() } }
+    fn bar() {}
+    fn main() {
+        bar();
+        let _ = call_foo!();
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_skips_overloaded_operators() {
+        test_rename(
+            r#"
+    #[lang = "add"]
+    trait Add<Rhs = Self> {
+        type Output;
+        fn add(self, rhs: Rhs) -> Self::Output;
+    }
+    struct Foo;
+    impl Add for Foo {
+        type Output = Foo;
+        fn add<|>(self, rhs: Foo) -> Foo { rhs }
+    }
+    fn main(a: Foo, b: Foo) {
+        a + b;
+    }"#,
+            "plus",
+            r#"
+    #[lang = "add"]
+    trait Add<Rhs = Self> {
+        type Output;
+        fn add(self, rhs: Rhs) -> Self::Output;
+    }
+    struct Foo;
+    impl Add for Foo {
+        type Output = Foo;
+        fn plus(self, rhs: Foo) -> Foo { rhs }
+    }
+    fn main(a: Foo, b: Foo) {
+        a + b;
+    }"#,
+        );
+    }
+
     #[test]
     fn test_rename_for_param_inside() {
         test_rename(
@@ -655,6 +1248,261 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_rename_lifetime() {
+        test_rename(
+            r#"
+    struct Foo<'a>(&'a str);
+    fn foo<'a, 'b: 'a>(x: &'a str, y: &'b<|> str) -> Foo<'b> {
+        fn bar<'b>(x: &'b str) {}
+        Foo(y)
+    }"#,
+            "'c",
+            r#"
+    struct Foo<'a>(&'a str);
+    fn foo<'a, 'c: 'a>(x: &'a str, y: &'c str) -> Foo<'c> {
+        fn bar<'b>(x: &'b str) {}
+        Foo(y)
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_label() {
+        test_rename(
+            r#"
+    fn main() {
+        'outer<|>: loop {
+            'inner: loop {
+                break 'outer;
+            }
+            continue 'outer;
+        }
+    }"#,
+            "'done",
+            r#"
+    fn main() {
+        'done: loop {
+            'inner: loop {
+                break 'done;
+            }
+            continue 'done;
+        }
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_macro_metavar() {
+        test_rename(
+            r#"
+    macro_rules! foo {
+        ($x:expr) => { $x<|> + 1 };
+        ($x:expr, $y:expr) => { $x + $y };
+    }"#,
+            "val",
+            r#"
+    macro_rules! foo {
+        ($val:expr) => { $val + 1 };
+        ($x:expr, $y:expr) => { $x + $y };
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_lifetime_to_identifier() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn foo<'a<|>>(x: &'a str) {}"#,
+        );
+        let source_change = analysis.rename(position, "b").unwrap();
+        assert!(source_change.is_none());
+    }
+
+    #[test]
+    fn test_prepare_rename_lifetime_and_label() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn foo<'a>(x: &'a<|> str) {}"#,
+        );
+        let range = analysis.prepare_rename(position).unwrap().unwrap().range;
+        assert_eq!(range, TextRange::from_to(20.into(), 22.into()));
+
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn main() {
+        'outer: loop {
+            break 'outer<|>;
+        }
+    }"#,
+        );
+        let range = analysis.prepare_rename(position).unwrap().unwrap().range;
+        assert_eq!(range, TextRange::from_to(58.into(), 64.into()));
+    }
+
+    #[test]
+    fn test_prepare_rename_keyword() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn main() {
+        lo<|>op {}
+    }"#,
+        );
+        assert!(analysis.prepare_rename(position).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rename_conflicts_with_shadowed_local() {
+        check_conflicts(
+            r#"
+    fn main() {
+        let x = 1;
+        let y<|> = 2;
+        x + y;
+    }"#,
+            "x",
+            &["FileId(1) [63; 64) x"],
+        );
+    }
+
+    #[test]
+    fn test_rename_conflicts_with_shadowing_local() {
+        check_conflicts(
+            r#"
+    fn foo() {}
+    fn main() {
+        let bar = 1;
+        foo<|>();
+    }"#,
+            "bar",
+            &["FileId(1) [62; 65) bar"],
+        );
+    }
+
+    #[test]
+    fn test_rename_conflicts_with_method() {
+        check_conflicts(
+            r#"
+    struct Foo;
+    impl Foo {
+        fn foo(&self) {}
+        fn bar<|>(&self) {}
+    }"#,
+            "foo",
+            &["FileId(1) [68; 71) foo"],
+        );
+    }
+
+    #[test]
+    fn test_rename_conflicts_with_variant() {
+        check_conflicts(
+            r#"
+    enum E { A<|>, B }"#,
+            "B",
+            &["FileId(1) [14; 15) B"],
+        );
+    }
+
+    #[test]
+    fn test_rename_variant_without_conflicts_with_module_items() {
+        check_conflicts(
+            r#"
+    fn B() {}
+    enum E { A<|> }"#,
+            "B",
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_rename_conflicts_with_unit_struct() {
+        check_conflicts(
+            r#"
+    struct Foo;
+    fn foo<|>() {}"#,
+            "Foo",
+            &["FileId(1) [24; 27) Foo"],
+        );
+    }
+
+    #[test]
+    fn test_rename_without_conflicts_with_record_struct() {
+        check_conflicts(
+            r#"
+    struct Foo {}
+    fn foo<|>() {}"#,
+            "Foo",
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_rename_conflicts_with_lifetime_param() {
+        check_conflicts(
+            r#"
+    fn foo<'a, 'b<|>>(x: &'a str, y: &'b str) {}"#,
+            "'a",
+            &["FileId(1) [16; 18) 'a"],
+        );
+    }
+
+    #[test]
+    fn test_rename_conflicts_with_enclosing_label() {
+        check_conflicts(
+            r#"
+    fn main() {
+        'outer: loop {
+            'inner<|>: loop {
+                break 'outer;
+            }
+        }
+    }"#,
+            "'outer",
+            &["FileId(1) [52; 58) 'outer"],
+        );
+    }
+
+    #[test]
+    fn test_rename_conflicts_with_macro_metavar() {
+        check_conflicts(
+            r#"
+    macro_rules! foo {
+        ($x<|>:expr, $y:expr) => { $x + $y };
+    }"#,
+            "y",
+            &["FileId(1) [34; 35) y"],
+        );
+    }
+
+    #[test]
+    fn test_rename_without_conflicts() {
+        check_conflicts(
+            r#"
+    fn main() {
+        let x = 1;
+        let y<|> = 2;
+        x + y;
+    }"#,
+            "z",
+            &[],
+        );
+    }
+
+    fn check_conflicts(text: &str, new_name: &str, expected: &[&str]) {
+        let (analysis, position) = single_file_with_position(text);
+        let conflicts = analysis
+            .rename_with_conflicts(position, new_name, true)
+            .unwrap()
+            .map_or_else(Vec::new, |it| it.info.conflicts);
+        let actual: Vec<String> = conflicts
+            .iter()
+            .map(|it| {
+                format!("{:?} {:?} {}", it.range.file_id, it.range.range, it.conflicting.name())
+            })
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
     fn test_rename(text: &str, new_name: &str, expected: &str) {
         let (analysis, position) = single_file_with_position(text);
         let source_change = analysis.rename(position, new_name).unwrap();
@@ -1,12 +1,17 @@
 //!  structural search replace
 
-use crate::source_change::SourceFileEdit;
-use ra_db::{SourceDatabase, SourceDatabaseExt};
+use crate::{
+    display::{NavigationTarget, ToNav},
+    doc_links::parse_path,
+    source_change::SourceFileEdit,
+};
+use hir::{HirDisplay, ModuleDef, PathResolution, Semantics, SemanticsScope};
+use ra_db::{FileRange, SourceDatabaseExt};
 use ra_ide_db::symbol_index::SymbolsDatabase;
 use ra_ide_db::RootDatabase;
 use ra_syntax::ast::make::try_expr_from_text;
-use ra_syntax::ast::{AstToken, Comment};
-use ra_syntax::{AstNode, SyntaxElement, SyntaxNode};
+use ra_syntax::ast::{self, AstToken, Comment};
+use ra_syntax::{match_ast, AstNode, SyntaxElement, SyntaxKind, SyntaxNode};
 use ra_text_edit::{TextEdit, TextEditBuilder};
 use rustc_hash::FxHashMap;
 use std::collections::HashMap;
@@ -23,9 +28,55 @@ impl std::fmt::Display for SsrError {
 
 impl std::error::Error for SsrError {}
 
+/// A match of the pattern of an SSR query, as reported by the search-only mode.
+#[derive(Debug)]
+pub struct SsrMatch {
+    pub range: FileRange,
+    /// The code matched by each placeholder, in the order of the pattern
+    pub bindings: Vec<(String, String)>,
+    /// The function, const or static containing the match
+    pub enclosing_item: Option<NavigationTarget>,
+}
+
+/// Finds the matches of the pattern of `query` without replacing them. The
+/// `==>>` and the template may be omitted.
+pub fn find_matches(query: &str, db: &RootDatabase) -> Result<Vec<SsrMatch>, SsrError> {
+    let pattern = if query.contains("==>>") {
+        query.parse::<SsrQuery>()?.pattern
+    } else {
+        query.trim().parse::<SsrPattern>()?
+    };
+    let sema = Semantics::new(db);
+    let mut res = vec![];
+    for &root in db.local_roots().iter() {
+        let sr = db.source_root(root);
+        for file_id in sr.walk() {
+            let matches = find(&pattern, sema.parse(file_id).syntax(), &sema);
+            res.extend(matches.matches.iter().map(|match_| {
+                SsrMatch {
+                    range: FileRange { file_id, range: match_.place.text_range() },
+                    bindings: pattern
+                        .vars
+                        .iter()
+                        .filter_map(|var| {
+                            let code = match_.binding.get(var)?;
+                            Some((var.name().to_string(), code.text().to_string()))
+                        })
+                        .collect(),
+                    enclosing_item: enclosing_item(&match_.place, &sema),
+                }
+            }));
+        }
+    }
+    Ok(res)
+}
+
+/// Replaces the matches of `query`. If `selected` is given, only the matches
+/// with one of these ranges are replaced.
 pub fn parse_search_replace(
     query: &str,
     parse_only: bool,
+    selected: Option<&[FileRange]>,
     db: &RootDatabase,
 ) -> Result<Vec<SourceFileEdit>, SsrError> {
     let mut edits = vec![];
@@ -33,12 +84,21 @@ pub fn parse_search_replace(
     if parse_only {
         return Ok(edits);
     }
+    let sema = Semantics::new(db);
     for &root in db.local_roots().iter() {
         let sr = db.source_root(root);
         for file_id in sr.walk() {
-            let matches = find(&query.pattern, db.parse(file_id).tree().syntax());
+            let mut matches = find(&query.pattern, sema.parse(file_id).syntax(), &sema);
+            if let Some(selected) = selected {
+                matches.matches.retain(|match_| {
+                    selected.contains(&FileRange { file_id, range: match_.place.text_range() })
+                });
+            }
             if !matches.matches.is_empty() {
-                edits.push(SourceFileEdit { file_id, edit: replace(&matches, &query.template) });
+                edits.push(SourceFileEdit {
+                    file_id,
+                    edit: replace(&matches, &query.template, &sema),
+                });
             }
         }
     }
@@ -55,12 +115,33 @@ struct SsrQuery {
 struct SsrPattern {
     pattern: SyntaxNode,
     vars: Vec<Var>,
+    constraints: FxHashMap<Var, Vec<Constraint>>,
 }
 
 /// represents an `$var` in an SSR query
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Var(String);
 
+impl Var {
+    /// The name of the placeholder as written in the query
+    fn name(&self) -> &str {
+        &self.0["__search_pattern_".len()..]
+    }
+}
+
+/// A restriction on the code a placeholder matches, as in `${a:kind(literal)}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Constraint {
+    Kind(NodeKind),
+    /// `type(Path)`, matching expressions of the type `Path` resolves to
+    Type(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Literal,
+}
+
 #[derive(Debug)]
 struct SsrTemplate {
     template: SyntaxNode,
@@ -95,17 +176,9 @@ impl FromStr for SsrQuery {
         if it.next().is_some() {
             return Err(SsrError("More than one delimiter found".into()));
         }
-        let mut vars = vec![];
-        let mut it = pattern.split('$');
-        let mut pattern = it.next().expect("something").to_string();
-
-        for part in it.map(split_by_var) {
-            let (var, var_type, remainder) = part?;
-            is_expr(var_type)?;
-            let new_var = create_name(var, &mut vars)?;
-            pattern.push_str(new_var);
-            pattern.push_str(remainder);
-            template = replace_in_template(template, var, new_var);
+        let pattern: SsrPattern = pattern.parse()?;
+        for var in &pattern.vars {
+            template = replace_in_template(template, var.name(), &var.0);
         }
 
         let template = try_expr_from_text(&template)
@@ -115,7 +188,7 @@ impl FromStr for SsrQuery {
         let mut placeholders = FxHashMap::default();
 
         traverse(&template, &mut |n| {
-            if let Some(v) = vars.iter().find(|v| v.0.as_str() == n.text()) {
+            if let Some(v) = pattern.vars.iter().find(|v| v.0.as_str() == n.text()) {
                 placeholders.insert(n.clone(), v.clone());
                 false
             } else {
@@ -123,18 +196,38 @@ impl FromStr for SsrQuery {
             }
         });
 
-        let pattern = SsrPattern {
-            pattern: try_expr_from_text(&pattern)
-                .ok_or(SsrError("Pattern is not an expression".into()))?
-                .syntax()
-                .clone(),
-            vars,
-        };
         let template = SsrTemplate { template, placeholders };
         Ok(SsrQuery { pattern, template })
     }
 }
 
+impl FromStr for SsrPattern {
+    type Err = SsrError;
+
+    fn from_str(pattern: &str) -> Result<SsrPattern, SsrError> {
+        let mut vars = vec![];
+        let mut constraints = FxHashMap::default();
+        let mut it = pattern.split('$');
+        let mut pattern = it.next().expect("something").to_string();
+
+        for part in it.map(split_by_var) {
+            let (var, var_constraints, remainder) = part?;
+            let new_var = create_name(var, &mut vars)?;
+            pattern.push_str(new_var);
+            pattern.push_str(remainder);
+            if !var_constraints.is_empty() {
+                constraints.insert(Var(new_var.to_string()), var_constraints);
+            }
+        }
+
+        let pattern = try_expr_from_text(&pattern)
+            .ok_or(SsrError("Pattern is not an expression".into()))?
+            .syntax()
+            .clone();
+        Ok(SsrPattern { pattern, vars, constraints })
+    }
+}
+
 fn traverse(node: &SyntaxNode, go: &mut impl FnMut(&SyntaxNode) -> bool) {
     if !go(node) {
         return;
@@ -144,15 +237,63 @@ fn traverse(node: &SyntaxNode, go: &mut impl FnMut(&SyntaxNode) -> bool) {
     }
 }
 
-fn split_by_var(s: &str) -> Result<(&str, &str, &str), SsrError> {
-    let end_of_name = s.find(':').ok_or_else(|| SsrError("Use $<name>:expr".into()))?;
+/// Splits the text following a `$` into the name of the placeholder, its
+/// constraints and the rest of the pattern. Placeholders are written as
+/// `$name`, `$name:expr` or `${name:constraint:constraint}`.
+fn split_by_var(s: &str) -> Result<(&str, Vec<Constraint>, &str), SsrError> {
+    if s.starts_with('{') {
+        let end = s.find('}').ok_or_else(|| SsrError("Missing `}` after `${`".into()))?;
+        let inner = &s[1..end];
+        let (name, mut rest) = match inner.find(':') {
+            Some(idx) => (&inner[..idx], &inner[idx + 1..]),
+            None => (inner, ""),
+        };
+        is_name(name)?;
+        let mut constraints = vec![];
+        while !rest.is_empty() {
+            let end_of_constraint = rest.find(')').map_or(rest.len(), |idx| idx + 1);
+            constraints.push(parse_constraint(&rest[..end_of_constraint])?);
+            rest = &rest[end_of_constraint..];
+            if rest.starts_with(':') {
+                rest = &rest[1..];
+            } else if !rest.is_empty() {
+                return Err(SsrError(format!("Unknown constraint `{}`", rest)));
+            }
+        }
+        return Ok((name, constraints, &s[end + 1..]));
+    }
+
+    let end_of_name =
+        s.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or_else(|| s.len());
     let name = &s[0..end_of_name];
-    is_name(name)?;
-    let type_begin = end_of_name + 1;
+    if name.is_empty() {
+        return Err(SsrError("Use $<name> or ${<name>:<constraint>}".into()));
+    }
+    let rest = &s[end_of_name..];
+    if !rest.starts_with(':') || rest.starts_with("::") {
+        return Ok((name, vec![], rest));
+    }
     let type_length =
-        s[type_begin..].find(|c| !char::is_ascii_alphanumeric(&c)).unwrap_or_else(|| s.len());
-    let type_name = &s[type_begin..type_begin + type_length];
-    Ok((name, type_name, &s[type_begin + type_length..]))
+        rest[1..].find(|c| !char::is_ascii_alphanumeric(&c)).unwrap_or_else(|| rest.len() - 1);
+    is_expr(&rest[1..1 + type_length])?;
+    Ok((name, vec![], &rest[1 + type_length..]))
+}
+
+fn parse_constraint(s: &str) -> Result<Constraint, SsrError> {
+    let unknown = || SsrError(format!("Unknown constraint `{}`", s));
+    if !s.ends_with(')') {
+        return Err(unknown());
+    }
+    let open = s.find('(').ok_or_else(unknown)?;
+    let arg = s[open + 1..s.len() - 1].trim();
+    match &s[..open] {
+        "kind" => match arg {
+            "literal" => Ok(Constraint::Kind(NodeKind::Literal)),
+            _ => Err(SsrError(format!("Unknown kind `{}`", arg))),
+        },
+        "type" => Ok(Constraint::Type(arg.to_string())),
+        _ => Err(unknown()),
+    }
 }
 
 fn is_name(s: &str) -> Result<(), SsrError> {
@@ -172,6 +313,7 @@ fn is_expr(s: &str) -> Result<(), SsrError> {
 }
 
 fn replace_in_template(template: String, var: &str, new_var: &str) -> String {
+    let template = template.replace(&format!("${{{}}}", var), new_var);
     let name = format!("${}", var);
     template.replace(&name, new_var)
 }
@@ -185,11 +327,13 @@ fn create_name<'a>(name: &str, vars: &'a mut Vec<Var>) -> Result<&'a str, SsrErr
     Ok(&vars.last().unwrap().0)
 }
 
-fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
+fn find(pattern: &SsrPattern, code: &SyntaxNode, sema: &Semantics<RootDatabase>) -> SsrMatches {
     fn check(
         pattern: &SyntaxElement,
         code: &SyntaxElement,
-        placeholders: &[Var],
+        ssr_pattern: &SsrPattern,
+        resolutions: &PatternResolutions,
+        sema: &Semantics<RootDatabase>,
         mut match_: Match,
     ) -> Option<Match> {
         match (pattern, code) {
@@ -201,9 +345,21 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
                 }
             }
             (SyntaxElement::Node(ref pattern), SyntaxElement::Node(ref code)) => {
-                if placeholders.iter().any(|n| n.0.as_str() == pattern.text()) {
-                    match_.binding.insert(Var(pattern.text().to_string()), code.clone());
+                if let Some(var) = ssr_pattern.vars.iter().find(|n| n.0.as_str() == pattern.text())
+                {
+                    let constraints =
+                        ssr_pattern.constraints.get(var).map_or(&[][..], |it| &it[..]);
+                    if !constraints.iter().all(|it| satisfies(code, it, resolutions, sema)) {
+                        return None;
+                    }
+                    match_.binding.insert(var.clone(), code.clone());
                     Some(match_)
+                } else if let Some(same) = same_resolution(pattern, code, resolutions, sema) {
+                    if same {
+                        Some(match_)
+                    } else {
+                        None
+                    }
                 } else {
                     let mut pattern_children = pattern
                         .children_with_tokens()
@@ -218,7 +374,9 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
                         .by_ref()
                         .zip(code_children.by_ref())
                         .fold(Some(match_), |accum, (a, b)| {
-                            accum.and_then(|match_| check(&a, &b, placeholders, match_))
+                            accum.and_then(|match_| {
+                                check(&a, &b, ssr_pattern, resolutions, sema, match_)
+                            })
                         });
                     match_from_children.and_then(|match_| {
                         if pattern_children.count() == 0 && code_children.count() == 0 {
@@ -232,6 +390,7 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
             _ => None,
         }
     }
+    let resolutions = resolve_pattern(pattern, code, sema);
     let kind = pattern.pattern.kind();
     let matches = code
         .descendants()
@@ -242,7 +401,9 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
             check(
                 &SyntaxElement::from(pattern.pattern.clone()),
                 &SyntaxElement::from(code),
-                &pattern.vars,
+                pattern,
+                &resolutions,
+                sema,
                 match_,
             )
         })
@@ -250,38 +411,201 @@ fn find(pattern: &SsrPattern, code: &SyntaxNode) -> SsrMatches {
     SsrMatches { matches }
 }
 
-fn replace(matches: &SsrMatches, template: &SsrTemplate) -> TextEdit {
+/// The paths of a pattern, and of the types its constraints refer to,
+/// resolved at the root of the crate being searched. This way a path of the
+/// pattern means the same item at every place it is compared with.
+#[derive(Default)]
+struct PatternResolutions {
+    paths: FxHashMap<SyntaxNode, PathResolution>,
+    types: FxHashMap<String, PathResolution>,
+}
+
+fn resolve_pattern(
+    pattern: &SsrPattern,
+    code: &SyntaxNode,
+    sema: &Semantics<RootDatabase>,
+) -> PatternResolutions {
+    let scope = match crate_root_scope(code, sema) {
+        Some(it) => it,
+        None => return PatternResolutions::default(),
+    };
+    let paths = pattern
+        .pattern
+        .descendants()
+        .filter(|it| it.kind() == SyntaxKind::PATH)
+        .filter_map(|it| {
+            let resolution = scope.resolve_hir_path(&parse_path(&it.text().to_string())?)?;
+            Some((it, resolution))
+        })
+        .collect();
+    let types = pattern
+        .constraints
+        .values()
+        .flatten()
+        .filter_map(|it| match it {
+            Constraint::Type(path) => {
+                let resolution = scope.resolve_hir_path(&parse_path(path)?)?;
+                Some((path.clone(), resolution))
+            }
+            Constraint::Kind(_) => None,
+        })
+        .collect();
+    PatternResolutions { paths, types }
+}
+
+/// The scope the paths of a query are resolved in: the root module of the
+/// crate `code` belongs to.
+fn crate_root_scope<'db>(
+    code: &SyntaxNode,
+    sema: &Semantics<'db, RootDatabase>,
+) -> Option<SemanticsScope<'db, RootDatabase>> {
+    let krate = sema.scope(code).module()?.krate();
+    let root = sema.parse(krate.root_file(sema.db));
+    Some(sema.scope(root.syntax()))
+}
+
+/// Compares a path of the pattern with one in the code by what they resolve
+/// to, so that `std::mem::replace` also matches `mem::replace`, while `foo`
+/// doesn't match a different `foo`. Returns `None` if either of them doesn't
+/// resolve, leaving them to be matched by syntax.
+fn same_resolution(
+    pattern: &SyntaxNode,
+    code: &SyntaxNode,
+    resolutions: &PatternResolutions,
+    sema: &Semantics<RootDatabase>,
+) -> Option<bool> {
+    let pattern_resolution = resolutions.paths.get(pattern)?;
+    let code_resolution = sema.resolve_path(&ast::Path::cast(code.clone())?)?;
+    Some(*pattern_resolution == code_resolution)
+}
+
+fn enclosing_item(place: &SyntaxNode, sema: &Semantics<RootDatabase>) -> Option<NavigationTarget> {
+    place.ancestors().find_map(|node| {
+        match_ast! {
+            match node {
+                ast::FnDef(it) => Some(sema.to_def(&it)?.to_nav(sema.db)),
+                ast::ConstDef(it) => Some(sema.to_def(&it)?.to_nav(sema.db)),
+                ast::StaticDef(it) => Some(sema.to_def(&it)?.to_nav(sema.db)),
+                _ => None,
+            }
+        }
+    })
+}
+
+fn satisfies(
+    code: &SyntaxNode,
+    constraint: &Constraint,
+    resolutions: &PatternResolutions,
+    sema: &Semantics<RootDatabase>,
+) -> bool {
+    match constraint {
+        Constraint::Kind(NodeKind::Literal) => ast::Literal::can_cast(code.kind()),
+        Constraint::Type(path) => has_type(code, path, resolutions, sema).unwrap_or(false),
+    }
+}
+
+fn has_type(
+    code: &SyntaxNode,
+    path: &str,
+    resolutions: &PatternResolutions,
+    sema: &Semantics<RootDatabase>,
+) -> Option<bool> {
+    let ty = sema.type_of_expr(&ast::Expr::cast(code.clone())?)?;
+    let res = match resolutions.types.get(path)? {
+        PathResolution::Def(ModuleDef::Adt(adt)) => ty.as_adt() == Some(*adt),
+        PathResolution::Def(ModuleDef::BuiltinType(builtin)) => {
+            ty.display(sema.db).to_string() == builtin.to_string()
+        }
+        _ => false,
+    };
+    Some(res)
+}
+
+fn replace(
+    matches: &SsrMatches,
+    template: &SsrTemplate,
+    sema: &Semantics<RootDatabase>,
+) -> TextEdit {
     let mut builder = TextEditBuilder::default();
+    let resolutions = match matches.matches.first() {
+        Some(match_) => resolve_template(template, &match_.place, sema),
+        None => FxHashMap::default(),
+    };
     for match_ in &matches.matches {
         builder.replace(
             match_.place.text_range(),
-            render_replace(&match_.binding, &match_.ignored_comments, template),
+            render_replace(match_, template, &resolutions, sema),
         );
     }
     builder.finish()
 }
 
+/// Resolves the paths of the template at the root of the crate being searched,
+/// like the ones of the pattern. Only whole paths are resolved, not their
+/// qualifiers.
+fn resolve_template(
+    template: &SsrTemplate,
+    code: &SyntaxNode,
+    sema: &Semantics<RootDatabase>,
+) -> FxHashMap<SyntaxNode, ModuleDef> {
+    let scope = match crate_root_scope(code, sema) {
+        Some(it) => it,
+        None => return FxHashMap::default(),
+    };
+    template
+        .template
+        .descendants()
+        .filter_map(|node| {
+            let path = ast::Path::cast(node.clone())?;
+            if path.syntax().parent().map_or(false, |it| it.kind() == SyntaxKind::PATH) {
+                return None;
+            }
+            match scope.resolve_hir_path(&hir::Path::from_ast(path)?)? {
+                PathResolution::Def(def) => Some((node, def)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
 fn render_replace(
-    binding: &Binding,
-    ignored_comments: &Vec<Comment>,
+    match_: &Match,
     template: &SsrTemplate,
+    resolutions: &FxHashMap<SyntaxNode, ModuleDef>,
+    sema: &Semantics<RootDatabase>,
 ) -> String {
     let mut builder = TextEditBuilder::default();
     for element in template.template.descendants() {
         if let Some(var) = template.placeholders.get(&element) {
-            builder.replace(element.text_range(), binding[var].to_string())
+            builder.replace(element.text_range(), match_.binding[var].to_string())
+        } else if let Some(def) = resolutions.get(&element) {
+            if let Some(path) = requalify_path(*def, &match_.place, sema) {
+                builder.replace(element.text_range(), path)
+            }
         }
     }
-    for comment in ignored_comments {
+    for comment in &match_.ignored_comments {
         builder.insert(template.template.text_range().end(), comment.syntax().to_string())
     }
     builder.finish().apply(&template.template.text().to_string())
 }
 
+/// Qualifies a path of the template the way it has to be written at the place
+/// of the match to refer to the same item.
+fn requalify_path(
+    def: ModuleDef,
+    place: &SyntaxNode,
+    sema: &Semantics<RootDatabase>,
+) -> Option<String> {
+    let path = sema.scope(place).module()?.find_use_path(sema.db, def)?;
+    Some(path.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ra_syntax::SourceFile;
+    use crate::mock_analysis::{single_file, MockAnalysis};
+    use ra_syntax::TextRange;
 
     fn parse_error_text(query: &str) -> String {
         format!("{}", query.parse::<SsrQuery>().unwrap_err())
@@ -316,14 +640,45 @@ mod tests {
     }
 
     #[test]
-    fn parser_no_pattern_type() {
-        assert_eq!(parse_error_text("foo($a) ==>>"), "Parse error: Use $<name>:expr");
+    fn parser_constraints() {
+        let result: SsrQuery =
+            "foo(${a:kind(literal)}, ${b:type(std::string::String)}, $c) ==>> bar(${a}, $b, $c)"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            &result.pattern.pattern.text(),
+            "foo(__search_pattern_a, __search_pattern_b, __search_pattern_c)"
+        );
+        assert_eq!(
+            result.pattern.constraints[&Var("__search_pattern_a".to_string())],
+            vec![Constraint::Kind(NodeKind::Literal)]
+        );
+        assert_eq!(
+            result.pattern.constraints[&Var("__search_pattern_b".to_string())],
+            vec![Constraint::Type("std::string::String".to_string())]
+        );
+        assert_eq!(
+            &result.template.template.text(),
+            "bar(__search_pattern_a, __search_pattern_b, __search_pattern_c)"
+        );
+    }
+
+    #[test]
+    fn parser_unknown_constraint() {
+        assert_eq!(
+            parse_error_text("foo(${a:size(1)}) ==>> ()"),
+            "Parse error: Unknown constraint `size(1)`"
+        );
+        assert_eq!(
+            parse_error_text("foo(${a:kind(block)}) ==>> ()"),
+            "Parse error: Unknown kind `block`"
+        );
     }
 
     #[test]
     fn parser_invalid_name() {
         assert_eq!(
-            parse_error_text("foo($a+:expr) ==>>"),
+            parse_error_text("foo(${a+}) ==>> ()"),
             "Parse error: Name can contain only alphanumerics and _"
         );
     }
@@ -359,8 +714,10 @@ mod tests {
         let query: SsrQuery = "foo($x:expr) ==>> bar($x)".parse().unwrap();
         let input = "fn main() { foo(1+2); }";
 
-        let code = SourceFile::parse(input).tree();
-        let matches = find(&query.pattern, code.syntax());
+        let (analysis, file_id) = single_file(input);
+        let sema = Semantics::new(&*analysis.db);
+        let code = sema.parse(file_id);
+        let matches = find(&query.pattern, code.syntax(), &sema);
         assert_eq!(matches.matches.len(), 1);
         assert_eq!(matches.matches[0].place.text(), "foo(1+2)");
         assert_eq!(matches.matches[0].binding.len(), 1);
@@ -369,16 +726,27 @@ mod tests {
             "1+2"
         );
 
-        let edit = replace(&matches, &query.template);
+        let edit = replace(&matches, &query.template, &sema);
         assert_eq!(edit.apply(input), "fn main() { bar(1+2); }");
     }
 
     fn assert_ssr_transform(query: &str, input: &str, result: &str) {
-        let query: SsrQuery = query.parse().unwrap();
-        let code = SourceFile::parse(input).tree();
-        let matches = find(&query.pattern, code.syntax());
-        let edit = replace(&matches, &query.template);
-        assert_eq!(edit.apply(input), result);
+        let (analysis, file_id) = single_file(input);
+        assert_ssr_transform_in_file(&analysis, file_id, query, result);
+    }
+
+    fn assert_ssr_transform_in_file(
+        analysis: &crate::Analysis,
+        file_id: ra_db::FileId,
+        query: &str,
+        result: &str,
+    ) {
+        let change = analysis.structural_search_replace(query, false, None).unwrap().unwrap();
+        let mut text = analysis.file_text(file_id).unwrap().to_string();
+        for edit in change.source_file_edits.into_iter().filter(|it| it.file_id == file_id) {
+            text = edit.edit.apply(&text);
+        }
+        assert_eq!(text, result);
     }
 
     #[test]
@@ -434,4 +802,142 @@ mod tests {
             "fn main() { bar(5)/* using 5 */ }",
         )
     }
+
+    #[test]
+    fn ssr_matches_paths_by_resolution() {
+        let analysis = MockAnalysis::with_files(
+            r#"
+            //- /main.rs
+            use std::mem;
+            use std::mem::replace;
+            mod other {
+                pub fn replace(dest: &mut i32, src: i32) -> i32 { src }
+            }
+            fn main() {
+                let mut a = 1;
+                std::mem::replace(&mut a, 2);
+                mem::replace(&mut a, 3);
+                replace(&mut a, 4);
+                other::replace(&mut a, 5);
+            }
+
+            //- /std/lib.rs
+            pub mod mem {
+                pub fn replace<T>(dest: &mut T, src: T) -> T { src }
+                pub fn take<T>(dest: &mut T) -> T { loop {} }
+            }
+            "#,
+        )
+        .analysis();
+        let file_id = ra_db::FileId(1);
+        let input = analysis.file_text(file_id).unwrap().to_string();
+        let result = input
+            .replace("std::mem::replace(&mut a, 2)", "mem::take(&mut a)")
+            .replace("    mem::replace(&mut a, 3)", "    mem::take(&mut a)")
+            .replace("    replace(&mut a, 4)", "    mem::take(&mut a)");
+        assert_ssr_transform_in_file(
+            &analysis,
+            file_id,
+            "std::mem::replace($a, $b) ==>> std::mem::take($a)",
+            &result,
+        );
+    }
+
+    #[test]
+    fn ssr_does_not_match_other_items_with_the_same_name() {
+        assert_ssr_transform(
+            "foo($a) ==>> bar($a)",
+            r#"
+fn foo(x: i32) {}
+fn bar(x: i32) {}
+mod m {
+    fn foo(x: i32) {}
+    fn f() { foo(1); }
+}
+fn main() { foo(2); }
+"#,
+            r#"
+fn foo(x: i32) {}
+fn bar(x: i32) {}
+mod m {
+    fn foo(x: i32) {}
+    fn f() { foo(1); }
+}
+fn main() { bar(2); }
+"#,
+        );
+    }
+
+    #[test]
+    fn ssr_kind_constraint() {
+        assert_ssr_transform(
+            "foo(${a:kind(literal)}) ==>> bar($a)",
+            "fn main() { let x = 1; foo(1); foo(x); }",
+            "fn main() { let x = 1; bar(1); foo(x); }",
+        );
+    }
+
+    #[test]
+    fn ssr_type_constraint() {
+        assert_ssr_transform(
+            "show(${a:type(Meters)}) ==>> show_meters($a)",
+            "struct Meters(u32); fn main() { let m = Meters(1); show(m); show(1); }",
+            "struct Meters(u32); fn main() { let m = Meters(1); show_meters(m); show(1); }",
+        );
+    }
+
+    #[test]
+    fn ssr_find_matches() {
+        let (analysis, file_id) =
+            single_file("fn main() { foo(1, 2); } const C: i32 = foo(x + y, 3);");
+        let matches = analysis.structural_search("foo($a, $b)").unwrap().unwrap();
+        assert_eq!(matches.len(), 2);
+
+        assert_eq!(
+            matches[0].range,
+            FileRange { file_id, range: TextRange::from_to(12.into(), 21.into()) }
+        );
+        assert_eq!(
+            matches[0].bindings,
+            vec![("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())]
+        );
+        assert_eq!(matches[0].enclosing_item.as_ref().unwrap().name(), "main");
+
+        assert_eq!(
+            matches[1].range,
+            FileRange { file_id, range: TextRange::from_to(40.into(), 53.into()) }
+        );
+        assert_eq!(
+            matches[1].bindings,
+            vec![("a".to_string(), "x + y".to_string()), ("b".to_string(), "3".to_string())]
+        );
+        assert_eq!(matches[1].enclosing_item.as_ref().unwrap().name(), "C");
+
+        let with_template =
+            analysis.structural_search("foo($a, $b) ==>> bar($b)").unwrap().unwrap();
+        assert_eq!(with_template.len(), 2);
+    }
+
+    #[test]
+    fn ssr_replace_selected_matches() {
+        let input = "fn main() { foo(1); foo(2); foo(3); }";
+        let (analysis, file_id) = single_file(input);
+        let selected: Vec<FileRange> = analysis
+            .structural_search("foo($a)")
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .filter(|it| it.bindings[0].1 != "2")
+            .map(|it| it.range)
+            .collect();
+        let change = analysis
+            .structural_search_replace("foo($a) ==>> bar($a)", false, Some(&selected))
+            .unwrap()
+            .unwrap();
+        let mut text = input.to_string();
+        for edit in change.source_file_edits.into_iter().filter(|it| it.file_id == file_id) {
+            text = edit.edit.apply(&text);
+        }
+        assert_eq!(text, "fn main() { bar(1); foo(2); bar(3); }");
+    }
 }
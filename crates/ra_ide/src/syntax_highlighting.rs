@@ -275,6 +275,8 @@ fn highlight_name(db: &RootDatabase, def: Definition) -> Highlight {
         },
         Definition::SelfType(_) => HighlightTag::SelfType,
         Definition::TypeParam(_) => HighlightTag::TypeParam,
+        Definition::LifetimeParam(_) | Definition::Label(_) => HighlightTag::Lifetime,
+        Definition::MacroMetavar(_) => HighlightTag::Local,
         // FIXME: distinguish between locals and parameters
         Definition::Local(local) => {
             let mut h = Highlight::new(HighlightTag::Local);
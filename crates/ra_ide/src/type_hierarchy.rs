@@ -0,0 +1,211 @@
+//! Entry point for type-hierarchy: supertypes are the supertraits of a trait
+//! or the traits implemented by a type, subtypes are the implementors of a
+//! trait.
+
+use hir::{Adt, Crate, ImplDef, ModuleDef, Semantics, Trait};
+use ra_ide_db::{
+    defs::{classify_name, classify_name_ref, Definition},
+    RootDatabase,
+};
+use ra_syntax::{algo::find_node_at_offset, ast, match_ast, AstNode, SyntaxKind::IDENT};
+
+use crate::{display::ToNav, FilePosition, NavigationTarget, RangeInfo};
+
+#[derive(Debug, Clone, Copy)]
+enum TypeHierarchyDef {
+    Trait(Trait),
+    Adt(Adt),
+}
+
+impl TypeHierarchyDef {
+    fn to_nav(self, db: &RootDatabase) -> NavigationTarget {
+        match self {
+            TypeHierarchyDef::Trait(it) => it.to_nav(db),
+            TypeHierarchyDef::Adt(it) => it.to_nav(db),
+        }
+    }
+}
+
+pub(crate) fn type_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let RangeInfo { range, info: def } = def_at(&sema, position)?;
+    Some(RangeInfo::new(range, def.to_nav(db)))
+}
+
+pub(crate) fn supertypes(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let traits = match def_at(&sema, position)?.info {
+        TypeHierarchyDef::Trait(it) => it.super_traits(db),
+        TypeHierarchyDef::Adt(adt) => {
+            let ty = adt.ty(db);
+            let mut traits = Vec::new();
+            for krate in Crate::all(db) {
+                for impl_def in ImplDef::all_in_crate(db, krate) {
+                    if !ty.is_equal_for_find_impls(&impl_def.target_ty(db)) {
+                        continue;
+                    }
+                    match impl_def.target_trait_def(db) {
+                        Some(tr) if !traits.contains(&tr) => traits.push(tr),
+                        _ => (),
+                    }
+                }
+            }
+            traits
+        }
+    };
+    Some(sorted(traits.into_iter().map(|it| it.to_nav(db)).collect()))
+}
+
+pub(crate) fn subtypes(db: &RootDatabase, position: FilePosition) -> Option<Vec<NavigationTarget>> {
+    let sema = Semantics::new(db);
+    let tr = match def_at(&sema, position)?.info {
+        TypeHierarchyDef::Trait(it) => it,
+        TypeHierarchyDef::Adt(_) => return Some(Vec::new()),
+    };
+
+    let mut adts = Vec::new();
+    let mut res = Vec::new();
+    for krate in Crate::all(db) {
+        for impl_def in ImplDef::for_trait(db, krate, tr) {
+            // Implementors which aren't ADTs, like `impl Foo for i32`, are
+            // represented by their impl block.
+            match impl_def.target_ty(db).as_adt() {
+                Some(adt) if adts.contains(&adt) => (),
+                Some(adt) => {
+                    adts.push(adt);
+                    res.push(adt.to_nav(db));
+                }
+                None => res.push(impl_def.to_nav(db)),
+            }
+        }
+    }
+    Some(sorted(res))
+}
+
+/// Impls are collected from hash maps, so sort them for a stable output.
+fn sorted(mut navs: Vec<NavigationTarget>) -> Vec<NavigationTarget> {
+    navs.sort_by_key(|nav| (nav.file_id(), nav.full_range().start()));
+    navs
+}
+
+fn def_at(
+    sema: &Semantics<RootDatabase>,
+    position: FilePosition,
+) -> Option<RangeInfo<TypeHierarchyDef>> {
+    let file = sema.parse(position.file_id);
+    let file = file.syntax();
+
+    let token = file.token_at_offset(position.offset).find(|it| it.kind() == IDENT);
+    if let Some(token) = token.map(|it| sema.descend_into_macros(it)) {
+        let parent = token.parent();
+        let def = match_ast! {
+            match parent {
+                ast::NameRef(name_ref) => classify_name_ref(sema, &name_ref).map(|d| d.definition()),
+                ast::Name(name) => classify_name(sema, &name).map(|d| d.definition()),
+                _ => None,
+            }
+        };
+        let def = match def {
+            Some(Definition::ModuleDef(ModuleDef::Trait(it))) => Some(TypeHierarchyDef::Trait(it)),
+            Some(Definition::ModuleDef(ModuleDef::Adt(it))) => Some(TypeHierarchyDef::Adt(it)),
+            Some(Definition::SelfType(it)) => {
+                it.target_ty(sema.db).as_adt().map(TypeHierarchyDef::Adt)
+            }
+            _ => None,
+        };
+        if let Some(def) = def {
+            return Some(RangeInfo::new(sema.original_range(&parent).range, def));
+        }
+    }
+
+    // Fall back to the item around the position
+    if let Some(nominal_def) = find_node_at_offset::<ast::NominalDef>(file, position.offset) {
+        let adt = match &nominal_def {
+            ast::NominalDef::StructDef(it) => Adt::Struct(sema.to_def(it)?),
+            ast::NominalDef::EnumDef(it) => Adt::Enum(sema.to_def(it)?),
+            ast::NominalDef::UnionDef(it) => Adt::Union(sema.to_def(it)?),
+        };
+        let range = nominal_def.syntax().text_range();
+        Some(RangeInfo::new(range, TypeHierarchyDef::Adt(adt)))
+    } else {
+        let trait_def = find_node_at_offset::<ast::TraitDef>(file, position.offset)?;
+        let range = trait_def.syntax().text_range();
+        Some(RangeInfo::new(range, TypeHierarchyDef::Trait(sema.to_def(&trait_def)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::FilePosition;
+
+    use crate::mock_analysis::analysis_and_position;
+
+    fn check_hierarchy(fixture: &str, expected: &str, supertypes: &[&str], subtypes: &[&str]) {
+        let (analysis, pos) = analysis_and_position(fixture);
+
+        let nav = analysis.type_hierarchy(pos).unwrap().unwrap().info;
+        nav.assert_match(expected);
+
+        let item_pos =
+            FilePosition { file_id: nav.file_id(), offset: nav.focus_range().unwrap().start() };
+        let actual = analysis.supertypes(item_pos).unwrap().unwrap();
+        assert_eq!(actual.len(), supertypes.len());
+        for (nav, expected) in actual.iter().zip(supertypes) {
+            nav.assert_match(expected);
+        }
+
+        let actual = analysis.subtypes(item_pos).unwrap().unwrap();
+        assert_eq!(actual.len(), subtypes.len());
+        for (nav, expected) in actual.iter().zip(subtypes) {
+            nav.assert_match(expected);
+        }
+    }
+
+    #[test]
+    fn test_type_hierarchy_of_trait() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            trait Base {}
+            trait Sub: Base where Self: Clone {}
+            trait Clone {}
+            struct Foo;
+            impl Sub<|> for Foo {}
+            impl Sub for u32 {}
+            "#,
+            "Sub TRAIT_DEF FileId(1) [14; 50) [20; 23)",
+            &[
+                "Base TRAIT_DEF FileId(1) [0; 13) [6; 10)",
+                "Clone TRAIT_DEF FileId(1) [51; 65) [57; 62)",
+            ],
+            &["Foo STRUCT_DEF FileId(1) [66; 77) [73; 76)", "impl IMPL_DEF FileId(1) [98; 117)"],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_of_struct() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            trait Foo {}
+            trait Bar {}
+            struct Ba<|>z;
+            impl Foo for Baz {}
+            impl Bar for Baz {}
+            impl Baz {}
+            "#,
+            "Baz STRUCT_DEF FileId(1) [26; 37) [33; 36)",
+            &[
+                "Foo TRAIT_DEF FileId(1) [0; 12) [6; 9)",
+                "Bar TRAIT_DEF FileId(1) [13; 25) [19; 22)",
+            ],
+            &[],
+        );
+    }
+}
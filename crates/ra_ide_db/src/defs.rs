@@ -6,20 +6,23 @@
 // FIXME: this badly needs rename/rewrite (matklad, 2020-02-06).
 
 use hir::{
-    Adt, FieldSource, HasSource, ImplDef, Local, MacroDef, Module, ModuleDef, Name, PathResolution,
-    Semantics, StructField, TypeParam,
+    Adt, AsAssocItem, AssocItemContainer, FieldSource, HasSource, ImplDef, Local, MacroDef, Module,
+    ModuleDef, Name, PathResolution, Semantics, StructField, Trait, TypeParam,
 };
+use ra_db::FileRange;
 use ra_prof::profile;
 use ra_syntax::{
     ast::{self, AstNode, VisibilityOwner},
     match_ast,
+    SyntaxKind::{self, *},
+    SyntaxNode, SyntaxToken, T,
 };
 use test_utils::tested_by;
 
 use crate::RootDatabase;
 
 // FIXME: a more precise name would probably be `Symbol`?
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Definition {
     Macro(MacroDef),
     StructField(StructField),
@@ -27,6 +30,11 @@ pub enum Definition {
     SelfType(ImplDef),
     Local(Local),
     TypeParam(TypeParam),
+    // Lifetimes, labels and metavariables are not known to hir, so they are
+    // identified by the range of the token declaring them.
+    LifetimeParam(FileRange),
+    Label(FileRange),
+    MacroMetavar(FileRange),
 }
 
 impl Definition {
@@ -38,6 +46,9 @@ impl Definition {
             Definition::SelfType(it) => Some(it.module(db)),
             Definition::Local(it) => Some(it.module(db)),
             Definition::TypeParam(it) => Some(it.module(db)),
+            Definition::LifetimeParam(_) | Definition::Label(_) | Definition::MacroMetavar(_) => {
+                None
+            }
         }
     }
 
@@ -48,24 +59,33 @@ impl Definition {
                 FieldSource::Named(it) => it.visibility(),
                 FieldSource::Pos(it) => it.visibility(),
             },
-            Definition::ModuleDef(def) => match def {
-                ModuleDef::Module(it) => it.declaration_source(db)?.value.visibility(),
-                ModuleDef::Function(it) => it.source(db).value.visibility(),
-                ModuleDef::Adt(adt) => match adt {
-                    Adt::Struct(it) => it.source(db).value.visibility(),
-                    Adt::Union(it) => it.source(db).value.visibility(),
-                    Adt::Enum(it) => it.source(db).value.visibility(),
-                },
-                ModuleDef::Const(it) => it.source(db).value.visibility(),
-                ModuleDef::Static(it) => it.source(db).value.visibility(),
-                ModuleDef::Trait(it) => it.source(db).value.visibility(),
-                ModuleDef::TypeAlias(it) => it.source(db).value.visibility(),
-                ModuleDef::EnumVariant(_) => None,
-                ModuleDef::BuiltinType(_) => None,
-            },
+            Definition::ModuleDef(def) => {
+                // Trait items, and the items of trait impls, are as visible as the trait
+                if let Some(trait_) = containing_trait(db, def) {
+                    return trait_.source(db).value.visibility();
+                }
+                match def {
+                    ModuleDef::Module(it) => it.declaration_source(db)?.value.visibility(),
+                    ModuleDef::Function(it) => it.source(db).value.visibility(),
+                    ModuleDef::Adt(adt) => match adt {
+                        Adt::Struct(it) => it.source(db).value.visibility(),
+                        Adt::Union(it) => it.source(db).value.visibility(),
+                        Adt::Enum(it) => it.source(db).value.visibility(),
+                    },
+                    ModuleDef::Const(it) => it.source(db).value.visibility(),
+                    ModuleDef::Static(it) => it.source(db).value.visibility(),
+                    ModuleDef::Trait(it) => it.source(db).value.visibility(),
+                    ModuleDef::TypeAlias(it) => it.source(db).value.visibility(),
+                    ModuleDef::EnumVariant(_) => None,
+                    ModuleDef::BuiltinType(_) => None,
+                }
+            }
             Definition::SelfType(_) => None,
             Definition::Local(_) => None,
             Definition::TypeParam(_) => None,
+            Definition::LifetimeParam(_) => None,
+            Definition::Label(_) => None,
+            Definition::MacroMetavar(_) => None,
         }
     }
 
@@ -91,11 +111,27 @@ impl Definition {
             Definition::SelfType(_) => return None,
             Definition::Local(it) => it.name(db)?,
             Definition::TypeParam(it) => it.name(db),
+            Definition::LifetimeParam(_) | Definition::Label(_) | Definition::MacroMetavar(_) => {
+                return None
+            }
         };
         Some(name)
     }
 }
 
+fn containing_trait(db: &RootDatabase, def: &ModuleDef) -> Option<Trait> {
+    let assoc_item = match def {
+        ModuleDef::Function(it) => it.as_assoc_item(db),
+        ModuleDef::Const(it) => it.as_assoc_item(db),
+        ModuleDef::TypeAlias(it) => it.as_assoc_item(db),
+        _ => None,
+    }?;
+    match assoc_item.container(db) {
+        AssocItemContainer::Trait(it) => Some(it),
+        AssocItemContainer::ImplDef(it) => it.target_trait_def(db),
+    }
+}
+
 pub enum NameClass {
     Definition(Definition),
     /// `None` in `if let None = Some(82) {}`
@@ -270,3 +306,149 @@ pub fn classify_name_ref(
     };
     Some(NameRefClass::Definition(res))
 }
+
+/// Classifies the operator token of an overloaded operator expression (`+` in
+/// `a + b`, `*` in `*a`, `[` in `a[b]`, `?` in `a?` or `await` in `a.await`)
+/// as the method it calls.
+pub fn classify_operator(
+    sema: &Semantics<RootDatabase>,
+    token: &SyntaxToken,
+) -> Option<Definition> {
+    let _p = profile("classify_operator");
+
+    let expr = ast::Expr::cast(token.parent())?;
+    if token.kind().is_trivia() || operator_token(&expr).is_none() {
+        return None;
+    }
+    let func = sema.resolve_operator(&expr)?;
+    Some(Definition::ModuleDef(func.into()))
+}
+
+/// Returns the token naming the operator of an expression which may be
+/// overloaded by a trait, or `None` for other expressions.
+pub fn operator_token(expr: &ast::Expr) -> Option<SyntaxToken> {
+    let mut tokens = expr
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !it.kind().is_trivia());
+    match expr {
+        ast::Expr::BinExpr(it) => it.op_token(),
+        ast::Expr::PrefixExpr(it) => it.op_token(),
+        ast::Expr::IndexExpr(_) | ast::Expr::TryExpr(_) => tokens.next(),
+        ast::Expr::AwaitExpr(_) => tokens.find(|it| it.kind() == T![await]),
+        _ => None,
+    }
+}
+
+/// Classifies a lifetime, a label or a metavariable of a `macro_rules!`
+/// definition. Unlike names, these are resolved syntactically, to the nearest
+/// `<'a>`, the enclosing labeled loop or the matcher of the macro arm.
+pub fn classify_token(sema: &Semantics<RootDatabase>, token: &SyntaxToken) -> Option<Definition> {
+    let _p = profile("classify_token");
+
+    let parent = token.parent();
+    // Tokens of macro expansions have no range in a file to identify them by
+    if parent.ancestors().last()?.kind() != SOURCE_FILE {
+        return None;
+    }
+    let file_id = sema.original_range(&parent).file_id;
+    let file_range = |it: SyntaxToken| FileRange { file_id, range: it.text_range() };
+
+    let res = match token.kind() {
+        LIFETIME => match parent.kind() {
+            LABEL => Definition::Label(file_range(token.clone())),
+            BREAK_EXPR | CONTINUE_EXPR => Definition::Label(file_range(label_declaration(token)?)),
+            _ => Definition::LifetimeParam(file_range(lifetime_declaration(token)?)),
+        },
+        IDENT => Definition::MacroMetavar(file_range(metavar_declaration(token)?)),
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// Finds the `'a` in the nearest `<'a>` declaring the lifetime `token`.
+fn lifetime_declaration(token: &SyntaxToken) -> Option<SyntaxToken> {
+    token.parent().ancestors().find_map(|node| {
+        let binder = match node.kind() {
+            // `for<'a>` in `where for<'a> &'a T: Trait<'a>` also covers the bounds
+            WHERE_PRED => node.children().find(|it| it.kind() == FOR_TYPE)?,
+            _ => node,
+        };
+        let params = binder.children().find(|it| it.kind() == TYPE_PARAM_LIST)?;
+        params
+            .children()
+            .filter(|it| it.kind() == LIFETIME_PARAM)
+            .filter_map(|it| child_token(&it, LIFETIME))
+            .find(|it| it.text() == token.text())
+    })
+}
+
+/// Finds the label of the loop or block a `break` or `continue` jumps out of.
+fn label_declaration(token: &SyntaxToken) -> Option<SyntaxToken> {
+    token
+        .parent()
+        .ancestors()
+        .skip(1)
+        .take_while(|it| !is_body_boundary(it))
+        .find_map(|it| label_lifetime(&it).filter(|it| it.text() == token.text()))
+}
+
+/// Finds the `$name:fragment` declaring the metavariable `$name` in the
+/// matcher of its `macro_rules!` arm.
+fn metavar_declaration(token: &SyntaxToken) -> Option<SyntaxToken> {
+    if token.prev_sibling_or_token()?.kind() != T![$] {
+        return None;
+    }
+    matcher_metavars(&macro_rules_matcher(token)?).find(|it| it.text() == token.text())
+}
+
+/// Returns whether `node` is a function, closure or async block. Their bodies
+/// have exit points of their own, and loops and labeled blocks outside them
+/// can't be broken out of from inside.
+pub fn is_body_boundary(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        FN_DEF | LAMBDA_EXPR => true,
+        BLOCK_EXPR => child_token(node, T![async]).is_some(),
+        _ => false,
+    }
+}
+
+/// Returns the `'label` of a labeled loop or block.
+pub fn label_lifetime(node: &SyntaxNode) -> Option<SyntaxToken> {
+    let label = node.children().find(|it| it.kind() == LABEL)?;
+    child_token(&label, LIFETIME)
+}
+
+/// Finds the matcher of the `macro_rules!` arm `token` is part of.
+pub fn macro_rules_matcher(token: &SyntaxToken) -> Option<SyntaxNode> {
+    let macro_call = token.parent().ancestors().find_map(ast::MacroCall::cast)?;
+    let is_macro_rules = macro_call
+        .path()
+        .and_then(|it| it.segment())
+        .and_then(|it| it.name_ref())
+        .map_or(false, |it| it.text() == "macro_rules");
+    if !is_macro_rules {
+        return None;
+    }
+
+    // The arms are `matcher => transcriber;` pairs of token trees
+    let parts: Vec<SyntaxNode> = macro_call.token_tree()?.syntax().children().collect();
+    let idx = parts.iter().position(|it| token.text_range().is_subrange(&it.text_range()))?;
+    Some(parts[idx - idx % 2].clone())
+}
+
+/// Returns the `name` tokens of the `$name:fragment` metavariables declared
+/// by a `macro_rules!` matcher.
+pub fn matcher_metavars(matcher: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    matcher.descendants_with_tokens().filter_map(|it| it.into_token()).filter(|it| {
+        it.kind() == IDENT
+            && it.prev_sibling_or_token().map_or(false, |it| it.kind() == T![$])
+            && it.next_sibling_or_token().map_or(false, |it| it.kind() == T![:])
+    })
+}
+
+/// Returns the first token of the given `kind` among the children of `node`.
+pub fn child_token(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    node.children_with_tokens().filter_map(|it| it.into_token()).find(|it| it.kind() == kind)
+}
@@ -22,19 +22,35 @@ impl<'a> ImportsLocator<'a> {
 
     pub fn find_imports(&mut self, name_to_import: &str) -> Vec<ModuleDef> {
         let _p = profile("search_for_imports");
+        self.search(name_to_import, true, 40)
+    }
+
+    /// Like `find_imports`, but fuzzy matches `name_to_import` against item
+    /// names, returning at most `limit` results from both the project and the
+    /// libraries.
+    pub fn find_similar_imports(&mut self, name_to_import: &str, limit: usize) -> Vec<ModuleDef> {
+        let _p = profile("search_for_similar_imports");
+        self.search(name_to_import, false, limit)
+    }
+
+    fn search(&mut self, name_to_import: &str, exact: bool, limit: usize) -> Vec<ModuleDef> {
         let db = self.sema.db;
 
         let project_results = {
             let mut query = Query::new(name_to_import.to_string());
-            query.exact();
-            query.limit(40);
+            if exact {
+                query.exact();
+            }
+            query.limit(limit);
             symbol_index::world_symbols(db, query)
         };
         let lib_results = {
             let mut query = Query::new(name_to_import.to_string());
             query.libs();
-            query.exact();
-            query.limit(40);
+            if exact {
+                query.exact();
+            }
+            query.limit(limit);
             symbol_index::world_symbols(db, query)
         };
 
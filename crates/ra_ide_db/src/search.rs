@@ -6,18 +6,22 @@
 
 use std::mem;
 
-use hir::{DefWithBody, HasSource, ModuleSource, Semantics};
+use hir::{DefWithBody, Function, HasSource, MacroDef, ModuleDef, ModuleSource, Semantics};
 use once_cell::unsync::Lazy;
-use ra_db::{FileId, FileRange, SourceDatabaseExt};
+use ra_db::{FileId, FileRange, SourceDatabase, SourceDatabaseExt};
 use ra_prof::profile;
 use ra_syntax::{
-    algo::find_node_at_offset, ast, match_ast, AstNode, TextRange, TextUnit, TokenAtOffset,
+    algo::{find_covering_element, find_node_at_offset},
+    ast::{self, NameOwner},
+    match_ast, AstNode,
+    SyntaxKind::{FOR_TYPE, LABEL, LIFETIME_PARAM, MACRO_CALL, WHERE_PRED},
+    SyntaxNode, TextRange, TextUnit, TokenAtOffset,
 };
 use rustc_hash::FxHashMap;
 use test_utils::tested_by;
 
 use crate::{
-    defs::{classify_name_ref, Definition, NameRefClass},
+    defs::{classify_name_ref, classify_token, operator_token, Definition, NameRefClass},
     RootDatabase,
 };
 
@@ -33,6 +37,12 @@ pub enum ReferenceKind {
     StructFieldShorthandForField,
     StructFieldShorthandForLocal,
     StructLiteral,
+    /// Produced by the body of a macro rather than by its input, so it can't
+    /// be edited. The range is the one of the macro call.
+    MacroGenerated,
+    /// An overloaded operator (`a + b`, `a?`, ...) calling the method, which
+    /// has no name to edit. The range is the one of the operator token.
+    Operator,
     Other,
 }
 
@@ -107,6 +117,17 @@ impl IntoIterator for SearchScope {
 impl Definition {
     fn search_scope(&self, db: &RootDatabase) -> SearchScope {
         let _p = profile("search_scope");
+        match self {
+            Definition::LifetimeParam(decl)
+            | Definition::Label(decl)
+            | Definition::MacroMetavar(decl) => {
+                let mut res = FxHashMap::default();
+                res.insert(decl.file_id, token_scope(db, *decl));
+                return SearchScope::new(res);
+            }
+            _ => (),
+        }
+
         let module = match self.module(db) {
             Some(it) => it,
             None => return SearchScope::empty(),
@@ -188,20 +209,41 @@ impl Definition {
     ) -> Vec<Reference> {
         let _p = profile("Definition::find_usages");
 
-        let search_scope = {
-            let base = self.search_scope(db);
-            match search_scope {
-                None => base,
-                Some(scope) => base.intersection(&scope),
-            }
+        let base = self.search_scope(db);
+        // Macros producing a reference may be defined anywhere the item is visible
+        let macro_files: Vec<FileId> = base.entries.keys().copied().collect();
+        let search_scope = match search_scope {
+            None => base,
+            Some(scope) => base.intersection(&scope),
         };
 
+        match self {
+            Definition::LifetimeParam(decl)
+            | Definition::Label(decl)
+            | Definition::MacroMetavar(decl) => {
+                return token_usages(db, self, *decl, search_scope);
+            }
+            _ => (),
+        }
+
         let name = match self.name(db) {
             None => return Vec::new(),
             Some(it) => it.to_string(),
         };
 
         let pat = name.as_str();
+        let operator_tokens: &[&str] = match self {
+            Definition::ModuleDef(ModuleDef::Function(func)) => func.operator_tokens(db),
+            _ => &[],
+        };
+        // Operators are found by inferring the bodies using them, which is too
+        // slow for all the library crates an operator trait of the sysroot is
+        // visible in, so only the local crates are searched for those.
+        let operators_in_libraries = !self.module(db).map_or(false, |module| {
+            let file_id = module.definition_source(db).file_id.original_file(db);
+            is_library_file(db, file_id)
+        });
+        let macros = Lazy::new(|| macros_mentioning(db, &macro_files, pat));
         let mut refs = vec![];
 
         for (file_id, search_range) in search_scope {
@@ -236,6 +278,17 @@ impl Definition {
                         }
                     };
 
+                // Edit the token of the macro call input the expansion got the
+                // name from, which may be several (nested) calls up
+                let file_range = match name_ref
+                    .syntax()
+                    .first_token()
+                    .and_then(|it| sema.original_token_range(&it))
+                {
+                    Some(it) => it,
+                    None => continue,
+                };
+
                 // FIXME: reuse sb
                 // See https://github.com/rust-lang/rust/pull/68198#issuecomment-574269098
 
@@ -249,7 +302,6 @@ impl Definition {
                             ReferenceKind::Other
                         };
 
-                        let file_range = sema.original_range(name_ref.syntax());
                         refs.push(Reference {
                             file_range,
                             kind,
@@ -259,12 +311,12 @@ impl Definition {
                     Some(NameRefClass::FieldShorthand { local, field }) => {
                         match self {
                             Definition::StructField(_) if &field == self => refs.push(Reference {
-                                file_range: sema.original_range(name_ref.syntax()),
+                                file_range,
                                 kind: ReferenceKind::StructFieldShorthandForField,
                                 access: reference_access(&field, &name_ref),
                             }),
                             Definition::Local(l) if &local == l => refs.push(Reference {
-                                file_range: sema.original_range(name_ref.syntax()),
+                                file_range,
                                 kind: ReferenceKind::StructFieldShorthandForLocal,
                                 access: reference_access(&Definition::Local(local), &name_ref),
                             }),
@@ -275,11 +327,266 @@ impl Definition {
                     _ => {} // not a usage
                 }
             }
+
+            // Macros may produce the name without it appearing in the text
+            if text.contains('!') && !macros.is_empty() {
+                refs.extend(macro_generated_usages(
+                    &sema,
+                    file_id,
+                    &tree,
+                    search_range,
+                    self,
+                    pat,
+                    &macros,
+                ));
+            }
+
+            if let Definition::ModuleDef(ModuleDef::Function(func)) = self {
+                if operator_tokens.iter().any(|it| text.contains(it))
+                    && (operators_in_libraries || !is_library_file(db, file_id))
+                {
+                    refs.extend(operator_usages(
+                        &sema,
+                        file_id,
+                        &tree,
+                        search_range,
+                        *func,
+                        operator_tokens,
+                    ));
+                }
+            }
         }
         refs
     }
 }
 
+/// Finds the `macro_rules!` definitions in `files` whose body mentions `name`,
+/// by the name of the macro. Only the calls of these macros may produce a
+/// reference to `name` without it appearing in their input.
+fn macros_mentioning(
+    db: &RootDatabase,
+    files: &[FileId],
+    name: &str,
+) -> FxHashMap<String, Vec<MacroDef>> {
+    let _p = profile("macros_mentioning");
+    let sema = Semantics::new(db);
+    let mut res: FxHashMap<String, Vec<MacroDef>> = FxHashMap::default();
+    for &file_id in files {
+        let text = db.file_text(file_id);
+        if !text.contains("macro_rules") || !text.contains(name) {
+            continue;
+        }
+        for call in sema.parse(file_id).syntax().descendants().filter_map(ast::MacroCall::cast) {
+            let macro_name = match call.name() {
+                Some(it) => it,
+                None => continue,
+            };
+            if !call.token_tree().map_or(false, |it| it.syntax().text().to_string().contains(name))
+            {
+                continue;
+            }
+            if let Some(def) = sema.to_def(&call) {
+                res.entry(macro_name.text().to_string()).or_default().push(def);
+            }
+        }
+    }
+    res
+}
+
+/// Finds the references to `def` in the expansions of the macro calls in
+/// `tree` which come from the body of a macro rather than from its input.
+/// Only the calls of `macros`, the macros whose definition contains `name`,
+/// are expanded, so a name a macro body only gets from another macro it calls
+/// isn't found.
+fn macro_generated_usages(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    tree: &SyntaxNode,
+    search_range: TextRange,
+    def: &Definition,
+    name: &str,
+    macros: &FxHashMap<String, Vec<MacroDef>>,
+) -> Vec<Reference> {
+    let mut res: Vec<Reference> = Vec::new();
+    let is_candidate = |call: &ast::MacroCall| {
+        let macro_name = call.path().and_then(|it| it.segment()).and_then(|it| it.name_ref());
+        macro_name.map_or(false, |it| macros.contains_key(it.text().as_str()))
+    };
+    // Nested calls are reported at the range of the outermost one
+    let mut calls: Vec<(ast::MacroCall, TextRange)> = tree
+        .descendants()
+        .filter_map(ast::MacroCall::cast)
+        .filter(|it| is_candidate(it))
+        .map(|it| {
+            let range = it.syntax().text_range();
+            (it, range)
+        })
+        .filter(|(_, range)| range.is_subrange(&search_range))
+        .collect();
+
+    while let Some((call, call_range)) = calls.pop() {
+        // Resolving is what is expensive, so it's only done for the candidates
+        let relevant = match sema.resolve_macro_call(&call) {
+            Some(macro_def) => macros.values().flatten().any(|it| *it == macro_def),
+            None => false,
+        };
+        if !relevant {
+            continue;
+        }
+        let expansion = match sema.expand(&call) {
+            Some(it) => it,
+            None => continue,
+        };
+        calls.extend(
+            expansion
+                .descendants()
+                .filter_map(ast::MacroCall::cast)
+                .filter(|it| is_candidate(it))
+                .map(|it| (it, call_range)),
+        );
+        if !expansion.text().to_string().contains(name) {
+            continue;
+        }
+
+        for name_ref in expansion.descendants().filter_map(ast::NameRef::cast) {
+            if name_ref.text().as_str() != name {
+                continue;
+            }
+            match classify_name_ref(sema, &name_ref) {
+                Some(NameRefClass::Definition(it)) if &it == def => (),
+                _ => continue,
+            }
+            let from_input = name_ref
+                .syntax()
+                .first_token()
+                .map_or(false, |it| sema.original_token_range(&it).is_some());
+            let file_range = FileRange { file_id, range: call_range };
+            if from_input || res.iter().any(|it| it.file_range == file_range) {
+                continue;
+            }
+            res.push(Reference { file_range, kind: ReferenceKind::MacroGenerated, access: None });
+        }
+    }
+    res
+}
+
+/// Finds the overloaded operators in `tree` which call `func`, either through
+/// the impl method or the trait method they desugar to. The operators are
+/// looked up in the resolutions of each body rather than resolved one by one,
+/// and only the bodies using one of the `operator_tokens` are inferred.
+fn operator_usages(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    tree: &SyntaxNode,
+    search_range: TextRange,
+    func: Function,
+    operator_tokens: &[&str],
+) -> Vec<Reference> {
+    let uses_operator = |body: &SyntaxNode| {
+        body.descendants()
+            .filter(|it| it.text_range().intersection(&search_range).is_some())
+            .filter_map(ast::Expr::cast)
+            .filter_map(|it| operator_token(&it))
+            .any(|it| operator_tokens.contains(&it.text().as_str()))
+    };
+    tree.descendants()
+        .filter(|it| it.text_range().intersection(&search_range).is_some())
+        .filter_map(|node| {
+            let body: DefWithBody = match_ast! {
+                match node {
+                    ast::FnDef(it) => sema.to_def(&it)?.into(),
+                    ast::ConstDef(it) => sema.to_def(&it)?.into(),
+                    ast::StaticDef(it) => sema.to_def(&it)?.into(),
+                    _ => return None,
+                }
+            };
+            if !uses_operator(&node) {
+                return None;
+            }
+            Some(body)
+        })
+        .flat_map(|body| body.operator_calls(sema.db, func))
+        // Operators produced by macros have no token to point to
+        .filter(|expr| expr.file_id == file_id.into())
+        .filter_map(|expr| {
+            let token = operator_token(&expr.value)?;
+            if !token.text_range().is_subrange(&search_range) {
+                return None;
+            }
+            Some(Reference {
+                file_range: FileRange { file_id, range: token.text_range() },
+                kind: ReferenceKind::Operator,
+                access: None,
+            })
+        })
+        .collect()
+}
+
+fn is_library_file(db: &RootDatabase, file_id: FileId) -> bool {
+    db.source_root(db.file_source_root(file_id)).is_library
+}
+
+/// Returns the range of the item, `for<>` binder, labeled loop or macro a
+/// lifetime, label or metavariable declared at `decl` can be used in.
+fn token_scope(db: &RootDatabase, decl: FileRange) -> Option<TextRange> {
+    let file = db.parse(decl.file_id).tree();
+    let token = find_covering_element(file.syntax(), decl.range).into_token()?;
+    let parent = token.parent();
+    let scope = match parent.kind() {
+        LIFETIME_PARAM => {
+            let owner = parent.parent()?.parent()?;
+            match owner.parent() {
+                Some(pred) if owner.kind() == FOR_TYPE && pred.kind() == WHERE_PRED => pred,
+                _ => owner,
+            }
+        }
+        LABEL => parent.parent()?,
+        _ => parent.ancestors().find(|it| it.kind() == MACRO_CALL)?,
+    };
+    Some(scope.text_range())
+}
+
+/// Finds the usages of a lifetime, label or metavariable among the tokens
+/// with the same text, as these are resolved syntactically.
+fn token_usages(
+    db: &RootDatabase,
+    def: &Definition,
+    decl: FileRange,
+    search_scope: SearchScope,
+) -> Vec<Reference> {
+    let sema = Semantics::new(db);
+    let tree = sema.parse(decl.file_id).syntax().clone();
+    let decl_token = match find_covering_element(&tree, decl.range).into_token() {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+
+    let mut refs = Vec::new();
+    for (file_id, search_range) in search_scope {
+        if file_id != decl.file_id {
+            continue;
+        }
+        let search_range = search_range.unwrap_or_else(|| tree.text_range());
+        for token in tree.descendants_with_tokens().filter_map(|it| it.into_token()) {
+            if token == decl_token
+                || token.kind() != decl_token.kind()
+                || token.text() != decl_token.text()
+                || !token.text_range().is_subrange(&search_range)
+            {
+                continue;
+            }
+            if classify_token(&sema, &token).as_ref() == Some(def) {
+                refs.push(Reference {
+                    file_range: FileRange { file_id, range: token.text_range() },
+                    kind: ReferenceKind::Other,
+                    access: None,
+                });
+            }
+        }
+    }
+    refs
+}
+
 fn reference_access(def: &Definition, name_ref: &ast::NameRef) -> Option<ReferenceAccess> {
     // Only Locals and Fields have accesses for now.
     match def {
@@ -1,14 +1,22 @@
 //! FIXME: write short doc here
 
-use std::path::{Path, PathBuf};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use anyhow::{Context, Result};
-use cargo_metadata::{CargoOpt, Message, MetadataCommand, PackageId};
+use cargo_metadata::{Message, Metadata, PackageId};
 use ra_arena::{impl_arena_id, Arena, RawId};
 use ra_cargo_watch::run_cargo;
+use ra_cfg::CfgOptions;
 use ra_db::Edition;
-use rustc_hash::FxHashMap;
-use serde::Deserialize;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::try_get_rustc_cfg_options;
 
 /// `CargoWorkspace` represents the logical structure of, well, a Cargo
 /// workspace. It pretty closely mirrors `cargo metadata` output.
@@ -22,6 +30,11 @@ pub struct CargoWorkspace {
     packages: Arena<Package, PackageData>,
     targets: Arena<Target, TargetData>,
     workspace_root: PathBuf,
+    /// Target triple the workspace is analyzed for, `None` means the host.
+    target: Option<String>,
+    /// `rustc --print cfg --target` output for every target triple used by
+    /// the packages of this workspace.
+    target_cfgs: FxHashMap<String, CfgOptions>,
 }
 
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -37,8 +50,19 @@ pub struct CargoFeatures {
     /// This will be ignored if `cargo_all_features` is true.
     pub features: Vec<String>,
 
-    /// Runs cargo check on launch to figure out the correct values of OUT_DIR
+    /// Runs cargo check on launch to figure out the correct values of OUT_DIR,
+    /// as well as the cfgs and env vars emitted by build scripts
     pub load_out_dirs_from_check: bool,
+
+    /// Target triple to analyze the workspace for, like `wasm32-unknown-unknown`.
+    /// Defaults to the host target.
+    pub target: Option<String>,
+
+    /// Per-package overrides of `target`, keyed by package name, or by
+    /// `name:version` if several versions of the package are used. Both the
+    /// cfgs and the dependencies of the package are resolved for its target,
+    /// and its dependencies are analyzed for it too.
+    pub package_targets: FxHashMap<String, String>,
 }
 
 impl Default for CargoFeatures {
@@ -48,6 +72,8 @@ impl Default for CargoFeatures {
             all_features: true,
             features: Vec::new(),
             load_out_dirs_from_check: false,
+            target: None,
+            package_targets: FxHashMap::default(),
         }
     }
 }
@@ -70,6 +96,13 @@ struct PackageData {
     edition: Edition,
     features: Vec<String>,
     out_dir: Option<PathBuf>,
+    /// Environment variables cargo sets when compiling the crates of this
+    /// package, including the ones emitted by its build script.
+    env: Vec<(String, String)>,
+    /// Extra cfg flags emitted by the build script.
+    cfgs: Vec<String>,
+    /// Target triple this package is analyzed for, `None` for the host.
+    target: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -144,6 +177,15 @@ impl Package {
     pub fn out_dir(self, ws: &CargoWorkspace) -> Option<&Path> {
         ws.packages[self].out_dir.as_ref().map(PathBuf::as_path)
     }
+    pub fn env(self, ws: &CargoWorkspace) -> &[(String, String)] {
+        &ws.packages[self].env
+    }
+    pub fn cfgs(self, ws: &CargoWorkspace) -> &[String] {
+        &ws.packages[self].cfgs
+    }
+    pub fn target(self, ws: &CargoWorkspace) -> Option<&str> {
+        ws.packages[self].target.as_ref().map(String::as_str)
+    }
 }
 
 impl Target {
@@ -169,27 +211,42 @@ impl CargoWorkspace {
         cargo_toml: &Path,
         cargo_features: &CargoFeatures,
     ) -> Result<CargoWorkspace> {
-        let mut meta = MetadataCommand::new();
-        meta.manifest_path(cargo_toml);
-        if cargo_features.all_features {
-            meta.features(CargoOpt::AllFeatures);
-        } else if cargo_features.no_default_features {
-            // FIXME: `NoDefaultFeatures` is mutual exclusive with `SomeFeatures`
-            // https://github.com/oli-obk/cargo_metadata/issues/79
-            meta.features(CargoOpt::NoDefaultFeatures);
-        } else if !cargo_features.features.is_empty() {
-            meta.features(CargoOpt::SomeFeatures(cargo_features.features.clone()));
-        }
-        if let Some(parent) = cargo_toml.parent() {
-            meta.current_dir(parent);
+        let ws_target = cargo_features.target.as_ref();
+        let mut homepages = FxHashMap::default();
+        let meta = exec_metadata(cargo_toml, cargo_features, ws_target, &mut homepages)?;
+        let package_targets =
+            resolve_package_targets(&meta.packages, &cargo_features.package_targets)?;
+
+        // Packages with a target override resolve their dependencies for it
+        let mut override_metas: Vec<(String, Metadata)> = Vec::new();
+        for target in package_targets.values() {
+            if Some(target) != ws_target && !override_metas.iter().any(|(it, _)| it == target) {
+                let meta = exec_metadata(cargo_toml, cargo_features, Some(target), &mut homepages)?;
+                override_metas.push((target.clone(), meta));
+            }
         }
-        let meta = meta.exec().with_context(|| {
-            format!("Failed to run `cargo metadata --manifest-path {}`", cargo_toml.display())
-        })?;
 
-        let mut out_dir_by_id = FxHashMap::default();
+        let resolves = Some((ws_target, &meta))
+            .into_iter()
+            .chain(override_metas.iter().map(|(target, meta)| (Some(target), meta)))
+            .map(|(target, meta)| {
+                let resolve = meta.resolve.as_ref().expect("metadata executed with deps");
+                (target, &resolve.nodes)
+            })
+            .collect::<Vec<_>>();
+        let pkg_targets = propagate_package_targets(&package_targets, |target, id| {
+            let nodes = resolves
+                .iter()
+                .find(|(it, _)| it.map(String::as_str) == Some(target))
+                .map_or(resolves[0].1, |&(_, nodes)| nodes);
+            let node = nodes.iter().find(|node| node.id == *id);
+            node.map(|node| node.deps.iter().map(|dep| dep.pkg.clone()).collect())
+                .unwrap_or_default()
+        });
+
+        let mut resources = ExternResources::default();
         if cargo_features.load_out_dirs_from_check {
-            out_dir_by_id = load_out_dirs(cargo_toml, cargo_features);
+            resources = load_extern_resources(cargo_toml, cargo_features, &meta);
         }
 
         let mut pkg_by_id = FxHashMap::default();
@@ -198,29 +255,45 @@ impl CargoWorkspace {
 
         let ws_members = &meta.workspace_members;
 
-        for meta_pkg in meta.packages {
+        // Dependencies of the overridden packages may only be listed by the
+        // metadata of their target
+        let meta_pkgs = meta
+            .packages
+            .iter()
+            .chain(override_metas.iter().flat_map(|(_, meta)| meta.packages.iter()));
+        for meta_pkg in meta_pkgs {
+            if pkg_by_id.contains_key(&meta_pkg.id) {
+                continue;
+            }
+            let mut env = package_env(meta_pkg, homepages.get(&meta_pkg.id));
             let cargo_metadata::Package { id, edition, name, manifest_path, .. } = meta_pkg;
-            let is_member = ws_members.contains(&id);
+            let is_member = ws_members.contains(id);
             let edition = edition
                 .parse::<Edition>()
                 .with_context(|| format!("Failed to parse edition {}", edition))?;
+            let build_script = resources.build_scripts.remove(id).unwrap_or_default();
+            env.extend(build_script.env);
+            let target = pkg_targets.get(id).or(ws_target).cloned();
             let pkg = packages.alloc(PackageData {
-                name,
-                manifest: manifest_path,
+                name: name.clone(),
+                manifest: manifest_path.clone(),
                 targets: Vec::new(),
                 is_member,
                 edition,
                 dependencies: Vec::new(),
                 features: Vec::new(),
-                out_dir: out_dir_by_id.get(&id).cloned(),
+                out_dir: build_script.out_dir,
+                env,
+                cfgs: build_script.cfgs,
+                target,
             });
             let pkg_data = &mut packages[pkg];
-            pkg_by_id.insert(id, pkg);
-            for meta_tgt in meta_pkg.targets {
+            pkg_by_id.insert(id.clone(), pkg);
+            for meta_tgt in meta_pkg.targets.iter() {
                 let is_proc_macro = meta_tgt.kind.as_slice() == ["proc-macro"];
                 let tgt = targets.alloc(TargetData {
                     pkg,
-                    name: meta_tgt.name,
+                    name: meta_tgt.name.clone(),
                     root: meta_tgt.src_path.clone(),
                     kind: TargetKind::new(meta_tgt.kind.as_slice()),
                     is_proc_macro,
@@ -228,36 +301,71 @@ impl CargoWorkspace {
                 pkg_data.targets.push(tgt);
             }
         }
-        let resolve = meta.resolve.expect("metadata executed with deps");
-        for node in resolve.nodes {
-            let source = match pkg_by_id.get(&node.id) {
-                Some(&src) => src,
-                // FIXME: replace this and a similar branch below with `.unwrap`, once
-                // https://github.com/rust-lang/cargo/issues/7841
-                // is fixed and hits stable (around 1.43-is probably?).
-                None => {
-                    log::error!("Node id do not match in cargo metadata, ignoring {}", node.id);
-                    continue;
-                }
-            };
-            for dep_node in node.deps {
-                let pkg = match pkg_by_id.get(&dep_node.pkg) {
-                    Some(&pkg) => pkg,
-                    None => {
-                        log::error!(
-                            "Dep node id do not match in cargo metadata, ignoring {}",
-                            dep_node.pkg
-                        );
+
+        // A package takes its dependencies and features from the metadata of
+        // its own target. Packages which only the metadata of another target
+        // lists take them from the first metadata listing them.
+        let mut resolved = FxHashSet::default();
+        for &own_target_only in [true, false].iter() {
+            for &(target, nodes) in resolves.iter() {
+                for node in nodes {
+                    let source = match pkg_by_id.get(&node.id) {
+                        Some(&src) => src,
+                        // FIXME: replace this and a similar branch below with `.unwrap`, once
+                        // https://github.com/rust-lang/cargo/issues/7841
+                        // is fixed and hits stable (around 1.43-is probably?).
+                        None => {
+                            if own_target_only {
+                                log::error!(
+                                    "Node id do not match in cargo metadata, ignoring {}",
+                                    node.id
+                                );
+                            }
+                            continue;
+                        }
+                    };
+                    if resolved.contains(&source)
+                        || (own_target_only && packages[source].target.as_ref() != target)
+                    {
                         continue;
                     }
-                };
-                let dep = PackageDependency { name: dep_node.name, pkg };
-                packages[source].dependencies.push(dep);
+                    resolved.insert(source);
+                    for dep_node in node.deps.iter() {
+                        let pkg = match pkg_by_id.get(&dep_node.pkg) {
+                            Some(&pkg) => pkg,
+                            None => {
+                                log::error!(
+                                    "Dep node id do not match in cargo metadata, ignoring {}",
+                                    dep_node.pkg
+                                );
+                                continue;
+                            }
+                        };
+                        let dep = PackageDependency { name: dep_node.name.clone(), pkg };
+                        packages[source].dependencies.push(dep);
+                    }
+                    packages[source].features.extend(node.features.iter().cloned());
+                }
             }
-            packages[source].features.extend(node.features);
         }
 
-        Ok(CargoWorkspace { packages, targets, workspace_root: meta.workspace_root })
+        let mut target_cfgs = FxHashMap::default();
+        let pkg_targets = packages.iter().filter_map(|(_, pkg)| pkg.target.as_ref());
+        for target in ws_target.into_iter().chain(pkg_targets) {
+            if !target_cfgs.contains_key(target) {
+                let cfgs = try_get_rustc_cfg_options(Some(target))
+                    .with_context(|| format!("Failed to get the cfgs of target {}", target))?;
+                target_cfgs.insert(target.clone(), cfgs);
+            }
+        }
+
+        Ok(CargoWorkspace {
+            packages,
+            targets,
+            workspace_root: meta.workspace_root.clone(),
+            target: cargo_features.target.clone(),
+            target_cfgs,
+        })
     }
 
     pub fn packages<'a>(&'a self) -> impl Iterator<Item = Package> + ExactSizeIterator + 'a {
@@ -271,12 +379,207 @@ impl CargoWorkspace {
     pub fn workspace_root(&self) -> &Path {
         &self.workspace_root
     }
+
+    /// Target triple the whole workspace is analyzed for, if not the host.
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_ref().map(String::as_str)
+    }
+
+    /// Cfg options reported by rustc for `target`, if any package of the
+    /// workspace is analyzed for it.
+    pub fn target_cfg_options(&self, target: &str) -> Option<&CfgOptions> {
+        self.target_cfgs.get(target)
+    }
 }
 
-pub fn load_out_dirs(
+/// Looks up the packages the keys of the `package_targets` setting refer to,
+/// either by name or, if several versions of a package are used, by
+/// `name:version`. A key matching no package, or more than one, is an error.
+fn resolve_package_targets(
+    packages: &[cargo_metadata::Package],
+    package_targets: &FxHashMap<String, String>,
+) -> Result<FxHashMap<PackageId, String>> {
+    let mut res = FxHashMap::default();
+    for (key, target) in package_targets {
+        let (name, version) = match key.find(':') {
+            Some(idx) => (&key[..idx], Some(&key[idx + 1..])),
+            None => (key.as_str(), None),
+        };
+        let matching = packages
+            .iter()
+            .filter(|pkg| pkg.name == name)
+            .filter(|pkg| version.map_or(true, |it| pkg.version.to_string() == it))
+            .collect::<Vec<_>>();
+        match matching.as_slice() {
+            [pkg] => {
+                res.insert(pkg.id.clone(), target.clone());
+            }
+            [] => anyhow::bail!("Unknown package `{}` in the package targets", key),
+            _ => {
+                let versions = matching
+                    .iter()
+                    .map(|pkg| format!("`{}:{}`", pkg.name, pkg.version))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                anyhow::bail!(
+                    "Package `{}` in the package targets is ambiguous, use one of {}",
+                    key,
+                    versions
+                )
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Returns the target of every package which is analyzed for a target override:
+/// the overridden packages and, transitively, their dependencies. `deps` lists
+/// the dependencies of a package resolved for a target.
+///
+/// An explicit override always wins. A dependency of several overridden
+/// packages takes the target of the one with the smallest package id.
+fn propagate_package_targets(
+    overrides: &FxHashMap<PackageId, String>,
+    deps: impl Fn(&str, &PackageId) -> Vec<PackageId>,
+) -> FxHashMap<PackageId, String> {
+    let mut overrides = overrides.iter().collect::<Vec<_>>();
+    overrides.sort_by(|(a, _), (b, _)| a.repr.cmp(&b.repr));
+
+    let mut res: FxHashMap<PackageId, String> =
+        overrides.iter().map(|&(id, target)| (id.clone(), target.clone())).collect();
+    for (id, target) in overrides {
+        let mut queue = deps(target, id);
+        while let Some(dep) = queue.pop() {
+            if res.contains_key(&dep) {
+                continue;
+            }
+            queue.extend(deps(target, &dep));
+            res.insert(dep, target.clone());
+        }
+    }
+    res
+}
+
+/// Runs `cargo metadata`, resolving the dependencies for `target`, or for all
+/// targets if there is none, and collects the homepages of the packages, which
+/// `cargo_metadata` doesn't know about, from its raw output into `homepages`.
+fn exec_metadata(
     cargo_toml: &Path,
     cargo_features: &CargoFeatures,
-) -> FxHashMap<PackageId, PathBuf> {
+    target: Option<&String>,
+    homepages: &mut FxHashMap<PackageId, String>,
+) -> Result<Metadata> {
+    let mut args: Vec<String> = vec![
+        "metadata".to_string(),
+        "--format-version".to_string(),
+        "1".to_string(),
+        "--manifest-path".to_string(),
+        format!("{}", cargo_toml.display()),
+    ];
+    if cargo_features.all_features {
+        args.push("--all-features".to_string());
+    } else if cargo_features.no_default_features {
+        // FIXME: `NoDefaultFeatures` is mutual exclusive with `SomeFeatures`
+        // https://github.com/oli-obk/cargo_metadata/issues/79
+        args.push("--no-default-features".to_string());
+    } else if !cargo_features.features.is_empty() {
+        args.push("--features".to_string());
+        args.push(cargo_features.features.join(" "));
+    }
+    if let Some(target) = target {
+        args.push("--filter-platform".to_string());
+        args.push(target.clone());
+    }
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut cmd = Command::new(cargo);
+    cmd.args(&args);
+    if let Some(parent) = cargo_toml.parent() {
+        cmd.current_dir(parent);
+    }
+    let context =
+        || format!("Failed to run `cargo metadata --manifest-path {}`", cargo_toml.display());
+    let output = cmd.output().with_context(context)?;
+    if !output.status.success() {
+        anyhow::bail!("{}: {}", context(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let raw: serde_json::Value = serde_json::from_slice(&output.stdout).with_context(context)?;
+    homepages.extend(package_homepages(&raw));
+    serde_json::from_value(raw).with_context(context)
+}
+
+/// Reads the `homepage` of each package from the raw `cargo metadata` output.
+fn package_homepages(raw: &serde_json::Value) -> Vec<(PackageId, String)> {
+    let packages = raw["packages"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    packages
+        .iter()
+        .filter_map(|pkg| {
+            let id = serde_json::from_value(pkg["id"].clone()).ok()?;
+            let homepage = pkg["homepage"].as_str()?;
+            Some((id, homepage.to_string()))
+        })
+        .collect()
+}
+
+/// Environment variables which cargo passes to rustc for every crate of the
+/// package, see https://doc.rust-lang.org/cargo/reference/environment-variables.html
+fn package_env(pkg: &cargo_metadata::Package, homepage: Option<&String>) -> Vec<(String, String)> {
+    let version = &pkg.version;
+    let pre = version.pre.iter().map(|it| it.to_string()).collect::<Vec<_>>().join(".");
+    let manifest_dir = pkg.manifest_path.parent().unwrap_or(&pkg.manifest_path);
+    let opt = |it: &Option<String>| it.clone().unwrap_or_default();
+
+    // FIXME: We probably mangle non UTF-8 paths here, figure out a better solution
+    vec![
+        ("CARGO".to_string(), std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string())),
+        ("CARGO_MANIFEST_DIR".to_string(), manifest_dir.to_string_lossy().to_string()),
+        ("CARGO_PKG_NAME".to_string(), pkg.name.clone()),
+        ("CARGO_PKG_VERSION".to_string(), version.to_string()),
+        ("CARGO_PKG_VERSION_MAJOR".to_string(), version.major.to_string()),
+        ("CARGO_PKG_VERSION_MINOR".to_string(), version.minor.to_string()),
+        ("CARGO_PKG_VERSION_PATCH".to_string(), version.patch.to_string()),
+        ("CARGO_PKG_VERSION_PRE".to_string(), pre),
+        ("CARGO_PKG_AUTHORS".to_string(), pkg.authors.join(":")),
+        ("CARGO_PKG_DESCRIPTION".to_string(), opt(&pkg.description)),
+        ("CARGO_PKG_REPOSITORY".to_string(), opt(&pkg.repository)),
+        ("CARGO_PKG_LICENSE".to_string(), opt(&pkg.license)),
+        (
+            "CARGO_PKG_LICENSE_FILE".to_string(),
+            pkg.license_file
+                .as_ref()
+                .map(|it| it.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        ),
+        ("CARGO_PKG_HOMEPAGE".to_string(), homepage.cloned().unwrap_or_default()),
+    ]
+}
+
+/// Output of a single build script run, as reported by `cargo check`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildScriptOutput {
+    pub out_dir: Option<PathBuf>,
+    /// `cargo:rustc-cfg` flags, like `has_i128` or `feature="foo"`.
+    pub cfgs: Vec<String>,
+    /// `cargo:rustc-env` variables.
+    pub env: Vec<(String, String)>,
+}
+
+/// Everything we learn about packages by running their build scripts.
+///
+/// Running `cargo check` is expensive, so the result is cached in the target
+/// directory together with a fingerprint of the inputs it depends on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExternResources {
+    fingerprint: u64,
+    build_scripts: FxHashMap<PackageId, BuildScriptOutput>,
+}
+
+pub fn load_extern_resources(
+    cargo_toml: &Path,
+    cargo_features: &CargoFeatures,
+    meta: &Metadata,
+) -> ExternResources {
     let mut args: Vec<String> = vec![
         "check".to_string(),
         "--message-format=json".to_string(),
@@ -291,18 +594,34 @@ pub fn load_out_dirs(
         // https://github.com/oli-obk/cargo_metadata/issues/79
         args.push("--no-default-features".to_string());
     } else if !cargo_features.features.is_empty() {
-        for feature in &cargo_features.features {
-            args.push(feature.clone());
+        args.push("--features".to_string());
+        args.push(cargo_features.features.join(" "));
+    }
+    if let Some(target) = &cargo_features.target {
+        args.push("--target".to_string());
+        args.push(target.clone());
+    }
+
+    let cache_path = meta.target_directory.join("rust-analyzer").join("build-scripts.json");
+    if let Some(res) = read_cache(&cache_path) {
+        // `cargo clean` removes the out dirs without changing any input
+        let out_dirs_exist =
+            res.build_scripts.values().filter_map(|it| it.out_dir.as_ref()).all(|it| it.exists());
+        if out_dirs_exist && res.fingerprint == fingerprint(meta, &args, &res) {
+            return res;
         }
     }
 
-    let mut res = FxHashMap::default();
+    let mut res = ExternResources::default();
     let mut child = run_cargo(&args, cargo_toml.parent(), &mut |message| {
         match message {
             Message::BuildScriptExecuted(message) => {
-                let package_id = message.package_id;
-                let out_dir = message.out_dir;
-                res.insert(package_id, out_dir);
+                let output = BuildScriptOutput {
+                    out_dir: Some(message.out_dir),
+                    cfgs: message.cfgs.iter().map(|it| it.to_string_lossy().to_string()).collect(),
+                    env: message.env,
+                };
+                res.build_scripts.insert(message.package_id, output);
             }
 
             Message::CompilerArtifact(_) => (),
@@ -312,6 +631,282 @@ pub fn load_out_dirs(
         true
     });
 
-    let _ = child.wait();
+    match child.wait() {
+        Ok(status) if status.success() => {
+            res.fingerprint = fingerprint(meta, &args, &res);
+            write_cache(&cache_path, &res)
+        }
+        // Don't cache partial results, e.g. when the build script failed to
+        // compile, so that we retry on the next start.
+        _ => (),
+    }
     res
 }
+
+/// Hashes everything that may change the output of the build scripts: the
+/// `cargo check` invocation, `Cargo.lock`, the manifests and build scripts of
+/// the workspace members, and the files the build scripts of `res` asked to
+/// be rerun for with `cargo:rerun-if-changed`. Registry dependencies are
+/// pinned by `Cargo.lock`.
+///
+/// The fingerprint is persisted, so it uses FNV-1a rather than the std hasher,
+/// whose output may change between releases.
+fn fingerprint(meta: &Metadata, args: &[String], res: &ExternResources) -> u64 {
+    let mut hasher = StableHasher::default();
+    for arg in args {
+        hasher.write(arg.as_bytes());
+    }
+    hasher.write_path(&meta.workspace_root.join("Cargo.lock"));
+    for pkg in meta.packages.iter() {
+        if meta.workspace_members.contains(&pkg.id) {
+            hasher.write_path(&pkg.manifest_path);
+            let build_scripts =
+                pkg.targets.iter().filter(|tgt| tgt.kind.iter().any(|it| it == "custom-build"));
+            for tgt in build_scripts {
+                hasher.write_path(&tgt.src_path);
+            }
+        }
+        let out_dir = res.build_scripts.get(&pkg.id).and_then(|it| it.out_dir.as_ref());
+        if let (Some(out_dir), Some(pkg_dir)) = (out_dir, pkg.manifest_path.parent()) {
+            for path in rerun_if_changed(out_dir) {
+                hasher.write_path(&pkg_dir.join(path));
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// The `cargo:rerun-if-changed` paths printed by a build script, read from
+/// the `output` file cargo stores next to its `OUT_DIR`.
+fn rerun_if_changed(out_dir: &Path) -> Vec<PathBuf> {
+    const RERUN_IF_CHANGED: &str = "cargo:rerun-if-changed=";
+    let output = match out_dir.parent().and_then(|it| fs::read_to_string(it.join("output")).ok()) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with(RERUN_IF_CHANGED))
+        .map(|line| PathBuf::from(&line[RERUN_IF_CHANGED.len()..]))
+        .collect()
+}
+
+/// 64-bit FNV-1a, which gives the same hash on every run and platform.
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> StableHasher {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        // Prefixing the length keeps `["ab", "c"]` and `["a", "bc"]` apart
+        for &byte in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x100_0000_01b3);
+        }
+    }
+
+    /// Hashes the contents of a file, or of the files of a directory.
+    fn write_path(&mut self, path: &Path) {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = match fs::read_dir(path) {
+                Ok(it) => it.filter_map(|it| Some(it.ok()?.path())).collect(),
+                Err(_) => return self.write(&[]),
+            };
+            entries.sort();
+            for entry in entries {
+                self.write(entry.to_string_lossy().as_bytes());
+                self.write_path(&entry);
+            }
+        } else {
+            match fs::read(path) {
+                Ok(contents) => self.write(&contents),
+                Err(_) => self.write(&[]),
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn read_cache(path: &Path) -> Option<ExternResources> {
+    let file = File::open(path).ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
+
+fn write_cache(path: &Path, res: &ExternResources) {
+    let write = || -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        serde_json::to_writer(File::create(path)?, res)?;
+        Ok(())
+    };
+    if let Err(err) = write() {
+        log::warn!("failed to cache build script results at {}: {}", path.display(), err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::PackageId;
+    use rustc_hash::FxHashMap;
+    use serde_json::json;
+
+    use super::{
+        package_env, package_homepages, propagate_package_targets, resolve_package_targets,
+        StableHasher,
+    };
+
+    #[test]
+    fn stable_hasher_is_stable() {
+        let hash = |chunks: &[&str]| {
+            let mut hasher = StableHasher::default();
+            for chunk in chunks {
+                hasher.write(chunk.as_bytes());
+            }
+            hasher.finish()
+        };
+        // The fingerprints are persisted, so these must never change
+        assert_eq!(hash(&["check", "--all-features"]), 0x44d3_102b_c2f6_7761);
+        assert_eq!(hash(&["check--all", "-features"]), 0xb0bb_12d9_4402_9089);
+    }
+
+    #[test]
+    fn package_targets_propagate_to_dependencies() {
+        let id = |it: &str| PackageId { repr: it.to_string() };
+        // a -> b -> c, d -> c, e
+        let deps = |_target: &str, pkg: &PackageId| match pkg.repr.as_str() {
+            "a" => vec![id("b")],
+            "b" | "d" => vec![id("c")],
+            _ => Vec::new(),
+        };
+        let mut overrides = FxHashMap::default();
+        overrides.insert(id("a"), "wasm32-unknown-unknown".to_string());
+        overrides.insert(id("b"), "thumbv7em-none-eabi".to_string());
+        overrides.insert(id("d"), "x86_64-pc-windows-msvc".to_string());
+
+        let targets = propagate_package_targets(&overrides, deps);
+        let mut targets: Vec<(&str, &str)> =
+            targets.iter().map(|(id, target)| (id.repr.as_str(), target.as_str())).collect();
+        targets.sort();
+        assert_eq!(
+            targets,
+            vec![
+                ("a", "wasm32-unknown-unknown"),
+                ("b", "thumbv7em-none-eabi"),
+                ("c", "thumbv7em-none-eabi"),
+                ("d", "x86_64-pc-windows-msvc"),
+            ]
+        );
+    }
+
+    fn package(name: &str, version: &str) -> cargo_metadata::Package {
+        serde_json::from_value(json!({
+            "name": name,
+            "version": version,
+            "authors": [],
+            "id": format!("{} {} (registry+https://example.com/index)", name, version),
+            "source": null,
+            "description": null,
+            "dependencies": [],
+            "license": null,
+            "license_file": null,
+            "targets": [],
+            "features": {},
+            "manifest_path": format!("/registry/{}-{}/Cargo.toml", name, version),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn package_targets_are_keyed_by_name() {
+        let packages =
+            vec![package("foo", "0.1.0"), package("bar", "1.0.0"), package("bar", "2.0.0")];
+        let mut package_targets = FxHashMap::default();
+        package_targets.insert("foo".to_string(), "wasm32-unknown-unknown".to_string());
+        package_targets.insert("bar:2.0.0".to_string(), "thumbv7em-none-eabi".to_string());
+
+        let targets = resolve_package_targets(&packages, &package_targets).unwrap();
+        let mut targets: Vec<(&str, &str)> =
+            targets.iter().map(|(id, target)| (id.repr.as_str(), target.as_str())).collect();
+        targets.sort();
+        assert_eq!(
+            targets,
+            vec![
+                ("bar 2.0.0 (registry+https://example.com/index)", "thumbv7em-none-eabi"),
+                ("foo 0.1.0 (registry+https://example.com/index)", "wasm32-unknown-unknown"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_package_target_is_an_error() {
+        let packages = vec![package("foo", "0.1.0")];
+        let mut package_targets = FxHashMap::default();
+        package_targets.insert("baz".to_string(), "wasm32-unknown-unknown".to_string());
+
+        let err = resolve_package_targets(&packages, &package_targets).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown package `baz` in the package targets");
+    }
+
+    #[test]
+    fn ambiguous_package_target_is_an_error() {
+        let packages = vec![package("bar", "1.0.0"), package("bar", "2.0.0")];
+        let mut package_targets = FxHashMap::default();
+        package_targets.insert("bar".to_string(), "wasm32-unknown-unknown".to_string());
+
+        let err = resolve_package_targets(&packages, &package_targets).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Package `bar` in the package targets is ambiguous, use one of `bar:1.0.0`, `bar:2.0.0`"
+        );
+    }
+
+    #[test]
+    fn package_env_reports_cargo_variables() {
+        let raw_pkg = json!({
+            "name": "foo",
+            "version": "1.2.3-alpha.1",
+            "authors": ["Alice", "Bob"],
+            "id": "foo 1.2.3-alpha.1 (path+file:///ws/foo)",
+            "source": null,
+            "description": "A foo",
+            "dependencies": [],
+            "license": "MIT",
+            "license_file": null,
+            "targets": [],
+            "features": {},
+            "manifest_path": "/ws/foo/Cargo.toml",
+            "repository": "https://example.com/foo",
+            "homepage": "https://foo.example.com"
+        });
+        let pkg: cargo_metadata::Package = serde_json::from_value(raw_pkg.clone()).unwrap();
+        let homepages: FxHashMap<PackageId, String> =
+            package_homepages(&json!({ "packages": [raw_pkg] })).into_iter().collect();
+
+        let env: FxHashMap<String, String> =
+            package_env(&pkg, homepages.get(&pkg.id)).into_iter().collect();
+        let var = |name: &str| env.get(name).map(String::as_str);
+        assert!(var("CARGO").is_some());
+        assert_eq!(var("CARGO_MANIFEST_DIR"), Some("/ws/foo"));
+        assert_eq!(var("CARGO_PKG_NAME"), Some("foo"));
+        assert_eq!(var("CARGO_PKG_VERSION"), Some("1.2.3-alpha.1"));
+        assert_eq!(var("CARGO_PKG_VERSION_MAJOR"), Some("1"));
+        assert_eq!(var("CARGO_PKG_VERSION_MINOR"), Some("2"));
+        assert_eq!(var("CARGO_PKG_VERSION_PATCH"), Some("3"));
+        assert_eq!(var("CARGO_PKG_VERSION_PRE"), Some("alpha.1"));
+        assert_eq!(var("CARGO_PKG_AUTHORS"), Some("Alice:Bob"));
+        assert_eq!(var("CARGO_PKG_DESCRIPTION"), Some("A foo"));
+        assert_eq!(var("CARGO_PKG_REPOSITORY"), Some("https://example.com/foo"));
+        assert_eq!(var("CARGO_PKG_LICENSE"), Some("MIT"));
+        assert_eq!(var("CARGO_PKG_LICENSE_FILE"), Some(""));
+        assert_eq!(var("CARGO_PKG_HOMEPAGE"), Some("https://foo.example.com"));
+    }
+}
@@ -23,6 +23,10 @@ pub struct Crate {
     pub(crate) atom_cfgs: FxHashSet<String>,
     pub(crate) key_value_cfgs: FxHashMap<String, String>,
     pub(crate) out_dir: Option<PathBuf>,
+    /// Environment variables passed to rustc when compiling this crate, e.g.
+    /// `CARGO_PKG_VERSION`.
+    #[serde(default)]
+    pub(crate) env: FxHashMap<String, String>,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize)]
@@ -14,10 +14,12 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 use ra_cfg::CfgOptions;
-use ra_db::{CrateGraph, CrateName, Edition, Env, ExternSource, ExternSourceId, FileId};
+use ra_db::{CrateGraph, CrateId, CrateName, Edition, Env, ExternSource, ExternSourceId, FileId};
 use rustc_hash::FxHashMap;
 use serde_json::from_reader;
 
+use crate::sysroot::SysrootCrate;
+
 pub use crate::{
     cargo_workspace::{CargoFeatures, CargoWorkspace, Package, Target, TargetKind},
     json_project::JsonProject,
@@ -49,6 +51,38 @@ pub enum ProjectWorkspace {
     Cargo { cargo: CargoWorkspace, sysroot: Sysroot },
     /// Project workspace was manually specified using a `rust-project.json` file.
     Json { project: JsonProject },
+    /// Standalone `.rs` files which don't belong to any project. Each file is
+    /// a crate of its own, depending only on the sysroot.
+    DetachedFiles { files: Vec<PathBuf>, sysroot: Sysroot },
+}
+
+/// A manifest which describes a project: either a `Cargo.toml` or a
+/// `rust-project.json` file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProjectManifest {
+    ProjectJson(PathBuf),
+    CargoToml(PathBuf),
+}
+
+impl ProjectManifest {
+    pub fn from_manifest_file(path: PathBuf) -> Result<ProjectManifest> {
+        if path.ends_with("rust-project.json") {
+            return Ok(ProjectManifest::ProjectJson(path));
+        }
+        if path.ends_with("Cargo.toml") {
+            return Ok(ProjectManifest::CargoToml(path));
+        }
+        bail!("project root must point to Cargo.toml or rust-project.json: {}", path.display())
+    }
+
+    pub fn discover(path: &Path) -> Result<ProjectManifest> {
+        if let Some(json_path) = find_rust_project_json(path) {
+            return Ok(ProjectManifest::ProjectJson(json_path));
+        }
+        let cargo_toml = find_cargo_toml(path)
+            .with_context(|| format!("Failed to find Cargo.toml for path {}", path.display()))?;
+        Ok(ProjectManifest::CargoToml(cargo_toml))
+    }
 }
 
 /// `PackageRoot` describes a package root folder.
@@ -60,11 +94,19 @@ pub struct PackageRoot {
     path: PathBuf,
     /// Is a member of the current workspace
     is_member: bool,
+    /// The only files of the folder which belong to the root, if they are
+    /// listed one by one, like detached files
+    files: Option<Vec<PathBuf>>,
 }
 
 impl PackageRoot {
     pub fn new(path: PathBuf, is_member: bool) -> PackageRoot {
-        PackageRoot { path, is_member }
+        PackageRoot { path, is_member, files: None }
+    }
+
+    /// A member root containing only the given `files` of the `path` folder.
+    pub fn with_files(path: PathBuf, files: Vec<PathBuf>) -> PackageRoot {
+        PackageRoot { path, is_member: true, files: Some(files) }
     }
 
     pub fn path(&self) -> &PathBuf {
@@ -74,6 +116,10 @@ impl PackageRoot {
     pub fn is_member(&self) -> bool {
         self.is_member
     }
+
+    pub fn files(&self) -> Option<&[PathBuf]> {
+        self.files.as_deref()
+    }
 }
 
 impl ProjectWorkspace {
@@ -86,8 +132,17 @@ impl ProjectWorkspace {
         with_sysroot: bool,
         cargo_features: &CargoFeatures,
     ) -> Result<ProjectWorkspace> {
-        match find_rust_project_json(path) {
-            Some(json_path) => {
+        let manifest = ProjectManifest::discover(path)?;
+        ProjectWorkspace::load(manifest, with_sysroot, cargo_features)
+    }
+
+    pub fn load(
+        manifest: ProjectManifest,
+        with_sysroot: bool,
+        cargo_features: &CargoFeatures,
+    ) -> Result<ProjectWorkspace> {
+        match manifest {
+            ProjectManifest::ProjectJson(json_path) => {
                 let file = File::open(&json_path)
                     .with_context(|| format!("Failed to open json file {}", json_path.display()))?;
                 let reader = BufReader::new(file);
@@ -97,10 +152,7 @@ impl ProjectWorkspace {
                     })?,
                 })
             }
-            None => {
-                let cargo_toml = find_cargo_toml(path).with_context(|| {
-                    format!("Failed to find Cargo.toml for path {}", path.display())
-                })?;
+            ProjectManifest::CargoToml(cargo_toml) => {
                 let cargo = CargoWorkspace::from_cargo_metadata(&cargo_toml, cargo_features)
                     .with_context(|| {
                         format!(
@@ -123,6 +175,22 @@ impl ProjectWorkspace {
         }
     }
 
+    pub fn load_detached_files(
+        files: Vec<PathBuf>,
+        with_sysroot: bool,
+    ) -> Result<ProjectWorkspace> {
+        let sysroot = match files.first() {
+            Some(file) if with_sysroot => Sysroot::discover(file).with_context(|| {
+                format!(
+                    "Failed to find sysroot for detached file {}. Is rust-src installed?",
+                    file.display()
+                )
+            })?,
+            _ => Sysroot::default(),
+        };
+        Ok(ProjectWorkspace::DetachedFiles { files, sysroot })
+    }
+
     /// Returns the roots for the current `ProjectWorkspace`
     /// The return type contains the path and whether or not
     /// the root is a member of the current workspace
@@ -147,6 +215,28 @@ impl ProjectWorkspace {
                 }
                 roots
             }
+            ProjectWorkspace::DetachedFiles { files, sysroot } => {
+                let mut roots = Vec::with_capacity(files.len() + sysroot.crates().len());
+                // Only the files themselves are loaded, not their neighbours
+                let mut files_by_dir: Vec<(&Path, Vec<PathBuf>)> = Vec::new();
+                for file in files {
+                    let dir = match file.parent() {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    match files_by_dir.iter_mut().find(|(it, _)| *it == dir) {
+                        Some((_, dir_files)) => dir_files.push(file.clone()),
+                        None => files_by_dir.push((dir, vec![file.clone()])),
+                    }
+                }
+                for (dir, dir_files) in files_by_dir {
+                    roots.push(PackageRoot::with_files(dir.to_path_buf(), dir_files));
+                }
+                for krate in sysroot.crates() {
+                    roots.push(PackageRoot::new(krate.root_dir(&sysroot).to_path_buf(), false))
+                }
+                roots
+            }
         }
     }
 
@@ -170,6 +260,7 @@ impl ProjectWorkspace {
                 }
                 out_dirs
             }
+            ProjectWorkspace::DetachedFiles { .. } => Vec::new(),
         }
     }
 
@@ -179,6 +270,9 @@ impl ProjectWorkspace {
             ProjectWorkspace::Cargo { cargo, sysroot } => {
                 cargo.packages().len() + sysroot.crates().len()
             }
+            ProjectWorkspace::DetachedFiles { files, sysroot } => {
+                files.len() + sysroot.crates().len()
+            }
         }
     }
 
@@ -211,6 +305,9 @@ impl ProjectWorkspace {
                         };
 
                         let mut env = Env::default();
+                        for (key, value) in &krate.env {
+                            env.set(key, value.clone());
+                        }
                         let mut extern_source = ExternSource::default();
                         if let Some(out_dir) = &krate.out_dir {
                             // FIXME: We probably mangle non UTF-8 paths here, figure out a better solution
@@ -258,45 +355,10 @@ impl ProjectWorkspace {
                 }
             }
             ProjectWorkspace::Cargo { cargo, sysroot } => {
-                let mut sysroot_crates = FxHashMap::default();
-                for krate in sysroot.crates() {
-                    if let Some(file_id) = load(krate.root(&sysroot)) {
-                        // Crates from sysroot have `cfg(test)` disabled
-                        let cfg_options = {
-                            let mut opts = default_cfg_options.clone();
-                            opts.remove_atom("test");
-                            opts
-                        };
-
-                        let env = Env::default();
-                        let extern_source = ExternSource::default();
-                        let crate_id = crate_graph.add_crate_root(
-                            file_id,
-                            Edition::Edition2018,
-                            Some(
-                                CrateName::new(krate.name(&sysroot))
-                                    .expect("Sysroot crate names should not contain dashes"),
-                            ),
-                            cfg_options,
-                            env,
-                            extern_source,
-                        );
-                        sysroot_crates.insert(krate, crate_id);
-                    }
-                }
-                for from in sysroot.crates() {
-                    for to in from.deps(&sysroot) {
-                        let name = to.name(&sysroot);
-                        if let (Some(&from), Some(&to)) =
-                            (sysroot_crates.get(&from), sysroot_crates.get(&to))
-                        {
-                            if crate_graph.add_dep(from, CrateName::new(name).unwrap(), to).is_err()
-                            {
-                                log::error!("cyclic dependency between sysroot crates")
-                            }
-                        }
-                    }
-                }
+                let sysroot_cfg_options =
+                    cfg_options_for_target(&cargo, cargo.target(), default_cfg_options);
+                let sysroot_crates =
+                    sysroot_to_crate_graph(&mut crate_graph, sysroot, &sysroot_cfg_options, load);
 
                 let libcore = sysroot.core().and_then(|it| sysroot_crates.get(&it).copied());
                 let liballoc = sysroot.alloc().and_then(|it| sysroot_crates.get(&it).copied());
@@ -314,11 +376,26 @@ impl ProjectWorkspace {
                         if let Some(file_id) = load(root) {
                             let edition = pkg.edition(&cargo);
                             let cfg_options = {
-                                let mut opts = default_cfg_options.clone();
+                                let mut opts = cfg_options_for_target(
+                                    &cargo,
+                                    pkg.target(&cargo),
+                                    default_cfg_options,
+                                );
                                 opts.insert_features(pkg.features(&cargo).iter().map(Into::into));
+                                for cfg in pkg.cfgs(&cargo) {
+                                    insert_cfg(&mut opts, cfg);
+                                }
                                 opts
                             };
                             let mut env = Env::default();
+                            for (key, value) in pkg.env(&cargo) {
+                                env.set(key, value.clone());
+                            }
+                            let crate_name = tgt.name(&cargo).replace('-', "_");
+                            env.set("CARGO_CRATE_NAME", crate_name);
+                            if tgt.kind(&cargo) == TargetKind::Bin {
+                                env.set("CARGO_BIN_NAME", tgt.name(&cargo).to_string());
+                            }
                             let mut extern_source = ExternSource::default();
                             if let Some(out_dir) = pkg.out_dir(cargo) {
                                 // FIXME: We probably mangle non UTF-8 paths here, figure out a better solution
@@ -434,6 +511,40 @@ impl ProjectWorkspace {
                     }
                 }
             }
+            ProjectWorkspace::DetachedFiles { files, sysroot } => {
+                let sysroot_crates =
+                    sysroot_to_crate_graph(&mut crate_graph, sysroot, default_cfg_options, load);
+                let sysroot_deps =
+                    [("core", sysroot.core()), ("alloc", sysroot.alloc()), ("std", sysroot.std())];
+
+                for file in files {
+                    let file_id = match load(file) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    let display_name = file
+                        .file_stem()
+                        .map(|it| CrateName::normalize_dashes(&it.to_string_lossy()));
+                    let crate_id = crate_graph.add_crate_root(
+                        file_id,
+                        Edition::Edition2018,
+                        display_name,
+                        default_cfg_options.clone(),
+                        Env::default(),
+                        ExternSource::default(),
+                    );
+                    for &(name, krate) in sysroot_deps.iter() {
+                        if let Some(&to) = krate.and_then(|it| sysroot_crates.get(&it)) {
+                            if crate_graph
+                                .add_dep(crate_id, CrateName::new(name).unwrap(), to)
+                                .is_err()
+                            {
+                                log::error!("cyclic dependency on {} for {}", name, file.display())
+                            }
+                        }
+                    }
+                }
+            }
         }
         crate_graph
     }
@@ -447,10 +558,58 @@ impl ProjectWorkspace {
                 .iter()
                 .find(|root| path.starts_with(&root.path))
                 .map(|root| root.path.as_ref()),
+            ProjectWorkspace::DetachedFiles { files, .. } => {
+                files.iter().find(|file| *file == path).and_then(|file| file.parent())
+            }
         }
     }
 }
 
+fn sysroot_to_crate_graph(
+    crate_graph: &mut CrateGraph,
+    sysroot: &Sysroot,
+    default_cfg_options: &CfgOptions,
+    load: &mut dyn FnMut(&Path) -> Option<FileId>,
+) -> FxHashMap<SysrootCrate, CrateId> {
+    let mut sysroot_crates = FxHashMap::default();
+    for krate in sysroot.crates() {
+        if let Some(file_id) = load(krate.root(&sysroot)) {
+            // Crates from sysroot have `cfg(test)` disabled
+            let cfg_options = {
+                let mut opts = default_cfg_options.clone();
+                opts.remove_atom("test");
+                opts
+            };
+
+            let env = Env::default();
+            let extern_source = ExternSource::default();
+            let crate_id = crate_graph.add_crate_root(
+                file_id,
+                Edition::Edition2018,
+                Some(
+                    CrateName::new(krate.name(&sysroot))
+                        .expect("Sysroot crate names should not contain dashes"),
+                ),
+                cfg_options,
+                env,
+                extern_source,
+            );
+            sysroot_crates.insert(krate, crate_id);
+        }
+    }
+    for from in sysroot.crates() {
+        for to in from.deps(&sysroot) {
+            let name = to.name(&sysroot);
+            if let (Some(&from), Some(&to)) = (sysroot_crates.get(&from), sysroot_crates.get(&to)) {
+                if crate_graph.add_dep(from, CrateName::new(name).unwrap(), to).is_err() {
+                    log::error!("cyclic dependency between sysroot crates")
+                }
+            }
+        }
+    }
+    sysroot_crates
+}
+
 fn find_rust_project_json(path: &Path) -> Option<PathBuf> {
     if path.ends_with("rust-project.json") {
         return Some(path.to_path_buf());
@@ -532,50 +691,231 @@ fn find_cargo_toml(path: &Path) -> Result<PathBuf> {
     }
 }
 
-pub fn get_rustc_cfg_options() -> CfgOptions {
-    let mut cfg_options = CfgOptions::default();
-
-    // Some nightly-only cfgs, which are required for stdlib
-    {
-        cfg_options.insert_atom("target_thread_local".into());
-        for &target_has_atomic in ["8", "16", "32", "64", "cas", "ptr"].iter() {
-            cfg_options.insert_key_value("target_has_atomic".into(), target_has_atomic.into());
-            cfg_options
-                .insert_key_value("target_has_atomic_load_store".into(), target_has_atomic.into());
+/// Cfg options for crates compiled for `target`.
+///
+/// Falls back to `default_cfg_options` (the host cfgs) when no target is set or
+/// rustc didn't report cfgs for it.
+fn cfg_options_for_target(
+    cargo: &CargoWorkspace,
+    target: Option<&str>,
+    default_cfg_options: &CfgOptions,
+) -> CfgOptions {
+    match target.and_then(|it| cargo.target_cfg_options(it)) {
+        Some(target_cfgs) => {
+            let mut opts = target_cfgs.clone();
+            // `cfg(test)` and `cfg(debug_assertion)` are not reported by rustc.
+            opts.insert_atom("test".into());
+            opts.insert_atom("debug_assertion".into());
+            opts
         }
+        None => default_cfg_options.clone(),
     }
+}
 
-    match (|| -> Result<String> {
-        // `cfg(test)` and `cfg(debug_assertion)` are handled outside, so we suppress them here.
-        let output = Command::new("rustc")
-            .args(&["--print", "cfg", "-O"])
-            .output()
-            .context("Failed to get output from rustc --print cfg -O")?;
-        if !output.status.success() {
-            bail!(
-                "rustc --print cfg -O exited with exit code ({})",
-                output
-                    .status
-                    .code()
-                    .map_or(String::from("no exit code"), |code| format!("{}", code))
-            );
-        }
-        Ok(String::from_utf8(output.stdout)?)
-    })() {
+pub fn get_rustc_cfg_options(target: Option<&str>) -> CfgOptions {
+    let mut cfg_options = nightly_cfg_options();
+    match rustc_cfgs(target) {
         Ok(rustc_cfgs) => {
             for line in rustc_cfgs.lines() {
-                match line.find('=') {
-                    None => cfg_options.insert_atom(line.into()),
-                    Some(pos) => {
-                        let key = &line[..pos];
-                        let value = line[pos + 1..].trim_matches('"');
-                        cfg_options.insert_key_value(key.into(), value.into());
-                    }
-                }
+                insert_cfg(&mut cfg_options, line);
             }
         }
         Err(e) => log::error!("failed to get rustc cfgs: {}", e),
     }
+    cfg_options
+}
 
+/// Like `get_rustc_cfg_options`, but fails if rustc can't report the cfgs,
+/// instead of leaving out the ones of the target.
+pub(crate) fn try_get_rustc_cfg_options(target: Option<&str>) -> Result<CfgOptions> {
+    let mut cfg_options = nightly_cfg_options();
+    for line in rustc_cfgs(target)?.lines() {
+        insert_cfg(&mut cfg_options, line);
+    }
+    Ok(cfg_options)
+}
+
+/// Some nightly-only cfgs, which are required for stdlib
+fn nightly_cfg_options() -> CfgOptions {
+    let mut cfg_options = CfgOptions::default();
+    cfg_options.insert_atom("target_thread_local".into());
+    for &target_has_atomic in ["8", "16", "32", "64", "cas", "ptr"].iter() {
+        cfg_options.insert_key_value("target_has_atomic".into(), target_has_atomic.into());
+        cfg_options
+            .insert_key_value("target_has_atomic_load_store".into(), target_has_atomic.into());
+    }
     cfg_options
 }
+
+fn rustc_cfgs(target: Option<&str>) -> Result<String> {
+    // `cfg(test)` and `cfg(debug_assertion)` are handled outside, so we suppress them here.
+    let mut cmd = Command::new("rustc");
+    cmd.args(&["--print", "cfg", "-O"]);
+    if let Some(target) = target {
+        cmd.args(&["--target", target]);
+    }
+    let output = cmd.output().context("Failed to get output from rustc --print cfg -O")?;
+    if !output.status.success() {
+        bail!(
+            "rustc --print cfg -O exited with exit code ({})",
+            output.status.code().map_or(String::from("no exit code"), |code| format!("{}", code))
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Parses a cfg flag in the `rustc --cfg` syntax, like `unix` or `feature="foo"`.
+fn insert_cfg(cfg_options: &mut CfgOptions, cfg: &str) {
+    match cfg.find('=') {
+        None => cfg_options.insert_atom(cfg.into()),
+        Some(pos) => {
+            let key = &cfg[..pos];
+            let value = cfg[pos + 1..].trim_matches('"');
+            cfg_options.insert_key_value(key.into(), value.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_from_manifest_file() {
+        assert_eq!(
+            ProjectManifest::from_manifest_file(PathBuf::from("/ws/Cargo.toml")).unwrap(),
+            ProjectManifest::CargoToml(PathBuf::from("/ws/Cargo.toml"))
+        );
+        assert_eq!(
+            ProjectManifest::from_manifest_file(PathBuf::from("/ws/rust-project.json")).unwrap(),
+            ProjectManifest::ProjectJson(PathBuf::from("/ws/rust-project.json"))
+        );
+        assert!(ProjectManifest::from_manifest_file(PathBuf::from("/ws/src/main.rs")).is_err());
+    }
+
+    #[test]
+    fn detached_files_are_loaded_alone() {
+        let files = vec![
+            PathBuf::from("/ws/scripts/a.rs"),
+            PathBuf::from("/ws/scripts/b-c.rs"),
+            PathBuf::from("/tmp/d.rs"),
+        ];
+        let workspace = ProjectWorkspace::load_detached_files(files.clone(), false).unwrap();
+
+        let roots = workspace.to_roots();
+        let roots: Vec<(&Path, Option<&[PathBuf]>)> =
+            roots.iter().map(|it| (it.path().as_path(), it.files())).collect();
+        assert_eq!(
+            roots,
+            vec![
+                (Path::new("/ws/scripts"), Some(&files[..2])),
+                (Path::new("/tmp"), Some(&files[2..])),
+            ]
+        );
+
+        let mut load =
+            |path: &Path| files.iter().position(|it| it == path).map(|idx| FileId(idx as u32));
+        let crate_graph =
+            workspace.to_crate_graph(&CfgOptions::default(), &FxHashMap::default(), &mut load);
+        let mut crates: Vec<(FileId, String)> = crate_graph
+            .iter()
+            .map(|it| {
+                let data = &crate_graph[it];
+                assert!(data.dependencies.is_empty());
+                (data.root_file_id, format!("{:?}", data.display_name))
+            })
+            .collect();
+        crates.sort();
+        assert_eq!(
+            crates,
+            vec![
+                (FileId(0), r#"Some(CrateName("a"))"#.to_string()),
+                (FileId(1), r#"Some(CrateName("b_c"))"#.to_string()),
+                (FileId(2), r#"Some(CrateName("d"))"#.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn detached_files_depend_on_the_sysroot() {
+        let src = std::env::temp_dir().join("ra_project_model_detached_sysroot");
+        for name in &["core", "alloc", "std"] {
+            let dir = src.join(format!("lib{}", name));
+            std::fs::create_dir_all(&dir).unwrap();
+            File::create(dir.join("lib.rs")).unwrap();
+        }
+        let sysroot = Sysroot::load(&src);
+        let files = vec![PathBuf::from("/ws/scripts/a.rs")];
+        let workspace = ProjectWorkspace::DetachedFiles { files: files.clone(), sysroot };
+
+        let roots: Vec<PathBuf> =
+            workspace.to_roots().iter().map(|it| it.path().to_path_buf()).collect();
+        assert_eq!(
+            roots,
+            vec![
+                PathBuf::from("/ws/scripts"),
+                src.join("libstd"),
+                src.join("libcore"),
+                src.join("liballoc"),
+            ]
+        );
+
+        let mut paths = files;
+        let mut load = |path: &Path| {
+            let idx = paths.iter().position(|it| it == path).unwrap_or_else(|| {
+                paths.push(path.to_path_buf());
+                paths.len() - 1
+            });
+            Some(FileId(idx as u32))
+        };
+        let crate_graph =
+            workspace.to_crate_graph(&CfgOptions::default(), &FxHashMap::default(), &mut load);
+        let script = crate_graph.iter().find(|&it| crate_graph[it].root_file_id == FileId(0));
+        let deps: Vec<(&str, Option<CrateName>)> = crate_graph[script.unwrap()]
+            .dependencies
+            .iter()
+            .map(|dep| (dep.name.as_str(), crate_graph[dep.crate_id].display_name.clone()))
+            .collect();
+        assert_eq!(
+            deps,
+            vec![
+                ("core", CrateName::new("core").ok()),
+                ("alloc", CrateName::new("alloc").ok()),
+                ("std", CrateName::new("std").ok()),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_project_env_is_passed_to_crates() {
+        let project: JsonProject = serde_json::from_value(serde_json::json!({
+            "roots": ["/ws"],
+            "crates": [{
+                "root_module": "/ws/src/lib.rs",
+                "edition": "2018",
+                "deps": [],
+                "atom_cfgs": [],
+                "key_value_cfgs": {},
+                "out_dir": "/ws/target/out",
+                "env": { "CARGO_PKG_VERSION": "0.1.0" }
+            }]
+        }))
+        .unwrap();
+        let workspace = ProjectWorkspace::Json { project };
+
+        let mut load = |path: &Path| {
+            if path == Path::new("/ws/src/lib.rs") {
+                Some(FileId(0))
+            } else {
+                None
+            }
+        };
+        let crate_graph =
+            workspace.to_crate_graph(&CfgOptions::default(), &FxHashMap::default(), &mut load);
+        let krate = crate_graph.iter().next().unwrap();
+        let env = &crate_graph[krate].env;
+        assert_eq!(env.get("CARGO_PKG_VERSION"), Some("0.1.0".to_string()));
+        assert_eq!(env.get("OUT_DIR"), Some("/ws/target/out".to_string()));
+        assert_eq!(env.get("CARGO_PKG_NAME"), None);
+    }
+}
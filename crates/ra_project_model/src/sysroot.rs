@@ -48,6 +48,12 @@ impl Sysroot {
 
     pub fn discover(cargo_toml: &Path) -> Result<Sysroot> {
         let src = get_or_install_rust_src(cargo_toml)?;
+        Ok(Sysroot::load(&src))
+    }
+
+    /// Loads the sysroot crates whose sources are found in `src`, laid out
+    /// like the `rust-src` component.
+    pub(crate) fn load(src: &Path) -> Sysroot {
         let mut sysroot = Sysroot { crates: Arena::default() };
         for name in SYSROOT_CRATES.trim().lines() {
             let root = src.join(format!("lib{}", name)).join("lib.rs");
@@ -71,7 +77,7 @@ impl Sysroot {
                 sysroot.crates[alloc].deps.push(core);
             }
         }
-        Ok(sysroot)
+        sysroot
     }
 
     fn by_name(&self, name: &str) -> Option<SysrootCrate> {
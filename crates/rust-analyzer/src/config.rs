@@ -1,16 +1,22 @@
 //! Config used by the language server.
 //!
-//! We currently get this config from `initialize` LSP request, which is not the
-//! best way to do it, but was the simplest thing we could implement.
+//! We get this config from `initialize` LSP request, and re-fetch it via
+//! `workspace/configuration` whenever the client reports a change. Clients
+//! which don't support `workspace/configuration` push their settings with the
+//! change notification instead, see `ServerConfig::with_client_settings`.
 //!
 //! Of particular interest is the `feature_flags` hash map: while other fields
 //! configure the server itself, feature flags are passed into analysis, and
 //! tweak things like automatic insertion of `()` in completions.
 
+use std::path::{Path, PathBuf};
+
 use rustc_hash::FxHashMap;
 
+use ra_ide::{Snippet, SnippetScope};
 use ra_project_model::CargoFeatures;
-use serde::{Deserialize, Deserializer};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use serde_json::Value;
 
 /// Client provided initialization options
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -34,8 +40,28 @@ pub struct ServerConfig {
     pub inlay_hints_type: bool,
     #[serde(deserialize_with = "nullable_bool_true")]
     pub inlay_hints_parameter: bool,
+    #[serde(deserialize_with = "nullable_bool_true")]
+    pub inlay_hints_chaining: bool,
+    #[serde(deserialize_with = "nullable_bool_false")]
+    pub inlay_hints_closure_return_type: bool,
+    #[serde(deserialize_with = "nullable_bool_false")]
+    pub inlay_hints_lifetime_elision: bool,
     pub inlay_hints_max_length: Option<usize>,
 
+    #[serde(deserialize_with = "nullable_bool_true")]
+    pub lens_run: bool,
+    #[serde(deserialize_with = "nullable_bool_true")]
+    pub lens_debug: bool,
+    #[serde(deserialize_with = "nullable_bool_true")]
+    pub lens_implementations: bool,
+    #[serde(deserialize_with = "nullable_bool_false")]
+    pub lens_references: bool,
+    #[serde(deserialize_with = "nullable_bool_false")]
+    pub lens_method_references: bool,
+
+    #[serde(deserialize_with = "nullable_bool_false")]
+    pub rename_allow_conflicts: bool,
+
     pub cargo_watch_enable: bool,
     pub cargo_watch_args: Vec<String>,
     pub cargo_watch_command: String,
@@ -53,10 +79,68 @@ pub struct ServerConfig {
     /// Cargo feature configurations.
     pub cargo_features: CargoFeatures,
 
+    /// Explicit list of `Cargo.toml` or `rust-project.json` files to load
+    /// instead of discovering a single project per workspace folder. Relative
+    /// paths are relative to the workspace root the setting is configured for,
+    /// as are those of `detached_files`, see `ServerConfig::merge_scoped`.
+    pub linked_projects: Vec<PathBuf>,
+    /// Standalone `.rs` files which get analyzed as crates of their own.
+    pub detached_files: Vec<PathBuf>,
+
+    /// User-defined postfix and snippet completions.
+    pub snippets: Vec<SnippetConfig>,
+
     /// Enabled if the vscode_lldb extension is available.
     pub vscode_lldb: bool,
 }
 
+/// A user-defined completion snippet, see `ra_ide::Snippet`.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetConfig {
+    pub label: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub body: String,
+    #[serde(default)]
+    pub postfix: bool,
+    #[serde(default)]
+    pub scope: SnippetScopeConfig,
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SnippetScopeConfig {
+    Expr,
+    Item,
+    Type,
+}
+
+impl Default for SnippetScopeConfig {
+    fn default() -> SnippetScopeConfig {
+        SnippetScopeConfig::Expr
+    }
+}
+
+impl SnippetConfig {
+    pub fn to_snippet(&self) -> Snippet {
+        Snippet {
+            label: self.label.clone(),
+            description: self.description.clone(),
+            body: self.body.clone(),
+            postfix: self.postfix,
+            scope: match self.scope {
+                SnippetScopeConfig::Expr => SnippetScope::Expr,
+                SnippetScopeConfig::Item => SnippetScope::Item,
+                SnippetScopeConfig::Type => SnippetScope::Type,
+            },
+            requires: self.requires.clone(),
+        }
+    }
+}
+
 impl Default for ServerConfig {
     fn default() -> ServerConfig {
         ServerConfig {
@@ -66,7 +150,16 @@ impl Default for ServerConfig {
             lru_capacity: None,
             inlay_hints_type: true,
             inlay_hints_parameter: true,
+            inlay_hints_chaining: true,
+            inlay_hints_closure_return_type: false,
+            inlay_hints_lifetime_elision: false,
             inlay_hints_max_length: None,
+            lens_run: true,
+            lens_debug: true,
+            lens_implementations: true,
+            lens_references: false,
+            lens_method_references: false,
+            rename_allow_conflicts: false,
             cargo_watch_enable: true,
             cargo_watch_args: Vec::new(),
             cargo_watch_command: "check".to_string(),
@@ -74,12 +167,137 @@ impl Default for ServerConfig {
             with_sysroot: true,
             feature_flags: FxHashMap::default(),
             cargo_features: Default::default(),
+            linked_projects: Vec::new(),
+            detached_files: Vec::new(),
             rustfmt_args: Vec::new(),
+            snippets: Vec::new(),
             vscode_lldb: false,
         }
     }
 }
 
+impl ServerConfig {
+    /// Converts the settings pushed by a client without `workspace/configuration`
+    /// support, which follow the layout of the `rust-analyzer.*` settings of the
+    /// VS Code extension, like `{ "inlayHints": { "typeHints": false } }`. This
+    /// is the conversion `serverConfig` in `editors/code/src/client.ts` does.
+    ///
+    /// Missing settings take their default. The fields which describe the client
+    /// rather than the user's settings are kept from `self`.
+    pub fn with_client_settings(&self, settings: &Value) -> ServerConfig {
+        let default = ServerConfig::default();
+        ServerConfig {
+            publish_decorations: self.publish_decorations,
+            exclude_globs: setting(settings, "excludeGlobs").unwrap_or(default.exclude_globs),
+            use_client_watching: setting(settings, "useClientWatching")
+                .unwrap_or(default.use_client_watching),
+            lru_capacity: setting(settings, "lruCapacity").unwrap_or(default.lru_capacity),
+            inlay_hints_type: setting(settings, "inlayHints.typeHints")
+                .unwrap_or(default.inlay_hints_type),
+            inlay_hints_parameter: setting(settings, "inlayHints.parameterHints")
+                .unwrap_or(default.inlay_hints_parameter),
+            inlay_hints_chaining: setting(settings, "inlayHints.chainingHints")
+                .unwrap_or(default.inlay_hints_chaining),
+            inlay_hints_closure_return_type: setting(settings, "inlayHints.closureReturnTypeHints")
+                .unwrap_or(default.inlay_hints_closure_return_type),
+            inlay_hints_lifetime_elision: setting(settings, "inlayHints.lifetimeElisionHints")
+                .unwrap_or(default.inlay_hints_lifetime_elision),
+            inlay_hints_max_length: setting(settings, "inlayHints.maxLength")
+                .unwrap_or(default.inlay_hints_max_length),
+            lens_run: setting(settings, "lens.run").unwrap_or(default.lens_run),
+            lens_debug: setting(settings, "lens.debug").unwrap_or(default.lens_debug),
+            lens_implementations: setting(settings, "lens.implementations")
+                .unwrap_or(default.lens_implementations),
+            lens_references: setting(settings, "lens.references")
+                .unwrap_or(default.lens_references),
+            lens_method_references: setting(settings, "lens.methodReferences")
+                .unwrap_or(default.lens_method_references),
+            rename_allow_conflicts: setting(settings, "rename.allowConflicts")
+                .unwrap_or(default.rename_allow_conflicts),
+            cargo_watch_enable: setting(settings, "cargo-watch.enable")
+                .unwrap_or(default.cargo_watch_enable),
+            cargo_watch_args: setting(settings, "cargo-watch.arguments")
+                .unwrap_or(default.cargo_watch_args),
+            cargo_watch_command: setting(settings, "cargo-watch.command")
+                .unwrap_or(default.cargo_watch_command),
+            cargo_watch_all_targets: setting(settings, "cargo-watch.allTargets")
+                .unwrap_or(default.cargo_watch_all_targets),
+            with_sysroot: self.with_sysroot,
+            feature_flags: setting(settings, "featureFlags").unwrap_or(default.feature_flags),
+            rustfmt_args: setting(settings, "rustfmtArgs").unwrap_or(default.rustfmt_args),
+            cargo_features: setting(settings, "cargoFeatures").unwrap_or(default.cargo_features),
+            linked_projects: setting(settings, "linkedProjects").unwrap_or(default.linked_projects),
+            detached_files: setting(settings, "detachedFiles").unwrap_or(default.detached_files),
+            snippets: setting(settings, "completion.snippets").unwrap_or(default.snippets),
+            vscode_lldb: self.vscode_lldb,
+        }
+    }
+
+    /// Combines the settings fetched for each workspace root, scoped to it.
+    /// The relative `linked_projects` and `detached_files` of each root are
+    /// resolved against it and loaded together, everything else comes from
+    /// the first root with settings.
+    pub fn merge_scoped<'a>(
+        scoped: impl IntoIterator<Item = (&'a PathBuf, Option<ServerConfig>)>,
+    ) -> Option<ServerConfig> {
+        let mut merged: Option<ServerConfig> = None;
+        for (root, config) in scoped {
+            let mut config = match config {
+                Some(it) => it,
+                None => continue,
+            };
+            config.resolve_paths(|path| Some(root.join(path)));
+            if let Some(merged) = &mut merged {
+                extend_unique(&mut merged.linked_projects, config.linked_projects);
+                extend_unique(&mut merged.detached_files, config.detached_files);
+                continue;
+            }
+            merged = Some(config);
+        }
+        merged
+    }
+
+    /// Resolves the relative paths of settings which aren't scoped to a
+    /// workspace root, like the initialization options. With several roots,
+    /// each path is taken to be configured for the first root it exists in.
+    pub fn resolve_unscoped_paths(&mut self, ws_roots: &[PathBuf]) {
+        self.resolve_paths(|path| match ws_roots {
+            [root] => Some(root.join(path)),
+            _ => ws_roots.iter().map(|root| root.join(path)).find(|it| it.exists()),
+        })
+    }
+
+    fn resolve_paths(&mut self, resolve: impl Fn(&Path) -> Option<PathBuf>) {
+        for path in self.linked_projects.iter_mut().chain(self.detached_files.iter_mut()) {
+            if path.is_relative() {
+                if let Some(absolute) = resolve(path) {
+                    *path = absolute;
+                }
+            }
+        }
+    }
+}
+
+fn extend_unique(paths: &mut Vec<PathBuf>, new_paths: Vec<PathBuf>) {
+    for path in new_paths {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+}
+
+/// Looks up a setting by its dotted VS Code name, relative to `rust-analyzer`.
+fn setting<T: DeserializeOwned>(settings: &Value, name: &str) -> Option<T> {
+    let value = name.split('.').try_fold(settings, |value, key| value.get(key))?;
+    match serde_json::from_value(value.clone()) {
+        Ok(it) => Some(it),
+        Err(e) => {
+            log::error!("invalid value for rust-analyzer.{}: {}", name, e);
+            None
+        }
+    }
+}
+
 /// Deserializes a null value to a bool false by default
 fn nullable_bool_false<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
@@ -112,4 +330,90 @@ mod test {
             serde_json::from_str(r#"{"publishDecorations":null, "lruCapacity":null}"#).unwrap()
         );
     }
+
+    #[test]
+    fn convert_client_settings() {
+        let settings = serde_json::json!({
+            "inlayHints": { "typeHints": false, "maxLength": 20 },
+            "cargo-watch": { "command": "clippy" },
+            "cargoFeatures": { "allFeatures": false, "features": ["foo"] },
+            "lruCapacity": null,
+            "rename": { "allowConflicts": "yes" },
+        });
+        let current = ServerConfig { with_sysroot: false, ..ServerConfig::default() };
+        let config = current.with_client_settings(&settings);
+        assert_eq!(
+            config,
+            ServerConfig {
+                with_sysroot: false,
+                inlay_hints_type: false,
+                inlay_hints_max_length: Some(20),
+                cargo_watch_command: "clippy".to_string(),
+                cargo_features: CargoFeatures {
+                    all_features: false,
+                    features: vec!["foo".to_string()],
+                    ..CargoFeatures::default()
+                },
+                ..ServerConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_snippets() {
+        let config: ServerConfig = serde_json::from_str(
+            r#"{"snippets": [
+                {"label": "arc", "body": "Arc::new(${receiver})", "postfix": true, "requires": ["std::sync::Arc"]},
+                {"label": "tmod", "body": "mod tests {\n    $0\n}", "scope": "item"}
+            ]}"#,
+        )
+        .unwrap();
+        let snippets: Vec<_> = config.snippets.iter().map(SnippetConfig::to_snippet).collect();
+        assert_eq!(
+            snippets,
+            vec![
+                Snippet {
+                    label: "arc".to_string(),
+                    description: None,
+                    body: "Arc::new(${receiver})".to_string(),
+                    postfix: true,
+                    scope: SnippetScope::Expr,
+                    requires: vec!["std::sync::Arc".to_string()],
+                },
+                Snippet {
+                    label: "tmod".to_string(),
+                    description: None,
+                    body: "mod tests {\n    $0\n}".to_string(),
+                    postfix: false,
+                    scope: SnippetScope::Item,
+                    requires: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_scoped_settings() {
+        let roots = vec![PathBuf::from("/ws/a"), PathBuf::from("/ws/b"), PathBuf::from("/ws/c")];
+        let a = ServerConfig {
+            lru_capacity: Some(64),
+            linked_projects: vec![PathBuf::from("Cargo.toml")],
+            ..ServerConfig::default()
+        };
+        let b = ServerConfig {
+            linked_projects: vec![PathBuf::from("sub/Cargo.toml"), roots[0].join("Cargo.toml")],
+            detached_files: vec![PathBuf::from("script.rs")],
+            ..ServerConfig::default()
+        };
+        let config = ServerConfig::merge_scoped(roots.iter().zip(vec![Some(a), Some(b), None]));
+        assert_eq!(
+            config,
+            Some(ServerConfig {
+                lru_capacity: Some(64),
+                linked_projects: vec![roots[0].join("Cargo.toml"), roots[1].join("sub/Cargo.toml")],
+                detached_files: vec![roots[1].join("script.rs")],
+                ..ServerConfig::default()
+            })
+        );
+    }
 }
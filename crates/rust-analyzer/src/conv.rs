@@ -9,10 +9,10 @@ use lsp_types::{
     TextDocumentPositionParams, Url, VersionedTextDocumentIdentifier, WorkspaceEdit,
 };
 use ra_ide::{
-    translate_offset_with_edit, CompletionItem, CompletionItemKind, FileId, FilePosition,
-    FileRange, FileSystemEdit, Fold, FoldKind, Highlight, HighlightModifier, HighlightTag,
-    InlayHint, InlayKind, InsertTextFormat, LineCol, LineIndex, NavigationTarget, RangeInfo,
-    ReferenceAccess, Severity, SourceChange, SourceFileEdit,
+    translate_offset_with_edit, CompletionItem, CompletionItemKind, CompletionScore, FileId,
+    FilePosition, FileRange, FileSystemEdit, Fold, FoldKind, Highlight, HighlightModifier,
+    HighlightTag, InlayHint, InlayKind, InsertTextFormat, LineCol, LineIndex, NavigationTarget,
+    RangeInfo, ReferenceAccess, Severity, SourceChange, SourceFileEdit,
 };
 use ra_syntax::{SyntaxKind, TextRange, TextUnit};
 use ra_text_edit::{AtomTextEdit, TextEdit};
@@ -98,6 +98,7 @@ impl Conv for CompletionItemKind {
             CompletionItemKind::Method => Method,
             CompletionItemKind::TypeParam => TypeParameter,
             CompletionItemKind::Macro => Method,
+            CompletionItemKind::Attribute => Property,
         }
     }
 }
@@ -167,6 +168,19 @@ impl ConvWith<(&LineIndex, LineEndings)> for CompletionItem {
             res.tags = Some(vec![lsp_types::CompletionItemTag::Deprecated])
         }
 
+        // Items fitting the expected type go first, then locals, and the rest
+        // keeps the client's alphabetical order.
+        let rank = match (self.score(), self.kind()) {
+            (Some(CompletionScore::TypeAndNameMatch), _) => 0,
+            (Some(CompletionScore::TypeMatch), _) => 1,
+            (None, Some(CompletionItemKind::Binding)) => 2,
+            (None, _) => 3,
+        };
+        res.sort_text = Some(format!("{}{}", rank, self.lookup()));
+        if self.score() == Some(CompletionScore::TypeAndNameMatch) {
+            res.preselect = Some(true);
+        }
+
         res.insert_text_format = Some(match self.insert_text_format() {
             InsertTextFormat::Snippet => lsp_types::InsertTextFormat::Snippet,
             InsertTextFormat::PlainText => lsp_types::InsertTextFormat::PlainText,
@@ -332,6 +346,9 @@ impl ConvWith<&LineIndex> for InlayHint {
             kind: match self.kind {
                 InlayKind::ParameterHint => req::InlayKind::ParameterHint,
                 InlayKind::TypeHint => req::InlayKind::TypeHint,
+                InlayKind::ChainingHint => req::InlayKind::ChainingHint,
+                InlayKind::ClosureReturnTypeHint => req::InlayKind::ClosureReturnTypeHint,
+                InlayKind::LifetimeHint => req::InlayKind::LifetimeHint,
             },
         }
     }
@@ -451,6 +468,16 @@ impl TryConvWith<&WorldSnapshot> for (&TextDocumentIdentifier, Range) {
     }
 }
 
+impl TryConvWith<&WorldSnapshot> for &Location {
+    type Output = FileRange;
+    fn try_conv_with(self, world: &WorldSnapshot) -> Result<FileRange> {
+        let file_id = self.uri.try_conv_with(world)?;
+        let line_index = world.analysis().file_line_index(file_id)?;
+        let range = self.range.conv_with(&line_index);
+        Ok(FileRange { file_id, range })
+    }
+}
+
 impl<T: TryConvWith<CTX>, CTX: Copy> TryConvWith<CTX> for Vec<T> {
     type Output = Vec<<T as TryConvWith<CTX>>::Output>;
     fn try_conv_with(self, ctx: CTX) -> Result<Self::Output> {
@@ -497,7 +524,12 @@ impl TryConvWith<&WorldSnapshot> for SourceChange {
             changes: None,
             document_changes: Some(DocumentChanges::Operations(document_changes)),
         };
-        Ok(req::SourceChange { label: self.label, workspace_edit, cursor_position })
+        Ok(req::SourceChange {
+            label: self.label,
+            workspace_edit,
+            cursor_position,
+            is_snippet: false,
+        })
     }
 }
 
@@ -608,6 +640,21 @@ pub fn to_call_hierarchy_item(
     })
 }
 
+pub fn to_type_hierarchy_item(
+    world: &WorldSnapshot,
+    nav: NavigationTarget,
+) -> Result<req::TypeHierarchyItem> {
+    let line_index = world.analysis().file_line_index(nav.file_id())?;
+    Ok(req::TypeHierarchyItem {
+        name: nav.name().to_string(),
+        kind: nav.kind().conv(),
+        detail: nav.description().map(|it| it.to_string()),
+        uri: nav.file_id().try_conv_with(world)?,
+        range: nav.full_range().conv_with(&line_index),
+        selection_range: nav.range().conv_with(&line_index),
+    })
+}
+
 pub fn to_location(
     file_id: FileId,
     range: TextRange,
@@ -18,7 +18,7 @@ use rustc_hash::FxHashMap;
 ///
 /// Also note that, at the moment, `FeatureFlags` also store features for
 /// `rust-analyzer`. This should be benign layering violation.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FeatureFlags {
     flags: FxHashMap<String, bool>,
 }
@@ -60,6 +60,7 @@ impl Default for FeatureFlags {
             ("completion.insertion.add-call-parenthesis", true),
             ("completion.insertion.add-argument-snippets", true),
             ("completion.enable-postfix", true),
+            ("completion.enable-flyimport", true),
             ("call-info.full", true),
             ("notifications.workspace-loaded", true),
             ("notifications.cargo-toml-not-found", true),
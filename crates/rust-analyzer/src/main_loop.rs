@@ -21,8 +21,9 @@ use lsp_types::{
     WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
 };
 use ra_cargo_watch::{url_from_path_with_drive_lowercasing, CheckOptions, CheckTask};
-use ra_ide::{Canceled, FileId, InlayHintsOptions, LibraryData, SourceRootId};
+use ra_ide::{AssistOptions, Canceled, FileId, InlayHintsOptions, LibraryData, SourceRootId};
 use ra_prof::profile;
+use ra_project_model::{ProjectManifest, ProjectWorkspace};
 use ra_vfs::{VfsFile, VfsTask, Watch};
 use relative_path::RelativePathBuf;
 use rustc_hash::FxHashSet;
@@ -30,6 +31,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use threadpool::ThreadPool;
 
 use crate::{
+    config::SnippetConfig,
     diagnostics::DiagnosticTask,
     feature_flags::FeatureFlags,
     main_loop::{
@@ -37,7 +39,7 @@ use crate::{
         subscriptions::Subscriptions,
     },
     req,
-    world::{Options, WorldSnapshot, WorldState},
+    world::{LensOptions, Options, WorldSnapshot, WorldState},
     Result, ServerConfig,
 };
 
@@ -45,13 +47,18 @@ use crate::{
 pub struct LspError {
     pub code: i32,
     pub message: String,
+    pub data: Option<serde_json::Value>,
 }
 
 impl LspError {
     pub const UNKNOWN_FILE: i32 = -32900;
 
     pub fn new(code: i32, message: String) -> LspError {
-        LspError { code, message }
+        LspError { code, message, data: None }
+    }
+
+    pub fn with_data(self, data: serde_json::Value) -> LspError {
+        LspError { data: Some(data), ..self }
     }
 }
 
@@ -66,9 +73,10 @@ impl Error for LspError {}
 pub fn main_loop(
     ws_roots: Vec<PathBuf>,
     client_caps: ClientCapabilities,
-    config: ServerConfig,
+    mut config: ServerConfig,
     connection: Connection,
 ) -> Result<()> {
+    config.resolve_unscoped_paths(&ws_roots);
     log::info!("server_config: {:#?}", config);
 
     // Windows scheduler implements priority boosts: if thread waits for an
@@ -92,54 +100,10 @@ pub fn main_loop(
 
     let mut loop_state = LoopState::default();
     let mut world_state = {
-        let feature_flags = {
-            let mut ff = FeatureFlags::default();
-            for (flag, value) in config.feature_flags {
-                if ff.set(flag.as_str(), value).is_err() {
-                    log::error!("unknown feature flag: {:?}", flag);
-                    show_message(
-                        req::MessageType::Error,
-                        format!("unknown feature flag: {:?}", flag),
-                        &connection.sender,
-                    );
-                }
-            }
-            ff
-        };
+        let feature_flags = get_feature_flags(&config, &connection.sender);
         log::info!("feature_flags: {:#?}", feature_flags);
 
-        // FIXME: support dynamic workspace loading.
-        let workspaces = {
-            let mut loaded_workspaces = Vec::new();
-            for ws_root in &ws_roots {
-                let workspace = ra_project_model::ProjectWorkspace::discover_with_sysroot(
-                    ws_root.as_path(),
-                    config.with_sysroot,
-                    &config.cargo_features,
-                );
-                match workspace {
-                    Ok(workspace) => loaded_workspaces.push(workspace),
-                    Err(e) => {
-                        log::error!("loading workspace failed: {:?}", e);
-
-                        if let Some(ra_project_model::CargoTomlNotFoundError { .. }) =
-                            e.downcast_ref()
-                        {
-                            if !feature_flags.get("notifications.cargo-toml-not-found") {
-                                continue;
-                            }
-                        }
-
-                        show_message(
-                            req::MessageType::Error,
-                            format!("rust-analyzer failed to load workspace: {:?}", e),
-                            &connection.sender,
-                        );
-                    }
-                }
-            }
-            loaded_workspaces
-        };
+        let workspaces = load_workspaces(&ws_roots, &config, &feature_flags, &connection.sender);
 
         let globs = config
             .exclude_globs
@@ -168,34 +132,16 @@ pub fn main_loop(
             connection.sender.send(request.into()).unwrap();
         }
 
-        let options = {
-            let text_document_caps = client_caps.text_document.as_ref();
-            Options {
-                publish_decorations: config.publish_decorations,
-                supports_location_link: text_document_caps
-                    .and_then(|it| it.definition)
-                    .and_then(|it| it.link_support)
-                    .unwrap_or(false),
-                line_folding_only: text_document_caps
-                    .and_then(|it| it.folding_range.as_ref())
-                    .and_then(|it| it.line_folding_only)
-                    .unwrap_or(false),
-                inlay_hints: InlayHintsOptions {
-                    type_hints: config.inlay_hints_type,
-                    parameter_hints: config.inlay_hints_parameter,
-                    max_length: config.inlay_hints_max_length,
-                },
-                cargo_watch: CheckOptions {
-                    enable: config.cargo_watch_enable,
-                    args: config.cargo_watch_args,
-                    command: config.cargo_watch_command,
-                    all_targets: config.cargo_watch_all_targets,
-                },
-                rustfmt_args: config.rustfmt_args,
-                vscode_lldb: config.vscode_lldb,
-            }
-        };
+        let options = get_options(&config, &client_caps);
 
+        loop_state.ws_roots = ws_roots.clone();
+        loop_state.config = config.clone();
+        loop_state.client_caps = client_caps;
+        // The initialization options aren't scoped to a workspace root, so we
+        // fetch the settings of each root to resolve their project paths.
+        if ws_roots.len() > 1 && supports_configuration(&loop_state.client_caps) {
+            request_configuration(&connection.sender, &mut loop_state);
+        }
         WorldState::new(
             ws_roots,
             workspaces,
@@ -266,11 +212,178 @@ pub fn main_loop(
     Ok(())
 }
 
+fn get_feature_flags(config: &ServerConfig, msg_sender: &Sender<Message>) -> FeatureFlags {
+    let mut ff = FeatureFlags::default();
+    for (flag, &value) in &config.feature_flags {
+        if ff.set(flag.as_str(), value).is_err() {
+            log::error!("unknown feature flag: {:?}", flag);
+            show_message(
+                req::MessageType::Error,
+                format!("unknown feature flag: {:?}", flag),
+                msg_sender,
+            );
+        }
+    }
+    ff
+}
+
+fn get_options(config: &ServerConfig, client_caps: &ClientCapabilities) -> Options {
+    let text_document_caps = client_caps.text_document.as_ref();
+    let experimental_cap = |name: &str| {
+        client_caps
+            .experimental
+            .as_ref()
+            .and_then(|it| it.get(name))
+            .and_then(|it| it.as_bool())
+            .unwrap_or(false)
+    };
+    Options {
+        publish_decorations: config.publish_decorations,
+        supports_location_link: text_document_caps
+            .and_then(|it| it.definition)
+            .and_then(|it| it.link_support)
+            .unwrap_or(false),
+        line_folding_only: text_document_caps
+            .and_then(|it| it.folding_range.as_ref())
+            .and_then(|it| it.line_folding_only)
+            .unwrap_or(false),
+        inlay_hints: InlayHintsOptions {
+            type_hints: config.inlay_hints_type,
+            parameter_hints: config.inlay_hints_parameter,
+            chaining_hints: config.inlay_hints_chaining,
+            closure_return_type_hints: config.inlay_hints_closure_return_type,
+            lifetime_elision_hints: config.inlay_hints_lifetime_elision,
+            max_length: config.inlay_hints_max_length,
+        },
+        assists: AssistOptions { snippet_edits: experimental_cap("snippetTextEdit") },
+        resolve_code_actions: experimental_cap("resolveCodeAction"),
+        hover_actions: experimental_cap("hoverActions"),
+        cargo_watch: CheckOptions {
+            enable: config.cargo_watch_enable,
+            args: config.cargo_watch_args.clone(),
+            command: config.cargo_watch_command.clone(),
+            all_targets: config.cargo_watch_all_targets,
+        },
+        rustfmt_args: config.rustfmt_args.clone(),
+        completion_snippets: config.snippets.iter().map(SnippetConfig::to_snippet).collect(),
+        vscode_lldb: config.vscode_lldb,
+        lens: LensOptions {
+            run: config.lens_run,
+            debug: config.lens_debug,
+            implementations: config.lens_implementations,
+            references: config.lens_references,
+            method_references: config.lens_method_references,
+        },
+        rename_allow_conflicts: config.rename_allow_conflicts,
+    }
+}
+
+fn load_workspaces(
+    ws_roots: &[PathBuf],
+    config: &ServerConfig,
+    feature_flags: &FeatureFlags,
+    msg_sender: &Sender<Message>,
+) -> Vec<ProjectWorkspace> {
+    let manifests = if config.linked_projects.is_empty() {
+        ws_roots.iter().map(|root| ProjectManifest::discover(root)).collect::<Vec<_>>()
+    } else {
+        config
+            .linked_projects
+            .iter()
+            .map(|path| ProjectManifest::from_manifest_file(path.clone()))
+            .collect()
+    };
+
+    let mut loaded_workspaces = Vec::new();
+    for manifest in manifests {
+        let workspace = manifest.and_then(|manifest| {
+            ProjectWorkspace::load(manifest, config.with_sysroot, &config.cargo_features)
+        });
+        match workspace {
+            Ok(workspace) => loaded_workspaces.push(workspace),
+            Err(e) => {
+                log::error!("loading workspace failed: {:?}", e);
+
+                if let Some(ra_project_model::CargoTomlNotFoundError { .. }) = e.downcast_ref() {
+                    if !feature_flags.get("notifications.cargo-toml-not-found") {
+                        continue;
+                    }
+                }
+
+                show_message(
+                    req::MessageType::Error,
+                    format!("rust-analyzer failed to load workspace: {:?}", e),
+                    msg_sender,
+                );
+            }
+        }
+    }
+
+    if !config.detached_files.is_empty() {
+        match ProjectWorkspace::load_detached_files(
+            config.detached_files.clone(),
+            config.with_sysroot,
+        ) {
+            Ok(workspace) => loaded_workspaces.push(workspace),
+            Err(e) => {
+                log::error!("loading detached files failed: {:?}", e);
+                show_message(
+                    req::MessageType::Error,
+                    format!("rust-analyzer failed to load detached files: {:?}", e),
+                    msg_sender,
+                );
+            }
+        }
+    }
+    loaded_workspaces
+}
+
+/// Applies a configuration the client reported a change of, fetched via
+/// `workspace/configuration` or sent along with the notification.
+fn update_configuration(
+    new_config: ServerConfig,
+    pool: &ThreadPool,
+    task_sender: &Sender<Task>,
+    msg_sender: &Sender<Message>,
+    world_state: &mut WorldState,
+    loop_state: &mut LoopState,
+) {
+    log::info!("updated server_config: {:#?}", new_config);
+    let old_config = std::mem::replace(&mut loop_state.config, new_config);
+    let config = &loop_state.config;
+
+    let feature_flags = get_feature_flags(config, msg_sender);
+    let options = get_options(config, &loop_state.client_caps);
+    world_state.update_configuration(config.lru_capacity, options, feature_flags);
+
+    if config.cargo_features != old_config.cargo_features
+        || config.linked_projects != old_config.linked_projects
+        || config.detached_files != old_config.detached_files
+        || config.with_sysroot != old_config.with_sysroot
+    {
+        loop_state.workspace_generation += 1;
+        let generation = loop_state.workspace_generation;
+        // Loading runs `cargo metadata`, which is too slow for the main loop
+        pool.execute({
+            let ws_roots = loop_state.ws_roots.clone();
+            let config = config.clone();
+            let feature_flags = world_state.feature_flags.clone();
+            let task_sender = task_sender.clone();
+            let msg_sender = msg_sender.clone();
+            move || {
+                let workspaces = load_workspaces(&ws_roots, &config, &feature_flags, &msg_sender);
+                task_sender.send(Task::ReloadWorkspaces { generation, workspaces }).unwrap();
+            }
+        });
+    }
+}
+
 #[derive(Debug)]
 enum Task {
     Respond(Response),
     Notify(Notification),
     Diagnostic(DiagnosticTask),
+    ReloadWorkspaces { generation: u64, workspaces: Vec<ProjectWorkspace> },
 }
 
 enum Event {
@@ -333,6 +446,13 @@ struct LoopState {
     pending_libraries: Vec<(SourceRootId, Vec<(FileId, RelativePathBuf, Arc<String>)>)>,
     workspace_loaded: bool,
     roots_scanned_progress: Option<usize>,
+    ws_roots: Vec<PathBuf>,
+    config: ServerConfig,
+    client_caps: ClientCapabilities,
+    configuration_request_id: Option<RequestId>,
+    /// Incremented by each workspace reload, so that the results of the older
+    /// ones still in flight can be dropped.
+    workspace_generation: u64,
 }
 
 impl LoopState {
@@ -365,6 +485,13 @@ fn loop_turn(
     }
 
     match event {
+        Event::Task(Task::ReloadWorkspaces { generation, .. })
+            if generation != loop_state.workspace_generation =>
+        {
+            // The configuration changed again while loading, and a newer reload
+            // is on its way
+            log::info!("dropping the workspaces of stale reload #{}", generation);
+        }
         Event::Task(task) => {
             on_task(task, &connection.sender, &mut loop_state.pending_requests, world_state);
             world_state.maybe_collect_garbage();
@@ -390,10 +517,11 @@ fn loop_turn(
             )?,
             Message::Notification(not) => {
                 on_notification(
+                    pool,
+                    task_sender,
                     &connection.sender,
                     world_state,
-                    &mut loop_state.pending_requests,
-                    &mut loop_state.subscriptions,
+                    loop_state,
                     not,
                 )?;
             }
@@ -402,6 +530,18 @@ fn loop_turn(
                 if !removed {
                     log::error!("unexpected response: {:?}", resp)
                 }
+
+                if Some(&resp.id) == loop_state.configuration_request_id.as_ref() {
+                    loop_state.configuration_request_id = None;
+                    on_configuration_response(
+                        resp,
+                        pool,
+                        task_sender,
+                        &connection.sender,
+                        world_state,
+                        loop_state,
+                    );
+                }
             }
         },
     };
@@ -486,6 +626,18 @@ fn on_task(
             msg_sender.send(n.into()).unwrap();
         }
         Task::Diagnostic(task) => on_diagnostic_task(task, msg_sender, state),
+        Task::ReloadWorkspaces { workspaces, .. } => {
+            if !state.reload_workspaces(workspaces) {
+                show_message(
+                    req::MessageType::Warning,
+                    "some crates of the reloaded workspace are outside of the initially loaded \
+                     directories, restart rust-analyzer to analyze them"
+                        .to_string(),
+                    msg_sender,
+                );
+            }
+            state.check_watcher.update();
+        }
     }
 }
 
@@ -531,34 +683,46 @@ fn on_request(
         .on::<req::DecorationsRequest>(handlers::handle_decorations)?
         .on::<req::Completion>(handlers::handle_completion)?
         .on::<req::CodeActionRequest>(handlers::handle_code_action)?
+        .on::<req::ResolveCodeAction>(handlers::handle_resolve_code_action)?
         .on::<req::CodeLensRequest>(handlers::handle_code_lens)?
         .on::<req::CodeLensResolve>(handlers::handle_code_lens_resolve)?
         .on::<req::FoldingRangeRequest>(handlers::handle_folding_range)?
         .on::<req::SignatureHelpRequest>(handlers::handle_signature_help)?
         .on::<req::HoverRequest>(handlers::handle_hover)?
         .on::<req::PrepareRenameRequest>(handlers::handle_prepare_rename)?
-        .on::<req::Rename>(handlers::handle_rename)?
+        .on_with_messages::<req::Rename>(handlers::handle_rename)?
+        .on_with_messages::<req::RenameAllowingConflicts>(
+            handlers::handle_rename_allowing_conflicts,
+        )?
         .on::<req::References>(handlers::handle_references)?
         .on::<req::Formatting>(handlers::handle_formatting)?
         .on::<req::DocumentHighlightRequest>(handlers::handle_document_highlight)?
+        .on::<req::DocumentLinkRequest>(handlers::handle_document_link)?
         .on::<req::InlayHints>(handlers::handle_inlay_hints)?
         .on::<req::CallHierarchyPrepare>(handlers::handle_call_hierarchy_prepare)?
         .on::<req::CallHierarchyIncomingCalls>(handlers::handle_call_hierarchy_incoming)?
         .on::<req::CallHierarchyOutgoingCalls>(handlers::handle_call_hierarchy_outgoing)?
+        .on::<req::PrepareTypeHierarchy>(handlers::handle_type_hierarchy_prepare)?
+        .on::<req::TypeHierarchySupertypes>(handlers::handle_type_hierarchy_supertypes)?
+        .on::<req::TypeHierarchySubtypes>(handlers::handle_type_hierarchy_subtypes)?
         .on::<req::SemanticTokensRequest>(handlers::handle_semantic_tokens)?
         .on::<req::SemanticTokensRangeRequest>(handlers::handle_semantic_tokens_range)?
         .on::<req::Ssr>(handlers::handle_ssr)?
+        .on::<req::SsrMatches>(handlers::handle_ssr_matches)?
         .finish();
     Ok(())
 }
 
 fn on_notification(
+    pool: &ThreadPool,
+    task_sender: &Sender<Task>,
     msg_sender: &Sender<Message>,
     state: &mut WorldState,
-    pending_requests: &mut PendingRequests,
-    subs: &mut Subscriptions,
+    loop_state: &mut LoopState,
     not: Notification,
 ) -> Result<()> {
+    let pending_requests = &mut loop_state.pending_requests;
+    let subs = &mut loop_state.subscriptions;
     let not = match notification_cast::<req::Cancel>(not) {
         Ok(params) => {
             let id: RequestId = match params.id {
@@ -624,7 +788,19 @@ fn on_notification(
         Err(not) => not,
     };
     let not = match notification_cast::<req::DidChangeConfiguration>(not) {
-        Ok(_params) => {
+        Ok(params) => {
+            if !supports_configuration(&loop_state.client_caps) {
+                // Clients which can't be asked for the configuration have
+                // to push their settings with the notification.
+                let settings = params.settings.get("rust-analyzer").unwrap_or(&params.settings);
+                let mut new_config = loop_state.config.with_client_settings(settings);
+                new_config.resolve_unscoped_paths(&loop_state.ws_roots);
+                update_configuration(new_config, pool, task_sender, msg_sender, state, loop_state);
+                return Ok(());
+            }
+            // The settings in the notification are client-specific, so we
+            // ignore them and query the actual config separately.
+            request_configuration(msg_sender, loop_state);
             return Ok(());
         }
         Err(not) => not,
@@ -648,6 +824,71 @@ fn on_notification(
     Ok(())
 }
 
+fn supports_configuration(client_caps: &ClientCapabilities) -> bool {
+    client_caps.workspace.as_ref().and_then(|it| it.configuration).unwrap_or(false)
+}
+
+/// Asks the client for the settings of each workspace root, as the relative
+/// project paths in them are resolved against the root they are set for.
+fn request_configuration(msg_sender: &Sender<Message>, loop_state: &mut LoopState) {
+    let section = Some("rust-analyzer".to_string());
+    let items = if loop_state.ws_roots.is_empty() {
+        vec![req::ConfigurationItem { scope_uri: None, section }]
+    } else {
+        loop_state
+            .ws_roots
+            .iter()
+            .map(|root| req::ConfigurationItem {
+                scope_uri: url_from_path_with_drive_lowercasing(root).ok(),
+                section: section.clone(),
+            })
+            .collect()
+    };
+    let request_id = loop_state.next_request_id();
+    let request = request_new::<req::WorkspaceConfiguration>(
+        request_id.clone(),
+        req::ConfigurationParams { items },
+    );
+    msg_sender.send(request.into()).unwrap();
+    loop_state.configuration_request_id = Some(request_id);
+}
+
+fn on_configuration_response(
+    resp: Response,
+    pool: &ThreadPool,
+    task_sender: &Sender<Task>,
+    msg_sender: &Sender<Message>,
+    world_state: &mut WorldState,
+    loop_state: &mut LoopState,
+) {
+    let configs = match (resp.error, resp.result) {
+        (Some(err), _) => {
+            log::error!("failed to fetch the server settings: {:?}", err);
+            return;
+        }
+        (None, Some(configs)) => configs,
+        (None, None) => {
+            log::error!("received empty server settings response from the client");
+            return;
+        }
+    };
+    let ws_roots = &loop_state.ws_roots;
+    let new_config = serde_json::from_value::<Vec<Option<ServerConfig>>>(configs).map(|configs| {
+        if ws_roots.is_empty() {
+            configs.into_iter().next().flatten()
+        } else {
+            ServerConfig::merge_scoped(ws_roots.iter().zip(configs))
+        }
+    });
+    match new_config {
+        Ok(Some(new_config)) => {
+            update_configuration(new_config, pool, task_sender, msg_sender, world_state, loop_state)
+        }
+        Ok(None) => log::warn!("client returned no rust-analyzer settings"),
+        Err(e) => log::error!("failed to parse the server settings: {}", e),
+    }
+}
+
 fn on_check_task(
     task: CheckTask,
     world_state: &mut WorldState,
@@ -828,6 +1069,42 @@ impl<'a> PoolDispatcher<'a> {
         Ok(self)
     }
 
+    /// Dispatches the request onto thread pool, showing the messages collected
+    /// by the handler to the user before responding
+    fn on_with_messages<R>(
+        &mut self,
+        f: fn(WorldSnapshot, R::Params, &mut Vec<req::ShowMessageParams>) -> Result<R::Result>,
+    ) -> Result<&mut Self>
+    where
+        R: req::Request + 'static,
+        R::Params: DeserializeOwned + Send + 'static,
+        R::Result: Serialize + 'static,
+    {
+        let (id, params) = match self.parse::<R>() {
+            Some(it) => it,
+            None => {
+                return Ok(self);
+            }
+        };
+
+        self.pool.execute({
+            let world = self.world.snapshot();
+            let sender = self.task_sender.clone();
+            move || {
+                let mut messages = Vec::new();
+                let result = f(world, params, &mut messages);
+                for message in messages {
+                    let not = notification_new::<req::ShowMessage>(message);
+                    sender.send(Task::Notify(not)).unwrap();
+                }
+                let task = result_to_task::<R>(id, result);
+                sender.send(task).unwrap();
+            }
+        });
+
+        Ok(self)
+    }
+
     fn parse<R>(&mut self) -> Option<(RequestId, R::Params)>
     where
         R: req::Request + 'static,
@@ -879,7 +1156,11 @@ where
                     // Work-around for https://github.com/rust-analyzer/rust-analyzer/issues/1521
                     Response::new_ok(id, ())
                 } else {
-                    Response::new_err(id, lsp_error.code, lsp_error.message)
+                    let mut response = Response::new_err(id, lsp_error.code, lsp_error.message);
+                    if let Some(error) = &mut response.error {
+                        error.data = lsp_error.data;
+                    }
+                    response
                 }
             }
             Err(e) => {
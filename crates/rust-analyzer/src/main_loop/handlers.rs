@@ -17,23 +17,24 @@ use lsp_types::{
     Hover, HoverContents, Location, MarkupContent, MarkupKind, Position, PrepareRenameResponse,
     Range, RenameParams, SemanticTokens, SemanticTokensParams, SemanticTokensRangeParams,
     SemanticTokensRangeResult, SemanticTokensResult, SymbolInformation, TextDocumentIdentifier,
-    TextEdit, WorkspaceEdit,
+    TextEdit, Url, WorkspaceEdit,
 };
 use ra_ide::{
-    Assist, AssistId, CompletionOptions, FileId, FilePosition, FileRange, Query, RangeInfo,
-    Runnable, RunnableKind, SearchScope,
+    Assist, AssistId, CompletionOptions, DocumentLinkTarget, FileId, FilePosition, FileRange,
+    HoverAction, HoverGotoTypeData, Query, RangeInfo, Runnable, RunnableKind, SearchScope,
+    UnresolvedAssist,
 };
 use ra_prof::profile;
 use ra_syntax::{AstNode, SyntaxKind, TextRange, TextUnit};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
-use serde_json::to_value;
+use serde_json::{json, to_value};
 
 use crate::{
     cargo_target_spec::CargoTargetSpec,
     conv::{
-        to_call_hierarchy_item, to_location, Conv, ConvWith, FoldConvCtx, MapConvWith, TryConvWith,
-        TryConvWithToVec,
+        to_call_hierarchy_item, to_location, to_type_hierarchy_item, Conv, ConvWith, FoldConvCtx,
+        MapConvWith, TryConvWith, TryConvWithToVec,
     },
     diagnostics::DiagnosticTask,
     from_json,
@@ -431,6 +432,8 @@ pub fn handle_completion(
         add_call_argument_snippets: world
             .feature_flags
             .get("completion.insertion.add-argument-snippets"),
+        enable_flyimport_completions: world.feature_flags.get("completion.enable-flyimport"),
+        snippets: world.options.completion_snippets.clone(),
     };
 
     let items = match world.analysis().completions(position, &options)? {
@@ -490,7 +493,7 @@ pub fn handle_signature_help(
 pub fn handle_hover(
     world: WorldSnapshot,
     params: req::TextDocumentPositionParams,
-) -> Result<Option<Hover>> {
+) -> Result<Option<req::Hover>> {
     let _p = profile("handle_hover");
     let position = params.try_conv_with(&world)?;
     let info = match world.analysis().hover(position)? {
@@ -499,12 +502,27 @@ pub fn handle_hover(
     };
     let line_index = world.analysis.file_line_index(position.file_id)?;
     let range = info.range.conv_with(&line_index);
-    let res = Hover {
-        contents: HoverContents::Markup(MarkupContent {
-            kind: MarkupKind::Markdown,
-            value: crate::markdown::format_docs(&info.info.to_markup()),
-        }),
-        range: Some(range),
+    let actions = if world.options.hover_actions {
+        prepare_hover_actions(&world, info.info.actions())
+    } else {
+        Vec::new()
+    };
+    // Intra-doc links to items of the local crates point to their source
+    let markup = info.info.to_markup_with_file_urls(|file_id, range| {
+        let uri = world.file_id_to_uri(file_id).ok()?;
+        let line_index = world.analysis().file_line_index(file_id).ok()?;
+        let pos = range.start().conv_with(&line_index);
+        Some(format!("{}#L{}", uri, pos.line + 1))
+    });
+    let res = req::Hover {
+        hover: Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: crate::markdown::format_docs(&markup),
+            }),
+            range: Some(range),
+        },
+        actions,
     };
     Ok(Some(res))
 }
@@ -516,8 +534,7 @@ pub fn handle_prepare_rename(
     let _p = profile("handle_prepare_rename");
     let position = params.try_conv_with(&world)?;
 
-    let optional_change = world.analysis().rename(position, "dummy")?;
-    let range = match optional_change {
+    let range = match world.analysis().prepare_rename(position)? {
         None => return Ok(None),
         Some(it) => it.range,
     };
@@ -528,8 +545,33 @@ pub fn handle_prepare_rename(
     Ok(Some(PrepareRenameResponse::Range(range)))
 }
 
-pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+pub fn handle_rename(
+    world: WorldSnapshot,
+    params: RenameParams,
+    messages: &mut Vec<req::ShowMessageParams>,
+) -> Result<Option<WorkspaceEdit>> {
     let _p = profile("handle_rename");
+    let find_conflicts = !world.options.rename_allow_conflicts;
+    rename(world, params, find_conflicts, messages)
+}
+
+/// Follow-up of a rename which failed because of conflicts, offered to the
+/// client as a command in the data of the error.
+pub fn handle_rename_allowing_conflicts(
+    world: WorldSnapshot,
+    params: RenameParams,
+    messages: &mut Vec<req::ShowMessageParams>,
+) -> Result<Option<WorkspaceEdit>> {
+    let _p = profile("handle_rename_allowing_conflicts");
+    rename(world, params, false, messages)
+}
+
+fn rename(
+    world: WorldSnapshot,
+    params: RenameParams,
+    find_conflicts: bool,
+    messages: &mut Vec<req::ShowMessageParams>,
+) -> Result<Option<WorkspaceEdit>> {
     let position = params.text_document_position.try_conv_with(&world)?;
 
     if params.new_name.is_empty() {
@@ -540,13 +582,53 @@ pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Optio
         .into());
     }
 
-    let optional_change = world.analysis().rename(position, &*params.new_name)?;
-    let change = match optional_change {
+    let optional_result =
+        world.analysis().rename_with_conflicts(position, &*params.new_name, find_conflicts)?;
+    let result = match optional_result {
         None => return Ok(None),
         Some(it) => it.info,
     };
 
-    let source_change_req = change.try_conv_with(&world)?;
+    let location = |range: FileRange| -> Result<String> {
+        let line_index = world.analysis().file_line_index(range.file_id)?;
+        let pos = range.range.start().conv_with(&line_index);
+        Ok(format!(
+            "{}:{}:{}",
+            world.file_id_to_path(range.file_id).display(),
+            pos.line + 1,
+            pos.character + 1,
+        ))
+    };
+    if !result.conflicts.is_empty() {
+        let mut message =
+            format!("Renaming to `{}` would change the meaning of the code:", params.new_name);
+        for conflict in result.conflicts {
+            message.push_str(&format!(
+                "\n{}: conflicts with `{}`",
+                location(conflict.range)?,
+                conflict.conflicting.name(),
+            ));
+        }
+        let rename_anyway = Command {
+            title: "Rename Anyway".to_string(),
+            command: "rust-analyzer.renameAllowingConflicts".to_string(),
+            arguments: Some(vec![to_value(&params)?]),
+        };
+        let data = json!({ "command": rename_anyway });
+        return Err(LspError::new(ErrorCode::InvalidParams as i32, message).with_data(data).into());
+    }
+    if !result.macro_generated.is_empty() {
+        let mut message = format!(
+            "Renamed to `{}`, but some macro bodies still use the old name:",
+            params.new_name
+        );
+        for range in result.macro_generated {
+            message.push_str(&format!("\n{}: used by the body of this macro", location(range)?));
+        }
+        messages.push(req::ShowMessageParams { typ: req::MessageType::Warning, message });
+    }
+
+    let source_change_req = result.change.try_conv_with(&world)?;
 
     Ok(Some(source_change_req.workspace_edit))
 }
@@ -663,24 +745,57 @@ pub fn handle_formatting(
     }]))
 }
 
+fn assist_source_change(assist: Assist, world: &WorldSnapshot) -> Result<req::SourceChange> {
+    let mut source_change = assist.source_change.try_conv_with(world)?;
+    source_change.is_snippet = assist.is_snippet;
+    Ok(source_change)
+}
+
+fn code_action_kind(id: AssistId) -> Option<String> {
+    match id {
+        AssistId("introduce_variable") => Some("refactor.extract.variable".to_string()),
+        AssistId("add_custom_impl") => Some("refactor.rewrite.add_custom_impl".to_string()),
+        _ => None,
+    }
+}
+
 fn create_single_code_action(assist: Assist, world: &WorldSnapshot) -> Result<CodeAction> {
-    let arg = to_value(assist.source_change.try_conv_with(world)?)?;
-    let title = assist.label;
+    let kind = code_action_kind(assist.id);
+    let title = assist.label.clone();
+    let arg = to_value(assist_source_change(assist, world)?)?;
     let command = Command {
         title: title.clone(),
         command: "rust-analyzer.applySourceChange".to_string(),
         arguments: Some(vec![arg]),
     };
 
-    let kind = match assist.id {
-        AssistId("introduce_variable") => Some("refactor.extract.variable".to_string()),
-        AssistId("add_custom_impl") => Some("refactor.rewrite.add_custom_impl".to_string()),
-        _ => None,
+    Ok(CodeAction {
+        title,
+        kind,
+        diagnostics: None,
+        edit: None,
+        command: Some(command),
+        is_preferred: None,
+    })
+}
+
+/// Creates a code action which asks the client to resolve the edit of the
+/// assist only once it gets applied.
+fn create_unresolved_code_action(
+    assist: &UnresolvedAssist,
+    params: &req::CodeActionParams,
+) -> Result<CodeAction> {
+    let title = assist.label.clone();
+    let arg = to_value(resolve_code_action_params(assist, params))?;
+    let command = Command {
+        title: title.clone(),
+        command: "rust-analyzer.resolveCodeAction".to_string(),
+        arguments: Some(vec![arg]),
     };
 
     Ok(CodeAction {
         title,
-        kind,
+        kind: code_action_kind(assist.id),
         diagnostics: None,
         edit: None,
         command: Some(command),
@@ -688,6 +803,17 @@ fn create_single_code_action(assist: Assist, world: &WorldSnapshot) -> Result<Co
     })
 }
 
+fn resolve_code_action_params(
+    assist: &UnresolvedAssist,
+    params: &req::CodeActionParams,
+) -> req::ResolveCodeActionParams {
+    req::ResolveCodeActionParams {
+        code_action_params: params.clone(),
+        id: assist.id.0.to_string(),
+        label: assist.label.clone(),
+    }
+}
+
 pub fn handle_code_action(
     world: WorldSnapshot,
     params: req::CodeActionParams,
@@ -734,8 +860,47 @@ pub fn handle_code_action(
         res.push(fix.action.clone());
     }
 
+    let frange = FileRange { file_id, range };
+    if world.options.resolve_code_actions {
+        let mut grouped_assists: FxHashMap<String, Vec<UnresolvedAssist>> = FxHashMap::default();
+        for assist in world.analysis().unresolved_assists(&world.options.assists, frange)? {
+            match &assist.group_label {
+                Some(label) => grouped_assists.entry(label.to_owned()).or_default().push(assist),
+                None => res.push(create_unresolved_code_action(&assist, &params)?.into()),
+            }
+        }
+
+        for (group_label, assists) in grouped_assists {
+            if assists.len() == 1 {
+                res.push(create_unresolved_code_action(&assists[0], &params)?.into());
+            } else {
+                let arguments = assists
+                    .iter()
+                    .map(|assist| to_value(resolve_code_action_params(assist, &params)))
+                    .collect::<serde_json::Result<Vec<_>>>()?;
+                let command = Some(Command {
+                    title: group_label.clone(),
+                    command: "rust-analyzer.selectAndResolveCodeAction".to_string(),
+                    arguments: Some(vec![serde_json::Value::Array(arguments)]),
+                });
+                res.push(
+                    CodeAction {
+                        title: group_label,
+                        kind: None,
+                        diagnostics: None,
+                        edit: None,
+                        command,
+                        is_preferred: None,
+                    }
+                    .into(),
+                );
+            }
+        }
+        return Ok(Some(res));
+    }
+
     let mut grouped_assists: FxHashMap<String, Vec<Assist>> = FxHashMap::default();
-    for assist in world.analysis().assists(FileRange { file_id, range })?.into_iter() {
+    for assist in world.analysis().assists(&world.options.assists, frange)?.into_iter() {
         match &assist.group_label {
             Some(label) => grouped_assists.entry(label.to_owned()).or_default().push(assist),
             None => res.push(create_single_code_action(assist, &world)?.into()),
@@ -752,7 +917,7 @@ pub fn handle_code_action(
 
             let mut arguments = Vec::with_capacity(assists.len());
             for assist in assists {
-                arguments.push(to_value(assist.source_change.try_conv_with(&world)?)?);
+                arguments.push(to_value(assist_source_change(assist, &world)?)?);
             }
 
             let command = Some(Command {
@@ -777,6 +942,28 @@ pub fn handle_code_action(
     Ok(Some(res))
 }
 
+pub fn handle_resolve_code_action(
+    world: WorldSnapshot,
+    params: req::ResolveCodeActionParams,
+) -> Result<Option<req::SourceChange>> {
+    let _p = profile("handle_resolve_code_action");
+    let file_id = params.code_action_params.text_document.try_conv_with(&world)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+    let range = params.code_action_params.range.conv_with(&line_index);
+    let frange = FileRange { file_id, range };
+
+    let assist = world.analysis().resolve_assist(
+        &world.options.assists,
+        frange,
+        &params.id,
+        &params.label,
+    )?;
+    match assist {
+        Some(assist) => Ok(Some(assist_source_change(assist, &world)?)),
+        None => Ok(None),
+    }
+}
+
 pub fn handle_code_lens(
     world: WorldSnapshot,
     params: req::CodeLensParams,
@@ -787,6 +974,8 @@ pub fn handle_code_lens(
 
     let mut lenses: Vec<CodeLens> = Default::default();
 
+    let lens_options = &world.options.lens;
+
     // Gather runnables
     for runnable in world.analysis().runnables(file_id)? {
         let title = match &runnable.kind {
@@ -796,18 +985,20 @@ pub fn handle_code_lens(
         }
         .to_string();
         let mut r = to_lsp_runnable(&world, file_id, runnable)?;
-        let lens = CodeLens {
-            range: r.range,
-            command: Some(Command {
-                title,
-                command: "rust-analyzer.runSingle".into(),
-                arguments: Some(vec![to_value(&r).unwrap()]),
-            }),
-            data: None,
-        };
-        lenses.push(lens);
+        if lens_options.run {
+            let lens = CodeLens {
+                range: r.range,
+                command: Some(Command {
+                    title,
+                    command: "rust-analyzer.runSingle".into(),
+                    arguments: Some(vec![to_value(&r).unwrap()]),
+                }),
+                data: None,
+            };
+            lenses.push(lens);
+        }
 
-        if world.options.vscode_lldb {
+        if world.options.vscode_lldb && lens_options.debug {
             if r.args[0] == "run" {
                 r.args[0] = "build".into();
             } else {
@@ -826,28 +1017,54 @@ pub fn handle_code_lens(
         }
     }
 
-    // Handle impls
-    lenses.extend(
-        world
-            .analysis()
-            .file_structure(file_id)?
-            .into_iter()
-            .filter(|it| match it.kind {
-                SyntaxKind::TRAIT_DEF | SyntaxKind::STRUCT_DEF | SyntaxKind::ENUM_DEF => true,
+    // Handle impls and references
+    let structure = world.analysis().file_structure(file_id)?;
+    for node in structure.iter() {
+        let parent_kind = node.parent.map(|idx| structure[idx].kind);
+        let is_method = node.kind == SyntaxKind::FN_DEF
+            && match parent_kind {
+                Some(SyntaxKind::IMPL_DEF) | Some(SyntaxKind::TRAIT_DEF) => true,
                 _ => false,
-            })
-            .map(|it| {
-                let range = it.node_range.conv_with(&line_index);
-                let pos = range.start;
-                let lens_params =
-                    req::TextDocumentPositionParams::new(params.text_document.clone(), pos);
-                CodeLens {
-                    range,
-                    command: None,
-                    data: Some(to_value(CodeLensResolveData::Impls(lens_params)).unwrap()),
-                }
-            }),
-    );
+            };
+        let range = node.node_range.conv_with(&line_index);
+        let name_pos = node.navigation_range.start().conv_with(&line_index);
+
+        let impls_pos = match node.kind {
+            SyntaxKind::TRAIT_DEF | SyntaxKind::STRUCT_DEF | SyntaxKind::ENUM_DEF => {
+                Some(range.start)
+            }
+            SyntaxKind::FN_DEF if parent_kind == Some(SyntaxKind::TRAIT_DEF) => Some(name_pos),
+            _ => None,
+        };
+        if let Some(pos) = impls_pos.filter(|_| lens_options.implementations) {
+            let lens_params =
+                req::TextDocumentPositionParams::new(params.text_document.clone(), pos);
+            lenses.push(CodeLens {
+                range,
+                command: None,
+                data: Some(to_value(CodeLensResolveData::Impls(lens_params)).unwrap()),
+            });
+        }
+
+        let show_references = match node.kind {
+            SyntaxKind::FN_DEF if is_method => lens_options.method_references,
+            SyntaxKind::FN_DEF
+            | SyntaxKind::CONST_DEF
+            | SyntaxKind::STATIC_DEF
+            | SyntaxKind::RECORD_FIELD_DEF
+            | SyntaxKind::ENUM_VARIANT => lens_options.references,
+            _ => false,
+        };
+        if show_references {
+            let lens_params =
+                req::TextDocumentPositionParams::new(params.text_document.clone(), name_pos);
+            lenses.push(CodeLens {
+                range,
+                command: None,
+                data: Some(to_value(CodeLensResolveData::References(lens_params)).unwrap()),
+            });
+        }
+    }
 
     Ok(Some(lenses))
 }
@@ -856,6 +1073,7 @@ pub fn handle_code_lens(
 #[serde(rename_all = "camelCase")]
 enum CodeLensResolveData {
     Impls(req::TextDocumentPositionParams),
+    References(req::TextDocumentPositionParams),
 }
 
 pub fn handle_code_lens_resolve(world: WorldSnapshot, code_lens: CodeLens) -> Result<CodeLens> {
@@ -880,19 +1098,36 @@ pub fn handle_code_lens_resolve(world: WorldSnapshot, code_lens: CodeLens) -> Re
             } else {
                 format!("{} implementations", locations.len())
             };
+            let cmd = show_references_command(title, &lens_params, locations);
+            Ok(CodeLens { range: code_lens.range, command: Some(cmd), data: None })
+        }
+        Some(CodeLensResolveData::References(lens_params)) => {
+            let position = lens_params.try_conv_with(&world)?;
+            let locations: Vec<Location> = match world.analysis().find_all_refs(position, None)? {
+                Some(refs) => refs
+                    .references()
+                    .iter()
+                    .filter_map(|reference| {
+                        let line_index =
+                            world.analysis().file_line_index(reference.file_range.file_id).ok()?;
+                        to_location(
+                            reference.file_range.file_id,
+                            reference.file_range.range,
+                            &world,
+                            &line_index,
+                        )
+                        .ok()
+                    })
+                    .collect(),
+                None => vec![],
+            };
 
-            // We cannot use the 'editor.action.showReferences' command directly
-            // because that command requires vscode types which we convert in the handler
-            // on the client side.
-            let cmd = Command {
-                title,
-                command: "rust-analyzer.showReferences".into(),
-                arguments: Some(vec![
-                    to_value(&lens_params.text_document.uri).unwrap(),
-                    to_value(code_lens.range.start).unwrap(),
-                    to_value(locations).unwrap(),
-                ]),
+            let title = if locations.len() == 1 {
+                "1 reference".into()
+            } else {
+                format!("{} references", locations.len())
             };
+            let cmd = show_references_command(title, &lens_params, locations);
             Ok(CodeLens { range: code_lens.range, command: Some(cmd), data: None })
         }
         None => Ok(CodeLens {
@@ -903,6 +1138,52 @@ pub fn handle_code_lens_resolve(world: WorldSnapshot, code_lens: CodeLens) -> Re
     }
 }
 
+fn show_references_command(
+    title: String,
+    lens_params: &req::TextDocumentPositionParams,
+    locations: Vec<Location>,
+) -> Command {
+    // We cannot use the 'editor.action.showReferences' command directly
+    // because that command requires vscode types which we convert in the handler
+    // on the client side.
+    Command {
+        title,
+        command: "rust-analyzer.showReferences".into(),
+        arguments: Some(vec![
+            to_value(&lens_params.text_document.uri).unwrap(),
+            to_value(lens_params.position).unwrap(),
+            to_value(locations).unwrap(),
+        ]),
+    }
+}
+
+pub fn handle_document_link(
+    world: WorldSnapshot,
+    params: req::DocumentLinkParams,
+) -> Result<Option<Vec<req::DocumentLink>>> {
+    let _p = profile("handle_document_link");
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+
+    let mut res = Vec::new();
+    for link in world.analysis().document_links(file_id)? {
+        let target = match link.target {
+            DocumentLinkTarget::File(file_id) => world.file_id_to_uri(file_id)?,
+            DocumentLinkTarget::Url(url) => match Url::parse(&url) {
+                Ok(it) => it,
+                Err(_) => continue,
+            },
+        };
+        res.push(req::DocumentLink {
+            range: link.range.conv_with(&line_index),
+            target,
+            tooltip: None,
+            data: None,
+        });
+    }
+    Ok(Some(res))
+}
+
 pub fn handle_document_highlight(
     world: WorldSnapshot,
     params: req::TextDocumentPositionParams,
@@ -910,14 +1191,22 @@ pub fn handle_document_highlight(
     let _p = profile("handle_document_highlight");
     let file_id = params.text_document.try_conv_with(&world)?;
     let line_index = world.analysis().file_line_index(file_id)?;
+    let position = params.try_conv_with(&world)?;
 
-    let refs = match world
-        .analysis()
-        .find_all_refs(params.try_conv_with(&world)?, Some(SearchScope::single_file(file_id)))?
-    {
-        None => return Ok(None),
-        Some(refs) => refs,
-    };
+    if let Some(ranges) = world.analysis().highlight_related(position)? {
+        return Ok(Some(
+            ranges
+                .into_iter()
+                .map(|range| DocumentHighlight { range: range.conv_with(&line_index), kind: None })
+                .collect(),
+        ));
+    }
+
+    let refs =
+        match world.analysis().find_all_refs(position, Some(SearchScope::single_file(file_id)))? {
+            None => return Ok(None),
+            Some(refs) => refs,
+        };
 
     Ok(Some(
         refs.into_iter()
@@ -932,12 +1221,46 @@ pub fn handle_document_highlight(
 
 pub fn handle_ssr(world: WorldSnapshot, params: req::SsrParams) -> Result<req::SourceChange> {
     let _p = profile("handle_ssr");
+    let selected = match params.selected {
+        Some(locations) => Some(locations.iter().try_conv_with_to_vec(&world)?),
+        None => None,
+    };
     world
         .analysis()
-        .structural_search_replace(&params.query, params.parse_only)??
+        .structural_search_replace(&params.query, params.parse_only, selected.as_deref())??
         .try_conv_with(&world)
 }
 
+pub fn handle_ssr_matches(
+    world: WorldSnapshot,
+    params: req::SsrMatchesParams,
+) -> Result<Vec<req::SsrMatch>> {
+    let _p = profile("handle_ssr_matches");
+    let mut res = Vec::new();
+    for ssr_match in world.analysis().structural_search(&params.query)?? {
+        let line_index = world.analysis().file_line_index(ssr_match.range.file_id)?;
+        let location =
+            to_location(ssr_match.range.file_id, ssr_match.range.range, &world, &line_index)?;
+        let bindings = ssr_match
+            .bindings
+            .into_iter()
+            .map(|(name, text)| req::SsrBinding { name, text })
+            .collect();
+        let enclosing_item = match ssr_match.enclosing_item {
+            Some(nav) => Some(SymbolInformation {
+                name: nav.name().to_string(),
+                kind: nav.kind().conv(),
+                location: nav.try_conv_with(&world)?,
+                container_name: nav.container_name().map(|v| v.to_string()),
+                deprecated: None,
+            }),
+            None => None,
+        };
+        res.push(req::SsrMatch { location, bindings, enclosing_item });
+    }
+    Ok(res)
+}
+
 pub fn publish_diagnostics(world: &WorldSnapshot, file_id: FileId) -> Result<DiagnosticTask> {
     let _p = profile("publish_diagnostics");
     let line_index = world.analysis().file_line_index(file_id)?;
@@ -967,6 +1290,100 @@ pub fn publish_decorations(
     Ok(req::PublishDecorationsParams { uri, decorations: highlight(&world, file_id)? })
 }
 
+fn prepare_hover_actions(
+    world: &WorldSnapshot,
+    actions: &[HoverAction],
+) -> Vec<req::CommandLinkGroup> {
+    actions
+        .iter()
+        .filter_map(|action| match action {
+            HoverAction::Implementation(position) => show_impl_command_link(world, position),
+            HoverAction::Runnable(file_id, runnable) => {
+                runnable_action_links(world, *file_id, runnable.clone())
+            }
+            HoverAction::GoToType(targets) => goto_type_action_links(world, targets),
+        })
+        .collect()
+}
+
+fn show_impl_command_link(
+    world: &WorldSnapshot,
+    position: &FilePosition,
+) -> Option<req::CommandLinkGroup> {
+    let nav_info = world.analysis().goto_implementation(*position).ok()??;
+    let locations: Vec<Location> =
+        nav_info.info.iter().filter_map(|nav| nav.try_conv_with(world).ok()).collect();
+    let uri = world.file_id_to_uri(position.file_id).ok()?;
+    let line_index = world.analysis().file_line_index(position.file_id).ok()?;
+    let lens_params = req::TextDocumentPositionParams::new(
+        TextDocumentIdentifier::new(uri),
+        position.offset.conv_with(&line_index),
+    );
+    let title = format!("Implementations ({})", locations.len());
+    let command = show_references_command(title, &lens_params, locations);
+    Some(req::CommandLinkGroup {
+        title: None,
+        commands: vec![req::CommandLink { command, tooltip: Some("Go to implementations".into()) }],
+    })
+}
+
+fn runnable_action_links(
+    world: &WorldSnapshot,
+    file_id: FileId,
+    runnable: Runnable,
+) -> Option<req::CommandLinkGroup> {
+    let r = to_lsp_runnable(world, file_id, runnable).ok()?;
+    let mut commands = vec![req::CommandLink {
+        command: Command {
+            title: "▶\u{fe0e} Run".into(),
+            command: "rust-analyzer.runSingle".into(),
+            arguments: Some(vec![to_value(&r).unwrap()]),
+        },
+        tooltip: Some(r.label.clone()),
+    }];
+    if world.options.vscode_lldb {
+        let mut r = r;
+        if r.args[0] == "run" {
+            r.args[0] = "build".into();
+        } else {
+            r.args.push("--no-run".into());
+        }
+        commands.push(req::CommandLink {
+            command: Command {
+                title: "Debug".into(),
+                command: "rust-analyzer.debugSingle".into(),
+                arguments: Some(vec![to_value(&r).unwrap()]),
+            },
+            tooltip: Some(r.label.clone()),
+        });
+    }
+    Some(req::CommandLinkGroup { title: None, commands })
+}
+
+fn goto_type_action_links(
+    world: &WorldSnapshot,
+    targets: &[HoverGotoTypeData],
+) -> Option<req::CommandLinkGroup> {
+    let commands: Vec<req::CommandLink> = targets
+        .iter()
+        .filter_map(|target| {
+            let location: Location = target.nav.try_conv_with(world).ok()?;
+            Some(req::CommandLink {
+                command: Command {
+                    title: target.nav.name().to_string(),
+                    command: "rust-analyzer.gotoLocation".into(),
+                    arguments: Some(vec![to_value(location).unwrap()]),
+                },
+                tooltip: Some(target.mod_path.clone()),
+            })
+        })
+        .collect();
+    if commands.is_empty() {
+        return None;
+    }
+    Some(req::CommandLinkGroup { title: Some("Go to".into()), commands })
+}
+
 fn to_lsp_runnable(
     world: &WorldSnapshot,
     file_id: FileId,
@@ -1162,3 +1579,55 @@ pub fn handle_semantic_tokens_range(
 
     Ok(Some(tokens.into()))
 }
+
+pub fn handle_type_hierarchy_prepare(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<Vec<req::TypeHierarchyItem>>> {
+    let _p = profile("handle_type_hierarchy_prepare");
+    let position = params.try_conv_with(&world)?;
+    let nav = match world.analysis().type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it.info,
+    };
+    Ok(Some(vec![to_type_hierarchy_item(&world, nav)?]))
+}
+
+pub fn handle_type_hierarchy_supertypes(
+    world: WorldSnapshot,
+    params: req::TypeHierarchyItemParams,
+) -> Result<Option<Vec<req::TypeHierarchyItem>>> {
+    let _p = profile("handle_type_hierarchy_supertypes");
+    let position = type_hierarchy_item_position(&world, params.item)?;
+    let navs = match world.analysis().supertypes(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res =
+        navs.into_iter().map(|nav| to_type_hierarchy_item(&world, nav)).collect::<Result<_>>()?;
+    Ok(Some(res))
+}
+
+pub fn handle_type_hierarchy_subtypes(
+    world: WorldSnapshot,
+    params: req::TypeHierarchyItemParams,
+) -> Result<Option<Vec<req::TypeHierarchyItem>>> {
+    let _p = profile("handle_type_hierarchy_subtypes");
+    let position = type_hierarchy_item_position(&world, params.item)?;
+    let navs = match world.analysis().subtypes(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res =
+        navs.into_iter().map(|nav| to_type_hierarchy_item(&world, nav)).collect::<Result<_>>()?;
+    Ok(Some(res))
+}
+
+fn type_hierarchy_item_position(
+    world: &WorldSnapshot,
+    item: req::TypeHierarchyItem,
+) -> Result<FilePosition> {
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange: FileRange = (&doc, item.selection_range).try_conv_with(world)?;
+    Ok(FilePosition { file_id: frange.file_id, offset: frange.range.start() })
+}
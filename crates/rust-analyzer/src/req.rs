@@ -1,21 +1,23 @@
 //! Defines `rust-analyzer` specific custom messages.
 
-use lsp_types::{Location, Position, Range, TextDocumentIdentifier, Url};
+use lsp_types::{
+    Command, Location, Position, Range, SymbolInformation, TextDocumentIdentifier, Url,
+};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
 pub use lsp_types::{
     notification::*, request::*, ApplyWorkspaceEditParams, CodeActionParams, CodeLens,
-    CodeLensParams, CompletionParams, CompletionResponse, DiagnosticTag,
-    DidChangeConfigurationParams, DidChangeWatchedFilesParams,
-    DidChangeWatchedFilesRegistrationOptions, DocumentOnTypeFormattingParams, DocumentSymbolParams,
-    DocumentSymbolResponse, FileSystemWatcher, Hover, InitializeResult, MessageType,
-    PartialResultParams, ProgressParams, ProgressParamsValue, ProgressToken,
-    PublishDiagnosticsParams, ReferenceParams, Registration, RegistrationParams, SelectionRange,
-    SelectionRangeParams, SemanticTokensParams, SemanticTokensRangeParams,
-    SemanticTokensRangeResult, SemanticTokensResult, ServerCapabilities, ShowMessageParams,
-    SignatureHelp, SymbolKind, TextDocumentEdit, TextDocumentPositionParams, TextEdit,
-    WorkDoneProgressParams, WorkspaceEdit, WorkspaceSymbolParams,
+    CodeLensParams, CompletionParams, CompletionResponse, ConfigurationItem, ConfigurationParams,
+    DiagnosticTag, DidChangeConfigurationParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DocumentLink, DocumentLinkParams,
+    DocumentOnTypeFormattingParams, DocumentSymbolParams, DocumentSymbolResponse,
+    FileSystemWatcher, InitializeResult, MessageType, PartialResultParams, ProgressParams,
+    ProgressParamsValue, ProgressToken, PublishDiagnosticsParams, ReferenceParams, Registration,
+    RegistrationParams, RenameParams, SelectionRange, SelectionRangeParams, SemanticTokensParams,
+    SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensResult, ServerCapabilities,
+    ShowMessageParams, SignatureHelp, SymbolKind, TextDocumentEdit, TextDocumentPositionParams,
+    TextEdit, WorkDoneProgressParams, WorkspaceEdit, WorkspaceSymbolParams,
 };
 
 pub enum AnalyzerStatus {}
@@ -180,6 +182,70 @@ pub struct SourceChange {
     pub label: String,
     pub workspace_edit: WorkspaceEdit,
     pub cursor_position: Option<TextDocumentPositionParams>,
+    /// Whether the text edits use snippet syntax (`$0`, `${1:placeholder}`).
+    /// Only set if the client announced the `snippetTextEdit` capability.
+    #[serde(default)]
+    pub is_snippet: bool,
+}
+
+pub enum ResolveCodeAction {}
+
+impl Request for ResolveCodeAction {
+    type Params = ResolveCodeActionParams;
+    type Result = Option<SourceChange>;
+    const METHOD: &'static str = "rust-analyzer/resolveCodeAction";
+}
+
+/// Identifies an assist which was sent without its edit, because the client
+/// announced the `resolveCodeAction` capability.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveCodeActionParams {
+    pub code_action_params: CodeActionParams,
+    pub id: String,
+    pub label: String,
+}
+
+pub enum PrepareTypeHierarchy {}
+
+impl Request for PrepareTypeHierarchy {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "rust-analyzer/prepareTypeHierarchy";
+}
+
+pub enum TypeHierarchySupertypes {}
+
+impl Request for TypeHierarchySupertypes {
+    type Params = TypeHierarchyItemParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "rust-analyzer/typeHierarchySupertypes";
+}
+
+pub enum TypeHierarchySubtypes {}
+
+impl Request for TypeHierarchySubtypes {
+    type Params = TypeHierarchyItemParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "rust-analyzer/typeHierarchySubtypes";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItemParams {
+    pub item: TypeHierarchyItem,
+}
+
+/// A trait or type in the type hierarchy, shaped like `CallHierarchyItem`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    pub uri: Url,
+    pub range: Range,
+    pub selection_range: Range,
 }
 
 pub enum InlayHints {}
@@ -200,6 +266,9 @@ pub struct InlayHintsParams {
 pub enum InlayKind {
     TypeHint,
     ParameterHint,
+    ChainingHint,
+    ClosureReturnTypeHint,
+    LifetimeHint,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -209,6 +278,14 @@ pub struct InlayHint {
     pub label: String,
 }
 
+pub enum RenameAllowingConflicts {}
+
+impl Request for RenameAllowingConflicts {
+    type Params = RenameParams;
+    type Result = Option<WorkspaceEdit>;
+    const METHOD: &'static str = "rust-analyzer/renameAllowingConflicts";
+}
+
 pub enum Ssr {}
 
 impl Request for Ssr {
@@ -222,4 +299,69 @@ impl Request for Ssr {
 pub struct SsrParams {
     pub query: String,
     pub parse_only: bool,
+    /// Only replace the matches at these locations, as reported by `SsrMatches`
+    #[serde(default)]
+    pub selected: Option<Vec<Location>>,
+}
+
+pub enum SsrMatches {}
+
+impl Request for SsrMatches {
+    type Params = SsrMatchesParams;
+    type Result = Vec<SsrMatch>;
+    const METHOD: &'static str = "rust-analyzer/ssrMatches";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SsrMatchesParams {
+    pub query: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SsrMatch {
+    pub location: Location,
+    pub bindings: Vec<SsrBinding>,
+    pub enclosing_item: Option<SymbolInformation>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SsrBinding {
+    pub name: String,
+    pub text: String,
+}
+
+pub enum HoverRequest {}
+
+impl Request for HoverRequest {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<Hover>;
+    const METHOD: &'static str = "textDocument/hover";
+}
+
+/// A hover with the actions the client can offer next to it. Actions are only
+/// sent if the client announced the `hoverActions` capability.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Hover {
+    #[serde(flatten)]
+    pub hover: lsp_types::Hover,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<CommandLinkGroup>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct CommandLinkGroup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub commands: Vec<CommandLink>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CommandLink {
+    #[serde(flatten)]
+    pub command: Command,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
 }
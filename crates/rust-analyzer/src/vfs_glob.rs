@@ -7,6 +7,7 @@
 
 use globset::{GlobSet, GlobSetBuilder};
 use ra_vfs::{Filter, RelativePath};
+use relative_path::RelativePathBuf;
 
 pub use globset::{Glob, GlobBuilder};
 
@@ -16,11 +17,16 @@ const IGNORED_FOR_NON_MEMBERS: &[&str] = &["examples/**", "tests/**", "benches/*
 pub struct RustPackageFilterBuilder {
     is_member: bool,
     exclude: GlobSetBuilder,
+    only_files: Option<Vec<RelativePathBuf>>,
 }
 
 impl Default for RustPackageFilterBuilder {
     fn default() -> RustPackageFilterBuilder {
-        RustPackageFilterBuilder { is_member: false, exclude: GlobSetBuilder::new() }
+        RustPackageFilterBuilder {
+            is_member: false,
+            exclude: GlobSetBuilder::new(),
+            only_files: None,
+        }
     }
 }
 
@@ -33,8 +39,13 @@ impl RustPackageFilterBuilder {
         self.exclude.add(glob);
         self
     }
+    /// Includes only the given files of the root folder, and no subfolders.
+    pub fn only_files(mut self, files: Vec<RelativePathBuf>) -> RustPackageFilterBuilder {
+        self.only_files = Some(files);
+        self
+    }
     pub fn into_vfs_filter(self) -> Box<dyn Filter> {
-        let RustPackageFilterBuilder { is_member, mut exclude } = self;
+        let RustPackageFilterBuilder { is_member, mut exclude, only_files } = self;
         for &glob in ALWAYS_IGNORED {
             exclude.add(Glob::new(glob).unwrap());
         }
@@ -43,21 +54,25 @@ impl RustPackageFilterBuilder {
                 exclude.add(Glob::new(glob).unwrap());
             }
         }
-        Box::new(RustPackageFilter { exclude: exclude.build().unwrap() })
+        Box::new(RustPackageFilter { exclude: exclude.build().unwrap(), only_files })
     }
 }
 
 struct RustPackageFilter {
     exclude: GlobSet,
+    only_files: Option<Vec<RelativePathBuf>>,
 }
 
 impl Filter for RustPackageFilter {
     fn include_dir(&self, dir_path: &RelativePath) -> bool {
-        !self.exclude.is_match(dir_path.as_str())
+        self.only_files.is_none() && !self.exclude.is_match(dir_path.as_str())
     }
 
     fn include_file(&self, file_path: &RelativePath) -> bool {
-        file_path.extension() == Some("rs")
+        match &self.only_files {
+            Some(files) => files.iter().any(|it| it == file_path),
+            None => file_path.extension() == Some("rs"),
+        }
     }
 }
 
@@ -91,4 +106,13 @@ fn test_globs() {
         .into_vfs_filter();
 
     assert!(!filter.include_dir(RelativePath::new("src/llvm-project/clang")));
+
+    let filter = RustPackageFilterBuilder::default()
+        .set_member(true)
+        .only_files(vec![RelativePathBuf::from("a.rs")])
+        .into_vfs_filter();
+
+    assert!(filter.include_file(RelativePath::new("a.rs")));
+    assert!(!filter.include_file(RelativePath::new("b.rs")));
+    assert!(!filter.include_dir(RelativePath::new("src")));
 }
@@ -13,8 +13,8 @@ use lsp_types::Url;
 use parking_lot::RwLock;
 use ra_cargo_watch::{url_from_path_with_drive_lowercasing, CheckOptions, CheckWatcher};
 use ra_ide::{
-    Analysis, AnalysisChange, AnalysisHost, CrateGraph, FileId, InlayHintsOptions, LibraryData,
-    SourceRootId,
+    Analysis, AnalysisChange, AnalysisHost, AssistOptions, CrateGraph, FileId, InlayHintsOptions,
+    LibraryData, Snippet, SourceRootId,
 };
 use ra_project_model::{get_rustc_cfg_options, ProjectWorkspace};
 use ra_vfs::{LineEndings, RootEntry, Vfs, VfsChange, VfsFile, VfsRoot, VfsTask, Watch};
@@ -36,9 +36,30 @@ pub struct Options {
     pub supports_location_link: bool,
     pub line_folding_only: bool,
     pub inlay_hints: InlayHintsOptions,
+    pub assists: AssistOptions,
+    /// Whether code actions are sent without edits, to be resolved with
+    /// `rust-analyzer/resolveCodeAction` once the user picks one.
+    pub resolve_code_actions: bool,
+    /// Whether hovers carry `rust-analyzer.*` command links, see `req::Hover`.
+    pub hover_actions: bool,
     pub rustfmt_args: Vec<String>,
+    pub completion_snippets: Vec<Snippet>,
     pub cargo_watch: CheckOptions,
     pub vscode_lldb: bool,
+    pub lens: LensOptions,
+    /// Whether renames are applied even if they change which items some names
+    /// refer to.
+    pub rename_allow_conflicts: bool,
+}
+
+/// Which code lenses are shown.
+#[derive(Debug, Clone)]
+pub struct LensOptions {
+    pub run: bool,
+    pub debug: bool,
+    pub implementations: bool,
+    pub references: bool,
+    pub method_references: bool,
 }
 
 /// `WorldState` is the primary mutable state of the language server
@@ -60,6 +81,7 @@ pub struct WorldState {
     pub latest_requests: Arc<RwLock<LatestRequests>>,
     pub check_watcher: CheckWatcher,
     pub diagnostics: DiagnosticCollection,
+    extern_source_roots: FxHashMap<PathBuf, ExternSourceId>,
 }
 
 /// An immutable snapshot of the world's state at a point in time.
@@ -94,14 +116,26 @@ impl WorldState {
             RootEntry::new(path.clone(), filter.into_vfs_filter())
         }));
         for ws in workspaces.iter() {
-            roots.extend(ws.to_roots().into_iter().map(|pkg_root| {
+            for pkg_root in ws.to_roots() {
                 let mut filter =
                     RustPackageFilterBuilder::default().set_member(pkg_root.is_member());
                 for glob in exclude_globs.iter() {
                     filter = filter.exclude(glob.clone());
                 }
-                RootEntry::new(pkg_root.path().clone(), filter.into_vfs_filter())
-            }));
+                if let Some(files) = pkg_root.files() {
+                    // Files inside of the workspace folders are loaded already
+                    if folder_roots.iter().any(|it| pkg_root.path().starts_with(it)) {
+                        continue;
+                    }
+                    let files = files
+                        .iter()
+                        .filter_map(|it| it.strip_prefix(pkg_root.path()).ok())
+                        .filter_map(|it| RelativePathBuf::from_path(it).ok())
+                        .collect();
+                    filter = filter.only_files(files);
+                }
+                roots.push(RootEntry::new(pkg_root.path().clone(), filter.into_vfs_filter()));
+            }
         }
 
         let mut extern_dirs = FxHashSet::default();
@@ -119,13 +153,23 @@ impl WorldState {
             RootEntry::new(PathBuf::from(&path), filter.into_vfs_filter())
         }));
 
+        // Members of linked projects and detached files may live outside of the
+        // workspace folders, but they are still local code rather than libraries.
+        let mut local_roots = folder_roots;
+        for pkg_root in workspaces.iter().flat_map(|ws| ws.to_roots()) {
+            let path = pkg_root.path();
+            if pkg_root.is_member() && !local_roots.iter().any(|it| path.starts_with(it)) {
+                local_roots.push(path.clone());
+            }
+        }
+
         let (task_sender, task_receiver) = unbounded();
         let task_sender = Box::new(move |t| task_sender.send(t).unwrap());
         let (mut vfs, vfs_roots) = Vfs::new(roots, task_sender, watch);
         let roots_to_scan = vfs_roots.len();
         for r in vfs_roots {
             let vfs_root_path = vfs.root2path(r);
-            let is_local = folder_roots.iter().any(|it| vfs_root_path.starts_with(it));
+            let is_local = local_roots.iter().any(|it| vfs_root_path.starts_with(it));
             change.add_root(SourceRootId(r.0), is_local);
             change.set_debug_root_path(SourceRootId(r.0), vfs_root_path.display().to_string());
 
@@ -135,44 +179,13 @@ impl WorldState {
             }
         }
 
-        // FIXME: Read default cfgs from config
-        let default_cfg_options = {
-            let mut opts = get_rustc_cfg_options();
-            opts.insert_atom("test".into());
-            opts.insert_atom("debug_assertion".into());
-            opts
+        let crate_graph = {
+            let mut load = |path: &Path| vfs.load(path).map(|f| FileId(f.0));
+            build_crate_graph(&workspaces, &extern_source_roots, &mut load)
         };
-
-        // Create crate graph from all the workspaces
-        let mut crate_graph = CrateGraph::default();
-        let mut load = |path: &std::path::Path| {
-            let vfs_file = vfs.load(path);
-            vfs_file.map(|f| FileId(f.0))
-        };
-
-        workspaces
-            .iter()
-            .map(|ws| ws.to_crate_graph(&default_cfg_options, &extern_source_roots, &mut load))
-            .for_each(|graph| {
-                crate_graph.extend(graph);
-            });
         change.set_crate_graph(crate_graph);
 
-        // FIXME: Figure out the multi-workspace situation
-        let check_watcher = workspaces
-            .iter()
-            .find_map(|w| match w {
-                ProjectWorkspace::Cargo { cargo, .. } => Some(cargo),
-                ProjectWorkspace::Json { .. } => None,
-            })
-            .map(|cargo| {
-                let cargo_project_root = cargo.workspace_root().to_path_buf();
-                CheckWatcher::new(&options.cargo_watch, cargo_project_root)
-            })
-            .unwrap_or_else(|| {
-                log::warn!("Cargo check watching only supported for cargo workspaces, disabling");
-                CheckWatcher::dummy()
-            });
+        let check_watcher = create_check_watcher(&workspaces, &options.cargo_watch);
 
         let mut analysis_host = AnalysisHost::new(lru_capacity);
         analysis_host.apply_change(change);
@@ -180,7 +193,7 @@ impl WorldState {
             options,
             feature_flags: Arc::new(feature_flags),
             roots_to_scan,
-            roots: folder_roots,
+            roots: local_roots,
             workspaces: Arc::new(workspaces),
             analysis_host,
             vfs: Arc::new(RwLock::new(vfs)),
@@ -188,7 +201,47 @@ impl WorldState {
             latest_requests: Default::default(),
             check_watcher,
             diagnostics: Default::default(),
+            extern_source_roots,
+        }
+    }
+
+    /// Applies settings which changed after initialization.
+    pub fn update_configuration(
+        &mut self,
+        lru_capacity: Option<usize>,
+        options: Options,
+        feature_flags: FeatureFlags,
+    ) {
+        self.analysis_host.update_lru_capacity(lru_capacity);
+        if options.cargo_watch != self.options.cargo_watch {
+            self.check_watcher = create_check_watcher(&self.workspaces, &options.cargo_watch);
         }
+        self.options = options;
+        self.feature_flags = Arc::new(feature_flags);
+    }
+
+    /// Replaces the loaded workspaces and rebuilds the crate graph for them.
+    ///
+    /// The set of vfs roots is fixed at startup, so crates whose sources live
+    /// outside of the already known roots are skipped. Returns `false` if that
+    /// happened and a restart is needed to analyze them.
+    pub fn reload_workspaces(&mut self, workspaces: Vec<ProjectWorkspace>) -> bool {
+        let known_roots = workspace_root_paths(&self.workspaces);
+        let all_roots_known =
+            workspace_root_paths(&workspaces).iter().all(|path| known_roots.contains(path));
+
+        let crate_graph = {
+            let vfs = &self.vfs;
+            let mut load = |path: &Path| vfs.write().load(path).map(|f| FileId(f.0));
+            build_crate_graph(&workspaces, &self.extern_source_roots, &mut load)
+        };
+        let mut change = AnalysisChange::new();
+        change.set_crate_graph(crate_graph);
+        self.analysis_host.apply_change(change);
+
+        self.check_watcher = create_check_watcher(&workspaces, &self.options.cargo_watch);
+        self.workspaces = Arc::new(workspaces);
+        all_roots_known
     }
 
     /// Returns a vec of libraries
@@ -267,6 +320,58 @@ impl WorldState {
     }
 }
 
+fn build_crate_graph(
+    workspaces: &[ProjectWorkspace],
+    extern_source_roots: &FxHashMap<PathBuf, ExternSourceId>,
+    load: &mut dyn FnMut(&Path) -> Option<FileId>,
+) -> CrateGraph {
+    // FIXME: Read default cfgs from config
+    let default_cfg_options = {
+        let mut opts = get_rustc_cfg_options(None);
+        opts.insert_atom("test".into());
+        opts.insert_atom("debug_assertion".into());
+        opts
+    };
+
+    // Create crate graph from all the workspaces
+    let mut crate_graph = CrateGraph::default();
+    workspaces
+        .iter()
+        .map(|ws| ws.to_crate_graph(&default_cfg_options, extern_source_roots, load))
+        .for_each(|graph| {
+            crate_graph.extend(graph);
+        });
+    crate_graph
+}
+
+fn create_check_watcher(workspaces: &[ProjectWorkspace], options: &CheckOptions) -> CheckWatcher {
+    // FIXME: Figure out the multi-workspace situation
+    workspaces
+        .iter()
+        .find_map(|w| match w {
+            ProjectWorkspace::Cargo { cargo, .. } => Some(cargo),
+            ProjectWorkspace::Json { .. } | ProjectWorkspace::DetachedFiles { .. } => None,
+        })
+        .map(|cargo| {
+            let cargo_project_root = cargo.workspace_root().to_path_buf();
+            CheckWatcher::new(options, cargo_project_root)
+        })
+        .unwrap_or_else(|| {
+            log::warn!("Cargo check watching only supported for cargo workspaces, disabling");
+            CheckWatcher::dummy()
+        })
+}
+
+/// Paths which become vfs roots for `workspaces`.
+fn workspace_root_paths(workspaces: &[ProjectWorkspace]) -> FxHashSet<PathBuf> {
+    let mut res = FxHashSet::default();
+    for ws in workspaces {
+        res.extend(ws.to_roots().into_iter().map(|pkg_root| pkg_root.path().clone()));
+        res.extend(ws.out_dirs());
+    }
+    res
+}
+
 impl WorldSnapshot {
     pub fn analysis(&self) -> &Analysis {
         &self.analysis
@@ -280,6 +385,7 @@ impl WorldSnapshot {
             LspError {
                 code: LspError::UNKNOWN_FILE,
                 message: "Rust file outside current workspace is not supported yet.".to_string(),
+                data: None,
             }
         })?;
         Ok(FileId(file.0))
@@ -333,3 +439,26 @@ impl WorldSnapshot {
         self.workspaces.iter().find_map(|ws| ws.workspace_root_for(&path))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crate_graph_merges_workspaces() {
+        let files =
+            [PathBuf::from("/ws/a.rs"), PathBuf::from("/ws/b.rs"), PathBuf::from("/tmp/c.rs")];
+        let workspaces = vec![
+            ProjectWorkspace::load_detached_files(vec![files[0].clone()], false).unwrap(),
+            ProjectWorkspace::load_detached_files(files[1..].to_vec(), false).unwrap(),
+        ];
+
+        let mut load =
+            |path: &Path| files.iter().position(|it| it == path).map(|idx| FileId(idx as u32));
+        let crate_graph = build_crate_graph(&workspaces, &FxHashMap::default(), &mut load);
+        let mut roots: Vec<FileId> =
+            crate_graph.iter().map(|it| crate_graph[it].root_file_id).collect();
+        roots.sort();
+        assert_eq!(roots, vec![FileId(0), FileId(1), FileId(2)]);
+    }
+}
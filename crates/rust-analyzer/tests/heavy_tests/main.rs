@@ -3,13 +3,14 @@ mod support;
 use std::{collections::HashMap, time::Instant};
 
 use lsp_types::{
-    CodeActionContext, DidOpenTextDocumentParams, DocumentFormattingParams, FormattingOptions,
-    PartialResultParams, Position, Range, TextDocumentItem, TextDocumentPositionParams,
-    WorkDoneProgressParams,
+    CodeActionContext, DidChangeConfigurationParams, DidOpenTextDocumentParams,
+    DocumentFormattingParams, FormattingOptions, PartialResultParams, Position, Range,
+    RenameParams, TextDocumentItem, TextDocumentPositionParams, WorkDoneProgressParams,
 };
 use rust_analyzer::req::{
-    CodeActionParams, CodeActionRequest, Completion, CompletionParams, DidOpenTextDocument,
-    Formatting, OnEnter, Runnables, RunnablesParams,
+    CodeActionParams, CodeActionRequest, Completion, CompletionParams, DidChangeConfiguration,
+    DidOpenTextDocument, Formatting, InlayHints, InlayHintsParams, OnEnter, Rename,
+    RenameAllowingConflicts, Runnables, RunnablesParams,
 };
 use serde_json::json;
 use tempfile::TempDir;
@@ -581,3 +582,108 @@ version = \"0.0.0\"
         }),
     );
 }
+
+#[test]
+fn reads_settings_pushed_by_clients_without_configuration_support() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    // The test client doesn't support `workspace/configuration`
+    let server = project(
+        r#"
+//- Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- src/lib.rs
+fn foo() { let x = 92; }
+"#,
+    );
+    server.wait_until_workspace_is_loaded();
+    server.request::<InlayHints>(
+        InlayHintsParams { text_document: server.doc_id("src/lib.rs") },
+        json!([
+          {
+            "kind": "TypeHint",
+            "label": "i32",
+            "range": {
+              "end": { "line": 0, "character": 16 },
+              "start": { "line": 0, "character": 15 }
+            }
+          }
+        ]),
+    );
+
+    server.notification::<DidChangeConfiguration>(DidChangeConfigurationParams {
+        settings: json!({ "rust-analyzer": { "inlayHints": { "typeHints": false } } }),
+    });
+    server.request::<InlayHints>(
+        InlayHintsParams { text_document: server.doc_id("src/lib.rs") },
+        json!([]),
+    );
+}
+
+#[test]
+fn offers_to_rename_despite_conflicts() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = project(
+        r#"
+//- Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- src/lib.rs
+fn main() {
+    let x = 1;
+    let y = 2;
+    x + y;
+}
+"#,
+    );
+    server.wait_until_workspace_is_loaded();
+    let error = server.send_failing_request::<Rename>(RenameParams {
+        text_document_position: TextDocumentPositionParams::new(
+            server.doc_id("src/lib.rs"),
+            Position::new(2, 8),
+        ),
+        new_name: "x".to_string(),
+        work_done_progress_params: WorkDoneProgressParams::default(),
+    });
+    let data = error.data.expect("no follow-up command");
+    let command = &data["command"];
+    assert_eq!(command["command"], "rust-analyzer.renameAllowingConflicts");
+
+    let params = serde_json::from_value(command["arguments"][0].clone()).unwrap();
+    server.request::<RenameAllowingConflicts>(
+        params,
+        json!({
+          "documentChanges": [
+            {
+              "textDocument": "{...}",
+              "edits": [
+                {
+                  "newText": "x",
+                  "range": {
+                    "end": { "line": 2, "character": 9 },
+                    "start": { "line": 2, "character": 8 }
+                  }
+                },
+                {
+                  "newText": "x",
+                  "range": {
+                    "end": { "line": 3, "character": 9 },
+                    "start": { "line": 3, "character": 8 }
+                  }
+                }
+              ]
+            }
+          ]
+        }),
+    );
+}
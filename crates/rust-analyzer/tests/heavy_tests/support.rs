@@ -7,7 +7,7 @@ use std::{
 };
 
 use crossbeam_channel::{after, select, Receiver};
-use lsp_server::{Connection, Message, Notification, Request};
+use lsp_server::{Connection, Message, Notification, Request, Response, ResponseError};
 use lsp_types::{
     notification::{DidOpenTextDocument, Exit},
     request::Shutdown,
@@ -182,7 +182,30 @@ impl Server {
         let r = Request::new(id.into(), R::METHOD.to_string(), params);
         self.send_request_(r)
     }
+    /// Sends a request which is expected to fail and returns the error
+    pub fn send_failing_request<R>(&self, params: R::Params) -> ResponseError
+    where
+        R: lsp_types::request::Request,
+        R::Params: Serialize,
+    {
+        let id = self.req_id.get();
+        self.req_id.set(id + 1);
+
+        let r = Request::new(id.into(), R::METHOD.to_string(), params);
+        let res = self.send_request_raw(r);
+        match res.error {
+            Some(err) => err,
+            None => panic!("expected an error response, got: {:#?}", res.result),
+        }
+    }
     fn send_request_(&self, r: Request) -> Value {
+        let res = self.send_request_raw(r);
+        if let Some(err) = res.error {
+            panic!("error response: {:#?}", err);
+        }
+        res.result.unwrap()
+    }
+    fn send_request_raw(&self, r: Request) -> Response {
         let id = r.id.clone();
         self.client.sender.send(r.into()).unwrap();
         while let Some(msg) = self.recv() {
@@ -191,10 +214,7 @@ impl Server {
                 Message::Notification(_) => (),
                 Message::Response(res) => {
                     assert_eq!(res.id, id);
-                    if let Some(err) = res.error {
-                        panic!("error response: {:#?}", err);
-                    }
-                    return res.result.unwrap();
+                    return res;
                 }
             }
         }
@@ -2,6 +2,7 @@ import * as lc from 'vscode-languageclient';
 import * as vscode from 'vscode';
 
 import { Config } from './config';
+import * as ra from './rust-analyzer-api';
 import { CallHierarchyFeature } from 'vscode-languageclient/lib/callHierarchy.proposed';
 import { SemanticTokensFeature, DocumentSemanticsTokensSignature } from 'vscode-languageclient/lib/semanticTokens.proposed';
 
@@ -22,33 +23,67 @@ export async function createClient(config: Config, serverPath: string): Promise<
     const traceOutputChannel = vscode.window.createOutputChannel(
         'Rust Analyzer Language Server Trace',
     );
-    const cargoWatchOpts = config.cargoWatchOptions;
 
     const clientOptions: lc.LanguageClientOptions = {
         documentSelector: [{ scheme: 'file', language: 'rust' }],
-        initializationOptions: {
-            publishDecorations: !config.highlightingSemanticTokens,
-            lruCapacity: config.lruCapacity,
-
-            inlayHintsType: config.inlayHints.typeHints,
-            inlayHintsParameter: config.inlayHints.parameterHints,
-            inlayHintsMaxLength: config.inlayHints.maxLength,
-
-            cargoWatchEnable: cargoWatchOpts.enable,
-            cargoWatchArgs: cargoWatchOpts.arguments,
-            cargoWatchCommand: cargoWatchOpts.command,
-            cargoWatchAllTargets: cargoWatchOpts.allTargets,
-
-            excludeGlobs: config.excludeGlobs,
-            useClientWatching: config.useClientWatching,
-            featureFlags: config.featureFlags,
-            withSysroot: config.withSysroot,
-            cargoFeatures: config.cargoFeatures,
-            rustfmtArgs: config.rustfmtArgs,
-            vscodeLldb: vscode.extensions.getExtension("vadimcn.vscode-lldb") != null,
+        initializationOptions: serverConfig(config),
+        synchronize: {
+            // Only used to get notified about changes, the server then fetches
+            // the settings via `workspace/configuration`.
+            configurationSection: 'rust-analyzer',
         },
         traceOutputChannel,
         middleware: {
+            workspace: {
+                // The server expects its own settings layout rather than the raw
+                // `rust-analyzer` section of the VS Code configuration.
+                configuration(params: lc.ConfigurationParams) {
+                    return params.items.map(item => {
+                        if (item.section !== 'rust-analyzer') return null;
+                        const scope = item.scopeUri ? vscode.Uri.parse(item.scopeUri) : undefined;
+                        return serverConfig(config, scope);
+                    });
+                },
+            },
+            // The server attaches `actions` to hovers, which we render as
+            // command links below the hover text.
+            async provideHover(document: vscode.TextDocument, position: vscode.Position, token: vscode.CancellationToken, _next: lc.ProvideHoverSignature) {
+                const params = res.code2ProtocolConverter.asTextDocumentPositionParams(document, position);
+                try {
+                    const result = await res.sendRequest(lc.HoverRequest.type, params, token);
+                    const hover = res.protocol2CodeConverter.asHover(result);
+                    const actions = (result as any)?.actions as ra.CommandLinkGroup[] | undefined;
+                    if (hover && actions && actions.length > 0) {
+                        hover.contents.push(renderHoverActions(actions));
+                    }
+                    return hover;
+                } catch (error) {
+                    res.logFailedRequest(lc.HoverRequest.type, error);
+                    return null;
+                }
+            },
+            // Renames which would change the meaning of the code fail with a
+            // follow-up command in the error data, which we offer to the user.
+            async provideRenameEdits(document: vscode.TextDocument, position: vscode.Position, newName: string, token: vscode.CancellationToken, _next: lc.ProvideRenameEditsSignature) {
+                const params: lc.RenameParams = {
+                    textDocument: res.code2ProtocolConverter.asTextDocumentIdentifier(document),
+                    position: res.code2ProtocolConverter.asPosition(position),
+                    newName,
+                };
+                try {
+                    const result = await res.sendRequest(lc.RenameRequest.type, params, token);
+                    return res.protocol2CodeConverter.asWorkspaceEdit(result);
+                } catch (error) {
+                    const command = error?.data?.command as lc.Command | undefined;
+                    if (!command) throw error;
+                    vscode.window.showWarningMessage(error.message, command.title).then(selected => {
+                        if (selected === command.title) {
+                            vscode.commands.executeCommand(command.command, ...(command.arguments ?? []));
+                        }
+                    });
+                    return null;
+                }
+            },
             // Workaround for https://github.com/microsoft/vscode-languageserver-node/issues/576
             async provideDocumentSemanticTokens(document: vscode.TextDocument, token: vscode.CancellationToken, next: DocumentSemanticsTokensSignature) {
                 const res = await next(document, token);
@@ -98,6 +133,7 @@ export async function createClient(config: Config, serverPath: string): Promise<
     // Here we want to just enable CallHierarchyFeature since it is available on stable.
     // Note that while the CallHierarchyFeature is stable the LSP protocol is not.
     res.registerFeature(new CallHierarchyFeature(res));
+    res.registerFeature(new ExperimentalFeatures());
 
     if (config.highlightingSemanticTokens) {
         res.registerFeature(new SemanticTokensFeature(res));
@@ -105,3 +141,67 @@ export async function createClient(config: Config, serverPath: string): Promise<
 
     return res;
 }
+
+function renderCommand(cmd: ra.CommandLink): string {
+    const args = encodeURIComponent(JSON.stringify(cmd.arguments ?? []));
+    return `[${cmd.title}](command:${cmd.command}?${args} '${cmd.tooltip ?? cmd.command}')`;
+}
+
+function renderHoverActions(actions: ra.CommandLinkGroup[]): vscode.MarkdownString {
+    const text = actions
+        .map(group => (group.title ? `${group.title} ` : '') + group.commands.map(renderCommand).join(' | '))
+        .join(' | ');
+    const result = new vscode.MarkdownString(text);
+    result.isTrusted = true;
+    return result;
+}
+
+class ExperimentalFeatures implements lc.StaticFeature {
+    fillClientCapabilities(capabilities: lc.ClientCapabilities): void {
+        const caps: any = capabilities.experimental ?? {};
+        caps.snippetTextEdit = true;
+        caps.resolveCodeAction = true;
+        caps.hoverActions = true;
+        capabilities.experimental = caps;
+    }
+    initialize(_capabilities: lc.ServerCapabilities, _documentSelector: lc.DocumentSelector | undefined): void {
+    }
+}
+
+function serverConfig(config: Config, scope?: vscode.Uri) {
+    const cargoWatchOpts = config.cargoWatchOptions;
+
+    return {
+        publishDecorations: !config.highlightingSemanticTokens,
+        lruCapacity: config.lruCapacity,
+
+        inlayHintsType: config.inlayHints.typeHints,
+        inlayHintsParameter: config.inlayHints.parameterHints,
+        inlayHintsChaining: config.inlayHints.chainingHints,
+        inlayHintsClosureReturnType: config.inlayHints.closureReturnTypeHints,
+        inlayHintsLifetimeElision: config.inlayHints.lifetimeElisionHints,
+        inlayHintsMaxLength: config.inlayHints.maxLength,
+        lensRun: config.lens.run,
+        lensDebug: config.lens.debug,
+        lensImplementations: config.lens.implementations,
+        lensReferences: config.lens.references,
+        lensMethodReferences: config.lens.methodReferences,
+        renameAllowConflicts: config.renameAllowConflicts,
+
+        cargoWatchEnable: cargoWatchOpts.enable,
+        cargoWatchArgs: cargoWatchOpts.arguments,
+        cargoWatchCommand: cargoWatchOpts.command,
+        cargoWatchAllTargets: cargoWatchOpts.allTargets,
+
+        excludeGlobs: config.excludeGlobs,
+        useClientWatching: config.useClientWatching,
+        featureFlags: config.featureFlags,
+        withSysroot: config.withSysroot,
+        cargoFeatures: config.cargoFeatures,
+        linkedProjects: config.linkedProjects(scope),
+        detachedFiles: config.detachedFiles(scope),
+        rustfmtArgs: config.rustfmtArgs,
+        snippets: config.completionSnippets,
+        vscodeLldb: vscode.extensions.getExtension("vadimcn.vscode-lldb") != null,
+    };
+}
@@ -34,6 +34,28 @@ export function showReferences(ctx: Ctx): Cmd {
     };
 }
 
+export function gotoLocation(ctx: Ctx): Cmd {
+    return async (location: lc.Location) => {
+        const client = ctx.client;
+        if (client) {
+            const uri = client.protocol2CodeConverter.asUri(location.uri);
+            let range = client.protocol2CodeConverter.asRange(location.range);
+            // Collapse the range to a position
+            range = range.with({ end: range.start });
+            await vscode.window.showTextDocument(uri, { selection: range });
+        }
+    };
+}
+
+export function renameAllowingConflicts(ctx: Ctx): Cmd {
+    return async (params: lc.RenameParams) => {
+        const client = ctx.client;
+        const edit = await client.sendRequest(ra.renameAllowingConflicts, params);
+        if (!edit) return;
+        await vscode.workspace.applyEdit(client.protocol2CodeConverter.asWorkspaceEdit(edit));
+    };
+}
+
 export function applySourceChange(ctx: Ctx): Cmd {
     return async (change: ra.SourceChange) => {
         await sourceChange.applySourceChange(ctx, change);
@@ -51,3 +73,21 @@ export function selectAndApplySourceChange(ctx: Ctx): Cmd {
         }
     };
 }
+
+export function resolveCodeAction(ctx: Ctx): Cmd {
+    return async (params: ra.ResolveCodeActionParams) => {
+        const change = await ctx.client.sendRequest(ra.resolveCodeAction, params);
+        if (!change) return;
+        await sourceChange.applySourceChange(ctx, change);
+    };
+}
+
+export function selectAndResolveCodeAction(ctx: Ctx): Cmd {
+    return async (params: ra.ResolveCodeActionParams[]) => {
+        const selected = params.length === 1
+            ? params[0]
+            : await vscode.window.showQuickPick(params);
+        if (!selected) return;
+        await resolveCodeAction(ctx)(selected);
+    };
+}
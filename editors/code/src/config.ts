@@ -8,9 +8,20 @@ const RA_LSP_DEBUG = process.env.__RA_LSP_SERVER_DEBUG;
 export interface InlayHintOptions {
     typeHints: boolean;
     parameterHints: boolean;
+    chainingHints: boolean;
+    closureReturnTypeHints: boolean;
+    lifetimeElisionHints: boolean;
     maxLength: number | null;
 }
 
+export interface LensOptions {
+    run: boolean;
+    debug: boolean;
+    implementations: boolean;
+    references: boolean;
+    methodReferences: boolean;
+}
+
 export interface CargoWatchOptions {
     enable: boolean;
     arguments: string[];
@@ -23,6 +34,8 @@ export interface CargoFeatures {
     allFeatures: boolean;
     features: string[];
     loadOutDirsFromCheck: boolean;
+    target: null | string;
+    packageTargets: Record<string, string>;
 }
 
 export const enum UpdatesChannel {
@@ -37,10 +50,9 @@ export class Config {
     private readonly rootSection = "rust-analyzer";
     private readonly requiresReloadOpts = [
         "serverPath",
-        "cargoFeatures",
-        "cargo-watch",
+        "excludeGlobs",
+        "useClientWatching",
         "highlighting.semanticTokens",
-        "inlayHints",
     ]
         .map(opt => `${this.rootSection}.${opt}`);
 
@@ -197,13 +209,36 @@ export class Config {
         return {
             typeHints: this.cfg.get("inlayHints.typeHints") as boolean,
             parameterHints: this.cfg.get("inlayHints.parameterHints") as boolean,
+            chainingHints: this.cfg.get("inlayHints.chainingHints") as boolean,
+            closureReturnTypeHints: this.cfg.get("inlayHints.closureReturnTypeHints") as boolean,
+            lifetimeElisionHints: this.cfg.get("inlayHints.lifetimeElisionHints") as boolean,
             maxLength: this.cfg.get("inlayHints.maxLength") as null | number,
         };
     }
+    get lens(): LensOptions {
+        return {
+            run: this.cfg.get("lens.run") as boolean,
+            debug: this.cfg.get("lens.debug") as boolean,
+            implementations: this.cfg.get("lens.implementations") as boolean,
+            references: this.cfg.get("lens.references") as boolean,
+            methodReferences: this.cfg.get("lens.methodReferences") as boolean,
+        };
+    }
+    get renameAllowConflicts() { return this.cfg.get("rename.allowConflicts") as boolean; }
     get excludeGlobs() { return this.cfg.get("excludeGlobs") as string[]; }
     get useClientWatching() { return this.cfg.get("useClientWatching") as boolean; }
     get featureFlags() { return this.cfg.get("featureFlags") as Record<string, boolean>; }
     get rustfmtArgs() { return this.cfg.get("rustfmtArgs") as string[]; }
+
+    // The project paths are relative to the workspace folder they are set
+    // for, so the server asks for them with the folder as the scope.
+    linkedProjects(scope?: vscode.Uri) { return this.scopedCfg(scope).get("linkedProjects") as string[]; }
+    detachedFiles(scope?: vscode.Uri) { return this.scopedCfg(scope).get("detachedFiles") as string[]; }
+    private scopedCfg(scope?: vscode.Uri) {
+        return scope ? vscode.workspace.getConfiguration(this.rootSection, scope) : this.cfg;
+    }
+
+    get completionSnippets() { return this.cfg.get("completion.snippets") as unknown[]; }
     get loadOutDirsFromCheck() { return this.cfg.get("loadOutDirsFromCheck") as boolean; }
 
     get cargoWatchOptions(): CargoWatchOptions {
@@ -221,6 +256,8 @@ export class Config {
             allFeatures: this.cfg.get("cargoFeatures.allFeatures") as boolean,
             features: this.cfg.get("cargoFeatures.features") as string[],
             loadOutDirsFromCheck: this.cfg.get("cargoFeatures.loadOutDirsFromCheck") as boolean,
+            target: this.cfg.get("cargoFeatures.target") as null | string,
+            packageTargets: this.cfg.get("cargoFeatures.packageTargets") as Record<string, string>,
         };
     }
 
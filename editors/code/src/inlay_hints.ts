@@ -10,7 +10,8 @@ export function activateInlayHints(ctx: Ctx) {
     const maybeUpdater = {
         updater: null as null | HintsUpdater,
         onConfigChange() {
-            if (!ctx.config.inlayHints.typeHints && !ctx.config.inlayHints.parameterHints) {
+            const { typeHints, parameterHints, chainingHints, closureReturnTypeHints, lifetimeElisionHints } = ctx.config.inlayHints;
+            if (!typeHints && !parameterHints && !chainingHints && !closureReturnTypeHints && !lifetimeElisionHints) {
                 return this.dispose();
             }
             if (!this.updater) this.updater = new HintsUpdater(ctx);
@@ -63,6 +64,54 @@ const paramHints = {
     }
 };
 
+const chainingHints = {
+    decorationType: vscode.window.createTextEditorDecorationType({
+        after: {
+            color: new vscode.ThemeColor('rust_analyzer.inlayHint'),
+            fontStyle: "normal",
+        }
+    }),
+
+    toDecoration(hint: ra.InlayHint.ChainingHint, conv: lc.Protocol2CodeConverter): vscode.DecorationOptions {
+        return {
+            range: conv.asRange(hint.range),
+            renderOptions: { after: { contentText: `: ${hint.label}` } }
+        };
+    }
+};
+
+const closureReturnTypeHints = {
+    decorationType: vscode.window.createTextEditorDecorationType({
+        after: {
+            color: new vscode.ThemeColor('rust_analyzer.inlayHint'),
+            fontStyle: "normal",
+        }
+    }),
+
+    toDecoration(hint: ra.InlayHint.ClosureReturnTypeHint, conv: lc.Protocol2CodeConverter): vscode.DecorationOptions {
+        return {
+            range: conv.asRange(hint.range),
+            renderOptions: { after: { contentText: ` -> ${hint.label}` } }
+        };
+    }
+};
+
+const lifetimeHints = {
+    decorationType: vscode.window.createTextEditorDecorationType({
+        after: {
+            color: new vscode.ThemeColor('rust_analyzer.inlayHint'),
+            fontStyle: "normal",
+        }
+    }),
+
+    toDecoration(hint: ra.InlayHint.LifetimeHint, conv: lc.Protocol2CodeConverter): vscode.DecorationOptions {
+        return {
+            range: conv.asRange(hint.range),
+            renderOptions: { after: { contentText: hint.label } }
+        };
+    }
+};
+
 class HintsUpdater implements Disposable {
     private sourceFiles = new Map<string, RustSourceFile>(); // map Uri -> RustSourceFile
     private readonly disposables: Disposable[] = [];
@@ -95,7 +144,7 @@ class HintsUpdater implements Disposable {
 
     dispose() {
         this.sourceFiles.forEach(file => file.inlaysRequest?.cancel());
-        this.ctx.visibleRustEditors.forEach(editor => this.renderDecorations(editor, { param: [], type: [] }));
+        this.ctx.visibleRustEditors.forEach(editor => this.renderDecorations(editor, { param: [], type: [], chaining: [], closureReturnType: [], lifetime: [] }));
         this.disposables.forEach(d => d.dispose());
     }
 
@@ -156,10 +205,13 @@ class HintsUpdater implements Disposable {
     private renderDecorations(editor: RustEditor, decorations: InlaysDecorations) {
         editor.setDecorations(typeHints.decorationType, decorations.type);
         editor.setDecorations(paramHints.decorationType, decorations.param);
+        editor.setDecorations(chainingHints.decorationType, decorations.chaining);
+        editor.setDecorations(closureReturnTypeHints.decorationType, decorations.closureReturnType);
+        editor.setDecorations(lifetimeHints.decorationType, decorations.lifetime);
     }
 
     private hintsToDecorations(hints: ra.InlayHint[]): InlaysDecorations {
-        const decorations: InlaysDecorations = { type: [], param: [] };
+        const decorations: InlaysDecorations = { type: [], param: [], chaining: [], closureReturnType: [], lifetime: [] };
         const conv = this.ctx.client.protocol2CodeConverter;
 
         for (const hint of hints) {
@@ -172,6 +224,18 @@ class HintsUpdater implements Disposable {
                     decorations.param.push(paramHints.toDecoration(hint, conv));
                     continue;
                 }
+                case ra.InlayHint.Kind.ChainingHint: {
+                    decorations.chaining.push(chainingHints.toDecoration(hint, conv));
+                    continue;
+                }
+                case ra.InlayHint.Kind.ClosureReturnTypeHint: {
+                    decorations.closureReturnType.push(closureReturnTypeHints.toDecoration(hint, conv));
+                    continue;
+                }
+                case ra.InlayHint.Kind.LifetimeHint: {
+                    decorations.lifetime.push(lifetimeHints.toDecoration(hint, conv));
+                    continue;
+                }
             }
         }
         return decorations;
@@ -198,6 +262,9 @@ class HintsUpdater implements Disposable {
 interface InlaysDecorations {
     type: vscode.DecorationOptions[];
     param: vscode.DecorationOptions[];
+    chaining: vscode.DecorationOptions[];
+    closureReturnType: vscode.DecorationOptions[];
+    lifetime: vscode.DecorationOptions[];
 }
 
 interface RustSourceFile {
@@ -94,8 +94,12 @@ export async function activate(context: vscode.ExtensionContext) {
     ctx.registerCommand('runSingle', commands.runSingle);
     ctx.registerCommand('debugSingle', commands.debugSingle);
     ctx.registerCommand('showReferences', commands.showReferences);
+    ctx.registerCommand('gotoLocation', commands.gotoLocation);
+    ctx.registerCommand('renameAllowingConflicts', commands.renameAllowingConflicts);
     ctx.registerCommand('applySourceChange', commands.applySourceChange);
     ctx.registerCommand('selectAndApplySourceChange', commands.selectAndApplySourceChange);
+    ctx.registerCommand('resolveCodeAction', commands.resolveCodeAction);
+    ctx.registerCommand('selectAndResolveCodeAction', commands.selectAndResolveCodeAction);
 
     activateStatusDisplay(ctx);
 
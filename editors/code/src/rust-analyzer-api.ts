@@ -86,12 +86,20 @@ export interface Runnable {
 }
 export const runnables = request<RunnablesParams, Vec<Runnable>>("runnables");
 
-export type InlayHint = InlayHint.TypeHint | InlayHint.ParamHint;
+export type InlayHint =
+    | InlayHint.TypeHint
+    | InlayHint.ParamHint
+    | InlayHint.ChainingHint
+    | InlayHint.ClosureReturnTypeHint
+    | InlayHint.LifetimeHint;
 
 export namespace InlayHint {
     export const enum Kind {
         TypeHint = "TypeHint",
         ParamHint = "ParameterHint",
+        ChainingHint = "ChainingHint",
+        ClosureReturnTypeHint = "ClosureReturnTypeHint",
+        LifetimeHint = "LifetimeHint",
     }
     interface Common {
         range: lc.Range;
@@ -99,6 +107,9 @@ export namespace InlayHint {
     }
     export type TypeHint = Common & { kind: Kind.TypeHint };
     export type ParamHint = Common & { kind: Kind.ParamHint };
+    export type ChainingHint = Common & { kind: Kind.ChainingHint };
+    export type ClosureReturnTypeHint = Common & { kind: Kind.ClosureReturnTypeHint };
+    export type LifetimeHint = Common & { kind: Kind.LifetimeHint };
 }
 export interface InlayHintsParams {
     textDocument: lc.TextDocumentIdentifier;
@@ -106,12 +117,46 @@ export interface InlayHintsParams {
 export const inlayHints = request<InlayHintsParams, Vec<InlayHint>>("inlayHints");
 
 
+export interface TypeHierarchyItem {
+    name: string;
+    kind: lc.SymbolKind;
+    detail: Option<string>;
+    uri: string;
+    range: lc.Range;
+    selectionRange: lc.Range;
+}
+export interface TypeHierarchyItemParams {
+    item: TypeHierarchyItem;
+}
+export const prepareTypeHierarchy = request<lc.TextDocumentPositionParams, Option<Vec<TypeHierarchyItem>>>("prepareTypeHierarchy");
+export const typeHierarchySupertypes = request<TypeHierarchyItemParams, Option<Vec<TypeHierarchyItem>>>("typeHierarchySupertypes");
+export const typeHierarchySubtypes = request<TypeHierarchyItemParams, Option<Vec<TypeHierarchyItem>>>("typeHierarchySubtypes");
+
+
+export const renameAllowingConflicts = request<lc.RenameParams, Option<lc.WorkspaceEdit>>("renameAllowingConflicts");
+
+
 export interface SsrParams {
     query: string;
     parseOnly: boolean;
+    selected?: Vec<lc.Location>;
 }
 export const ssr = request<SsrParams, SourceChange>("ssr");
 
+export interface SsrMatchesParams {
+    query: string;
+}
+export interface SsrBinding {
+    name: string;
+    text: string;
+}
+export interface SsrMatch {
+    location: lc.Location;
+    bindings: Vec<SsrBinding>;
+    enclosingItem: Option<lc.SymbolInformation>;
+}
+export const ssrMatches = request<SsrMatchesParams, Vec<SsrMatch>>("ssrMatches");
+
 
 export const publishDecorations = notification<PublishDecorationsParams>("publishDecorations");
 
@@ -120,4 +165,24 @@ export interface SourceChange {
     label: string;
     workspaceEdit: lc.WorkspaceEdit;
     cursorPosition: Option<lc.TextDocumentPositionParams>;
+    isSnippet?: boolean;
+}
+
+export interface ResolveCodeActionParams {
+    codeActionParams: lc.CodeActionParams;
+    id: string;
+    label: string;
+}
+export const resolveCodeAction = request<ResolveCodeActionParams, Option<SourceChange>>("resolveCodeAction");
+
+
+export interface CommandLink extends lc.Command {
+    /**
+     * A tooltip for the command, when represented in the UI.
+     */
+    tooltip?: string;
+}
+export interface CommandLinkGroup {
+    title?: string;
+    commands: CommandLink[];
 }
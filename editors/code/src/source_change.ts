@@ -8,6 +8,9 @@ export async function applySourceChange(ctx: Ctx, change: ra.SourceChange) {
     const client = ctx.client;
     if (!client) return;
 
+    const snippetSelection = change.isSnippet
+        ? stripSnippets(client, change.workspaceEdit)
+        : undefined;
     const wsEdit = client.protocol2CodeConverter.asWorkspaceEdit(
         change.workspaceEdit,
     );
@@ -25,7 +28,17 @@ export async function applySourceChange(ctx: Ctx, change: ra.SourceChange) {
     const toOpen = created || moved;
     const toReveal = change.cursorPosition;
     await vscode.workspace.applyEdit(wsEdit);
-    if (toOpen) {
+    if (snippetSelection) {
+        const editor = vscode.window.activeTextEditor;
+        if (!editor) return;
+        const doc = editor.document;
+        const selection = new vscode.Selection(
+            doc.positionAt(snippetSelection.start),
+            doc.positionAt(snippetSelection.end),
+        );
+        editor.selection = selection;
+        editor.revealRange(selection, vscode.TextEditorRevealType.Default);
+    } else if (toOpen) {
         const toOpenUri = vscode.Uri.parse(toOpen);
         const doc = await vscode.workspace.openTextDocument(toOpenUri);
         await vscode.window.showTextDocument(doc);
@@ -50,3 +63,92 @@ export async function applySourceChange(ctx: Ctx, change: ra.SourceChange) {
         );
     }
 }
+
+interface TabStop {
+    index: number;
+    start: number;
+    end: number;
+}
+
+/**
+ * Replaces the snippets in the text edits of `wsEdit` with plain text.
+ *
+ * VS Code can only insert snippets into a single place at a time and
+ * re-indents them, so instead of inserting them as snippets we select the
+ * first tab stop in the active editor. Returns its offsets in the edited
+ * document, if any.
+ */
+function stripSnippets(
+    client: lc.LanguageClient,
+    wsEdit: lc.WorkspaceEdit,
+): { start: number; end: number } | undefined {
+    const editor = vscode.window.activeTextEditor;
+    let res: { start: number; end: number } | undefined;
+
+    for (const docChange of wsEdit.documentChanges ?? []) {
+        if (!lc.TextDocumentEdit.is(docChange)) continue;
+
+        const uri = client.protocol2CodeConverter.asUri(docChange.textDocument.uri);
+        const doc = editor && editor.document.uri.toString() === uri.toString()
+            ? editor.document
+            : undefined;
+
+        const offsetOf = (pos: lc.Position) =>
+            doc ? doc.offsetAt(client.protocol2CodeConverter.asPosition(pos)) : 0;
+        const edits = [...docChange.edits].sort(
+            (a, b) => offsetOf(a.range.start) - offsetOf(b.range.start)
+        );
+
+        // How much the preceding edits shifted the current one.
+        let delta = 0;
+        for (const edit of edits) {
+            const [text, tabStops] = parseSnippet(edit.newText);
+            const start = offsetOf(edit.range.start);
+            const end = offsetOf(edit.range.end);
+            edit.newText = text;
+
+            const first = tabStops
+                .filter(it => it.index !== 0)
+                .sort((a, b) => a.index - b.index)[0] ?? tabStops.find(it => it.index === 0);
+            if (doc && first && !res) {
+                res = { start: start + delta + first.start, end: start + delta + first.end };
+            }
+            delta += text.length - (end - start);
+        }
+    }
+    return res;
+}
+
+/**
+ * Turns a snippet into plain text, keeping placeholders and dropping tab
+ * stops (`$0`, `${1:placeholder}`). Returns the text and the tab stop ranges.
+ */
+function parseSnippet(snippet: string): [string, TabStop[]] {
+    let text = '';
+    const tabStops: TabStop[] = [];
+    let i = 0;
+    while (i < snippet.length) {
+        const c = snippet[i++];
+        if (c === '\\') {
+            if (i < snippet.length) text += snippet[i++];
+            continue;
+        }
+        const tabStop = c === '$' ? /^(\{)?(\d+)/.exec(snippet.slice(i)) : null;
+        if (!tabStop) {
+            text += c;
+            continue;
+        }
+        i += tabStop[0].length;
+        const start = text.length;
+        if (tabStop[1]) {
+            if (snippet[i] === ':') i++;
+            while (i < snippet.length && snippet[i] !== '}') {
+                if (snippet[i] === '\\') i++;
+                if (i < snippet.length) text += snippet[i++];
+            }
+            i++; // closing `}`
+        }
+        tabStops.push({ index: Number(tabStop[2]), start, end: text.length });
+    }
+    return [text, tabStops];
+}